@@ -0,0 +1,209 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::term::BlankNode;
+
+// Each generator has its own namespace, a first letter no other generator uses: `b` for the
+// counter, `s` for seeded, `h` for hashed and `l_` for preserved labels. Labels from documents
+// only reach the store through `Preserve`, so they never occupy the namespace of generated identifiers.
+
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+// Distinguishes the identifiers of this run of the store from those of earlier runs
+static RUN: OnceLock<String> = OnceLock::new();
+
+fn run() -> &'static str {
+    RUN.get_or_init(|| {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|since| since.as_nanos()).unwrap_or(0);
+        format!("{:x}{:x}", nanos, std::process::id())
+    })
+}
+
+/// Produces identifiers for blank nodes.
+/// `label` is the label the node had in its source document, if any.
+pub trait BlankNodeIdGenerator: Send {
+    fn generate(&mut self, label: Option<&str>) -> String;
+}
+
+/// Returns the next value of the store-wide monotonic counter, as `b<run>_<count>`. The run part
+/// differs between restarts, so identifiers stay unique when the counter starts over.
+pub fn next_id() -> String {
+    format!("b{}_{}", run(), NEXT_ID.fetch_add(1, Ordering::SeqCst))
+}
+
+/// Store-wide monotonic counter, shared by every instance and by `BlankNode::new`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Counter;
+
+impl BlankNodeIdGenerator for Counter {
+    fn generate(&mut self, _label: Option<&str>) -> String {
+        next_id()
+    }
+}
+
+/// Keeps labels from source documents recognizable, as `l_<label>`, and falls back to the
+/// store-wide counter. Documents read this way share nodes with the same label.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Preserve;
+
+impl BlankNodeIdGenerator for Preserve {
+    fn generate(&mut self, label: Option<&str>) -> String {
+        match label {
+            Some(label) => format!("l_{}", label),
+            None => next_id(),
+        }
+    }
+}
+
+/// Deterministic pseudo-random identifiers (splitmix64), for reproducible fixtures.
+#[derive(Debug, Clone)]
+pub struct Seeded {
+    state: u64,
+}
+
+impl Seeded {
+    pub fn new(seed: u64) -> Seeded {
+        Seeded { state: seed }
+    }
+}
+
+impl BlankNodeIdGenerator for Seeded {
+    fn generate(&mut self, _label: Option<&str>) -> String {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        format!("s{:016x}", z ^ (z >> 31))
+    }
+}
+
+/// FNV-1a 64-bit hash of `content` as a blank node label.
+pub fn hash_label(content: &[u8]) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in content {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    format!("h{:016x}", hash)
+}
+
+/// Labels derived from content: the hash of `salt` (e.g. the source document)
+/// followed by the node's label, or by a running count for unlabeled nodes.
+#[derive(Debug, Clone)]
+pub struct Hashed {
+    salt: Vec<u8>,
+    count: u64,
+}
+
+impl Hashed {
+    pub fn new<S>(salt: S) -> Hashed
+    where
+        S: Into<Vec<u8>>,
+    {
+        Hashed {
+            salt: salt.into(),
+            count: 0,
+        }
+    }
+}
+
+impl BlankNodeIdGenerator for Hashed {
+    fn generate(&mut self, label: Option<&str>) -> String {
+        let mut content = self.salt.clone();
+        content.push(0);
+        match label {
+            Some(label) => content.extend(label.as_bytes()),
+            None => {
+                self.count += 1;
+                content.extend(self.count.to_string().as_bytes());
+            }
+        }
+        hash_label(&content)
+    }
+}
+
+/// Maps the blank node labels of a single document to generated identifiers,
+/// so every occurrence of a label resolves to the same node.
+pub struct BlankNodeScope {
+    generator: Box<dyn BlankNodeIdGenerator>,
+    labels: HashMap<String, String>,
+}
+
+impl BlankNodeScope {
+    pub fn new<G>(generator: G) -> BlankNodeScope
+    where
+        G: BlankNodeIdGenerator + 'static,
+    {
+        BlankNodeScope {
+            generator: Box::new(generator),
+            labels: HashMap::new(),
+        }
+    }
+
+    pub fn labeled(&mut self, label: &str) -> BlankNode {
+        if let Some(value) = self.labels.get(label) {
+            return BlankNode::from_value(value.as_str());
+        }
+        let value = self.generator.generate(Some(label));
+        self.labels.insert(label.to_owned(), value.clone());
+        BlankNode::from_value(value)
+    }
+
+    pub fn fresh(&mut self) -> BlankNode {
+        BlankNode::from_value(self.generator.generate(None))
+    }
+}
+
+// Every document gets fresh nodes
+impl Default for BlankNodeScope {
+    fn default() -> BlankNodeScope {
+        BlankNodeScope::new(Counter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::blank_node_id::{BlankNodeIdGenerator, BlankNodeScope, Counter, Hashed, Preserve, Seeded};
+
+    #[test]
+    fn test_seeded_is_reproducible() {
+        let mut first = Seeded::new(42);
+        let mut second = Seeded::new(42);
+        let a: Vec<String> = (0..3).map(|_| first.generate(None)).collect();
+        let b: Vec<String> = (0..3).map(|_| second.generate(None)).collect();
+        assert_eq!(a, b);
+        assert_ne!(a[0], a[1]);
+    }
+
+    #[test]
+    fn test_counter_is_monotonic() {
+        let mut counter = Counter;
+        let count = |id: String| -> usize { id.rsplit('_').next().unwrap().parse().unwrap() };
+        let a = count(counter.generate(None));
+        let b = count(counter.generate(None));
+        assert!(b > a);
+    }
+
+    #[test]
+    fn test_labels_do_not_collide() {
+        let mut counter = Counter;
+        let generated = counter.generate(None);
+        let mut default = BlankNodeScope::default();
+        let mut other = BlankNodeScope::default();
+        assert_ne!(default.labeled(&generated).value, generated);
+        assert_ne!(default.labeled("b0"), other.labeled("b0"));
+        let mut preserve = BlankNodeScope::new(Preserve);
+        assert_eq!(preserve.labeled(&generated).value, format!("l_{}", generated));
+    }
+
+    #[test]
+    fn test_scope_and_hashed() {
+        let mut scope = BlankNodeScope::new(Hashed::new("document"));
+        let first = scope.labeled("x");
+        assert_eq!(first, scope.labeled("x"));
+        assert_ne!(first, scope.labeled("y"));
+        let mut other = BlankNodeScope::new(Hashed::new("document"));
+        assert_eq!(first, other.labeled("x"));
+    }
+}
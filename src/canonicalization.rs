@@ -0,0 +1,348 @@
+use crate::nquads_serialize::serialize_quad;
+use crate::quad::Quad;
+use crate::term::{BlankNode, Identifier, Node};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+// RDF Dataset Canonicalization (RDFC-1.0, https://www.w3.org/TR/rdf-canon/) with SHA-256. Blank
+// nodes are relabelled c14n0, c14n1, ... from the hashes of the quads around them, so isomorphic
+// datasets come out identical whatever labels they were read with.
+
+// Hash N-Degree Quads calls and permutations tried before giving up, so that poison datasets
+// (many indistinguishable blank nodes) fail instead of running practically for ever
+const MAX_WORK: usize = 100_000;
+const TOO_MUCH_WORK: &str = "Too many indistinguishable blank nodes to canonicalize";
+
+#[derive(Clone)]
+struct IdentifierIssuer {
+    prefix: &'static str,
+    // Existing identifiers in the order they were issued
+    issued: Vec<String>,
+    map: HashMap<String, String>,
+}
+
+impl IdentifierIssuer {
+    fn new(prefix: &'static str) -> IdentifierIssuer {
+        IdentifierIssuer { prefix, issued: vec![], map: HashMap::new() }
+    }
+
+    fn get(&self, existing: &str) -> Option<&String> {
+        self.map.get(existing)
+    }
+
+    fn issue(&mut self, existing: &str) -> String {
+        if let Some(issued) = self.map.get(existing) {
+            return issued.clone();
+        }
+        let issued = format!("{}{}", self.prefix, self.issued.len());
+        self.issued.push(existing.to_owned());
+        self.map.insert(existing.to_owned(), issued.clone());
+        issued
+    }
+}
+
+fn sha256(data: &str) -> String {
+    Sha256::digest(data.as_bytes()).iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+// The blank nodes of a quad with their positions: s, o or g
+fn blank_nodes(quad: &Quad) -> Vec<(char, &str)> {
+    let mut blank_nodes = vec![];
+    if let Identifier::BlankNode(blank_node) = &quad.subject {
+        blank_nodes.push(('s', blank_node.value.as_str()));
+    }
+    if let Node::BlankNode(blank_node) = &quad.object {
+        blank_nodes.push(('o', blank_node.value.as_str()));
+    }
+    if let Some(Identifier::BlankNode(blank_node)) = &quad.context {
+        blank_nodes.push(('g', blank_node.value.as_str()));
+    }
+    blank_nodes
+}
+
+fn relabel(quad: &Quad, label: impl Fn(&str) -> String) -> Quad {
+    let identifier = |identifier: &Identifier| match identifier {
+        Identifier::BlankNode(blank_node) => Identifier::BlankNode(BlankNode::from_value(label(&blank_node.value))),
+        identifier => identifier.clone(),
+    };
+    Quad {
+        subject: identifier(&quad.subject),
+        predicate: quad.predicate.clone(),
+        object: match &quad.object {
+            Node::BlankNode(blank_node) => Node::BlankNode(BlankNode::from_value(label(&blank_node.value))),
+            node => node.clone(),
+        },
+        context: quad.context.as_ref().map(identifier),
+    }
+}
+
+fn permutations(items: &[String]) -> Vec<Vec<String>> {
+    if items.len() <= 1 {
+        return vec![items.to_vec()];
+    }
+    let mut permutations = vec![];
+    for index in 0..items.len() {
+        let mut rest = items.to_vec();
+        let first = rest.remove(index);
+        for mut permutation in self::permutations(&rest) {
+            permutation.insert(0, first.clone());
+            permutations.push(permutation);
+        }
+    }
+    permutations
+}
+
+struct Canonicalizer<'a> {
+    quads: &'a [Quad],
+    // Indices of the quads each blank node appears in
+    mentions: HashMap<&'a str, Vec<usize>>,
+    first_degree: HashMap<&'a str, String>,
+    canonical: IdentifierIssuer,
+    work: usize,
+}
+
+impl<'a> Canonicalizer<'a> {
+    fn new(quads: &'a [Quad]) -> Canonicalizer<'a> {
+        let mut mentions: HashMap<&str, Vec<usize>> = HashMap::new();
+        for (index, quad) in quads.iter().enumerate() {
+            for (_, blank_node) in blank_nodes(quad) {
+                let indices = mentions.entry(blank_node).or_default();
+                if indices.last() != Some(&index) {
+                    indices.push(index);
+                }
+            }
+        }
+        let mut canonicalizer = Canonicalizer {
+            quads,
+            mentions,
+            first_degree: HashMap::new(),
+            canonical: IdentifierIssuer::new("c14n"),
+            work: 0,
+        };
+        let first_degree = canonicalizer.mentions.keys().map(|&blank_node| (blank_node, canonicalizer.hash_first_degree(blank_node))).collect();
+        canonicalizer.first_degree = first_degree;
+        canonicalizer
+    }
+
+    fn hash_first_degree(&self, reference: &str) -> String {
+        let mut lines: Vec<String> = self.mentions[reference]
+            .iter()
+            .map(|&index| {
+                let quad = relabel(&self.quads[index], |blank_node| if blank_node == reference { "a" } else { "z" }.to_owned());
+                serialize_quad(quad) + "\n"
+            })
+            .collect();
+        lines.sort();
+        sha256(&lines.concat())
+    }
+
+    fn hash_related(&self, related: &str, quad: &Quad, issuer: &IdentifierIssuer, position: char) -> String {
+        let mut input = position.to_string();
+        if position != 'g' {
+            input.push_str(&format!("<{}>", quad.predicate.value));
+        }
+        match self.canonical.get(related).or_else(|| issuer.get(related)) {
+            Some(issued) => input.push_str(&format!("_:{}", issued)),
+            None => input.push_str(&self.first_degree[related]),
+        }
+        sha256(&input)
+    }
+
+    fn spend(&mut self) -> Result<(), String> {
+        self.work += 1;
+        if self.work > MAX_WORK {
+            return Err(TOO_MUCH_WORK.to_owned());
+        }
+        Ok(())
+    }
+
+    fn hash_n_degree(&mut self, reference: &str, mut issuer: IdentifierIssuer) -> Result<(String, IdentifierIssuer), String> {
+        self.spend()?;
+        let mut related_hashes: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for &index in &self.mentions[reference] {
+            let quad = &self.quads[index];
+            for (position, related) in blank_nodes(quad) {
+                if related != reference {
+                    let hash = self.hash_related(related, quad, &issuer, position);
+                    related_hashes.entry(hash).or_default().push(related.to_owned());
+                }
+            }
+        }
+        let mut data = String::new();
+        for (hash, related) in related_hashes {
+            data.push_str(&hash);
+            let mut chosen_path = String::new();
+            let mut chosen_issuer = None;
+            let count = (1..=related.len()).try_fold(1usize, |count, n| count.checked_mul(n));
+            if count.is_none_or(|count| self.work + count > MAX_WORK) {
+                return Err(TOO_MUCH_WORK.to_owned());
+            }
+            for permutation in permutations(&related) {
+                self.spend()?;
+                let mut issuer_copy = issuer.clone();
+                let mut path = String::new();
+                let mut recursion = vec![];
+                // A path longer than the chosen one can only sort after it
+                let worse = |path: &str, chosen_path: &str| !chosen_path.is_empty() && path.len() >= chosen_path.len() && path > chosen_path;
+                let mut skipped = false;
+                for related in &permutation {
+                    match self.canonical.get(related) {
+                        Some(issued) => path.push_str(&format!("_:{}", issued)),
+                        None => {
+                            if issuer_copy.get(related).is_none() {
+                                recursion.push(related.clone());
+                            }
+                            path.push_str(&format!("_:{}", issuer_copy.issue(related)));
+                        }
+                    }
+                    if worse(&path, &chosen_path) {
+                        skipped = true;
+                        break;
+                    }
+                }
+                if skipped {
+                    continue;
+                }
+                for related in recursion {
+                    let (hash, result_issuer) = self.hash_n_degree(&related, issuer_copy.clone())?;
+                    path.push_str(&format!("_:{}<{}>", issuer_copy.issue(&related), hash));
+                    issuer_copy = result_issuer;
+                    if worse(&path, &chosen_path) {
+                        skipped = true;
+                        break;
+                    }
+                }
+                if skipped {
+                    continue;
+                }
+                if chosen_path.is_empty() || path < chosen_path {
+                    chosen_path = path;
+                    chosen_issuer = Some(issuer_copy);
+                }
+            }
+            data.push_str(&chosen_path);
+            // The first permutation is never skipped
+            issuer = chosen_issuer.unwrap();
+        }
+        Ok((sha256(&data), issuer))
+    }
+
+    fn issue_canonical(&mut self) -> Result<(), String> {
+        let mut by_hash: BTreeMap<String, Vec<&'a str>> = BTreeMap::new();
+        for (&blank_node, hash) in &self.first_degree {
+            by_hash.entry(hash.clone()).or_default().push(blank_node);
+        }
+        let (unique, shared): (Vec<_>, Vec<_>) = by_hash.into_iter().partition(|(_, blank_nodes)| blank_nodes.len() == 1);
+        for (_, blank_nodes) in unique {
+            self.canonical.issue(blank_nodes[0]);
+        }
+        for (_, blank_nodes) in shared {
+            let mut paths = vec![];
+            for blank_node in blank_nodes {
+                if self.canonical.get(blank_node).is_some() {
+                    continue;
+                }
+                let mut issuer = IdentifierIssuer::new("b");
+                issuer.issue(blank_node);
+                paths.push(self.hash_n_degree(blank_node, issuer)?);
+            }
+            paths.sort_by(|(a, _), (b, _)| a.cmp(b));
+            for (_, issuer) in paths {
+                for existing in &issuer.issued {
+                    self.canonical.issue(existing);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Relabels the blank nodes of `quads` with their RDFC-1.0 canonical identifiers. Duplicate quads
+/// are dropped. Fails when the dataset needs more work to canonicalize than is reasonable.
+pub fn canonicalize(quads: impl IntoIterator<Item = Quad>) -> Result<Vec<Quad>, String> {
+    let mut seen = HashSet::new();
+    let quads: Vec<Quad> = quads.into_iter().filter(|quad| seen.insert(quad.clone())).collect();
+    let mut canonicalizer = Canonicalizer::new(&quads);
+    canonicalizer.issue_canonical()?;
+    let canonical = &canonicalizer.canonical;
+    Ok(quads.iter().map(|quad| relabel(quad, |blank_node| canonical.map[blank_node].clone())).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::canonicalization::canonicalize;
+    use crate::nquads_deserialize::deserialize;
+    use crate::nquads_serialize::serialize_quad;
+    use crate::quad::Quad;
+    use crate::term::{BlankNode, IRI};
+
+    fn canonical(nquads: &str) -> Vec<String> {
+        let quads: Vec<Quad> = deserialize(nquads).map(|quad| quad.unwrap()).collect();
+        let mut lines: Vec<String> = canonicalize(quads).unwrap().into_iter().map(serialize_quad).collect();
+        lines.sort();
+        lines
+    }
+
+    #[test]
+    fn test_canonicalize() {
+        // Example 2 of the specification: unique first degree hashes
+        let expected = vec![
+            "<http://example.com/#p> <http://example.com/#q> _:c14n0 .",
+            "<http://example.com/#p> <http://example.com/#r> _:c14n1 .",
+            "_:c14n0 <http://example.com/#s> <http://example.com/#u> .",
+            "_:c14n1 <http://example.com/#t> <http://example.com/#u> .",
+        ];
+        let input = "<http://example.com/#p> <http://example.com/#q> _:e0 .
+<http://example.com/#p> <http://example.com/#r> _:e1 .
+_:e0 <http://example.com/#s> <http://example.com/#u> .
+_:e1 <http://example.com/#t> <http://example.com/#u> .
+";
+        assert_eq!(canonical(input), expected);
+        let relabelled = "_:y <http://example.com/#t> <http://example.com/#u> .
+<http://example.com/#p> <http://example.com/#r> _:y .
+_:x <http://example.com/#s> <http://example.com/#u> .
+<http://example.com/#p> <http://example.com/#q> _:x .
+";
+        assert_eq!(canonical(relabelled), expected);
+    }
+
+    #[test]
+    fn test_canonicalize_shared_hashes() {
+        // Example 3 of the specification: _:e0 and _:e1 share a first degree hash
+        let input = "<http://example.com/#p> <http://example.com/#q> _:e0 .
+<http://example.com/#p> <http://example.com/#q> _:e1 .
+_:e0 <http://example.com/#p> _:e2 .
+_:e1 <http://example.com/#p> _:e3 .
+_:e2 <http://example.com/#r> _:e3 .
+";
+        let expected = vec![
+            "<http://example.com/#p> <http://example.com/#q> _:c14n2 .",
+            "<http://example.com/#p> <http://example.com/#q> _:c14n3 .",
+            "_:c14n0 <http://example.com/#r> _:c14n1 .",
+            "_:c14n2 <http://example.com/#p> _:c14n1 .",
+            "_:c14n3 <http://example.com/#p> _:c14n0 .",
+        ];
+        assert_eq!(canonical(input), expected);
+        // Two indistinguishable cycles
+        let cycles = "_:a <http://example.com/#p> _:b .
+_:b <http://example.com/#p> _:c .
+_:c <http://example.com/#p> _:a .
+_:d <http://example.com/#p> _:e .
+_:e <http://example.com/#p> _:f .
+_:f <http://example.com/#p> _:d .
+";
+        let relabelled = cycles.replace("_:a", "_:x").replace("_:e", "_:a").replace("_:x", "_:e");
+        assert_eq!(canonical(cycles), canonical(&relabelled));
+        assert!(canonical(cycles).iter().all(|line| line.starts_with("_:c14n")));
+        // A clique of blank nodes is a poison dataset
+        let mut clique = vec![];
+        for a in 0..10 {
+            for b in 0..10 {
+                if a != b {
+                    clique.push(Quad::new(BlankNode::from_value(a.to_string()), IRI::new("http://example.com/#p"), BlankNode::from_value(b.to_string()), None));
+                }
+            }
+        }
+        assert!(canonicalize(clique).is_err());
+    }
+}
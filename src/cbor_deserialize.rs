@@ -0,0 +1,165 @@
+use crate::blank_node_id::BlankNodeScope;
+use crate::cbor_serialize::{Codes, CBOR_LD_TAG};
+use crate::jsonld_deserialize::{expand, to_rdf, ContextRegistry};
+use crate::parse_error::{ParseError, ParseErrorKind};
+use crate::quad::Quad;
+use crate::term::{Identifier, Node};
+use serde_cbor::Value as Cbor;
+use serde_json::{Map, Number, Value};
+use std::convert::TryFrom;
+
+// CBOR input, see `cbor_serialize` for the formats.
+
+fn unexpected(message: String) -> ParseError {
+    ParseError::new(ParseErrorKind::UnexpectedValue(message), 0, 0, 0, String::new())
+}
+
+fn unknown_code(code: i128) -> ParseError {
+    match i64::try_from(code) {
+        Ok(code) => ParseError::new(ParseErrorKind::UnknownTermCode(code), 0, 0, 0, String::new()),
+        Err(_) => unexpected(format!("Integer out of range {}", code)),
+    }
+}
+
+fn cbor_error(error: serde_cbor::error::Error) -> ParseError {
+    ParseError::new(ParseErrorKind::Invalid(error.to_string()), 0, 0, error.offset() as usize, String::new())
+}
+
+// CBOR as JSON, without decompression
+fn to_json(value: Cbor) -> Result<Value, ParseError> {
+    Ok(match value {
+        Cbor::Null => Value::Null,
+        Cbor::Bool(value) => Value::Bool(value),
+        Cbor::Integer(integer) => match (i64::try_from(integer), u64::try_from(integer)) {
+            (Ok(integer), _) => Value::from(integer),
+            (_, Ok(integer)) => Value::from(integer),
+            _ => return Err(unexpected(format!("Integer out of range {}", integer))),
+        },
+        Cbor::Float(value) => Number::from_f64(value).map_or(Value::Null, Value::Number),
+        Cbor::Text(value) => Value::String(value),
+        Cbor::Array(items) => Value::Array(items.into_iter().map(to_json).collect::<Result<_, _>>()?),
+        Cbor::Map(map) => {
+            let mut object = Map::new();
+            for (key, value) in map {
+                match key {
+                    Cbor::Text(key) => object.insert(key, to_json(value)?),
+                    key => return Err(unexpected(format!("Unexpected key {:?}", key))),
+                };
+            }
+            Value::Object(object)
+        }
+        value => return Err(unexpected(format!("Unexpected value {:?}", value))),
+    })
+}
+
+fn decompress(value: Cbor, codes: &Codes) -> Result<Value, ParseError> {
+    match value {
+        Cbor::Array(items) => Ok(Value::Array(items.into_iter().map(|item| decompress(item, codes)).collect::<Result<_, _>>()?)),
+        Cbor::Map(map) => {
+            let mut object = Map::new();
+            for (key, value) in map {
+                let key = match key {
+                    Cbor::Text(key) => key,
+                    Cbor::Integer(code) => match codes.key(code) {
+                        Some(key) => key.to_owned(),
+                        None => return Err(unknown_code(code)),
+                    },
+                    key => return Err(unexpected(format!("Unexpected key {:?}", key))),
+                };
+                let value = match key.as_str() {
+                    "@context" => to_json(value)?,
+                    "@type" => decompress_types(value, codes)?,
+                    _ => decompress(value, codes)?,
+                };
+                object.insert(key, value);
+            }
+            Ok(Value::Object(object))
+        }
+        value => to_json(value),
+    }
+}
+
+fn decompress_types(value: Cbor, codes: &Codes) -> Result<Value, ParseError> {
+    match value {
+        Cbor::Integer(code) => match codes.key(code) {
+            Some(term) => Ok(Value::from(term)),
+            None => Err(unknown_code(code)),
+        },
+        Cbor::Array(types) => Ok(Value::Array(types.into_iter().map(|t| decompress_types(t, codes)).collect::<Result<_, _>>()?)),
+        value => to_json(value),
+    }
+}
+
+/// Parses a CBOR array of quads. Blank node labels go through a `BlankNodeScope`, as in the other parsers.
+pub fn deserialize(cbor: &[u8]) -> Result<Vec<Quad>, ParseError> {
+    let quads: Vec<Quad> = serde_cbor::from_slice(cbor).map_err(cbor_error)?;
+    let mut blank_nodes = BlankNodeScope::default();
+    let mut identifier = |identifier: Identifier| match identifier {
+        Identifier::BlankNode(blank_node) => Identifier::BlankNode(blank_nodes.labeled(&blank_node.value)),
+        identifier => identifier,
+    };
+    Ok(quads
+        .into_iter()
+        .map(|quad| Quad {
+            subject: identifier(quad.subject),
+            predicate: quad.predicate,
+            object: match quad.object {
+                Node::BlankNode(blank_node) => Node::from(identifier(Identifier::BlankNode(blank_node))),
+                object => object,
+            },
+            context: quad.context.map(&mut identifier),
+        })
+        .collect())
+}
+
+/// Decompresses a CBOR-LD document to JSON-LD. Its context may be the IRI of a context of `registry`.
+pub fn decompress_ld(cbor: &[u8], registry: &ContextRegistry) -> Result<Value, ParseError> {
+    let document: Cbor = serde_cbor::from_slice(cbor.strip_prefix(&CBOR_LD_TAG[..]).unwrap_or(cbor)).map_err(cbor_error)?;
+    let context = match &document {
+        Cbor::Map(map) => map.get(&Cbor::Integer(0)).or_else(|| map.get(&Cbor::Integer(1))).cloned(),
+        _ => None,
+    };
+    let context = match context {
+        Some(context) => to_json(context)?,
+        None => Value::Null,
+    };
+    decompress(document, &Codes::new(&context, registry))
+}
+
+/// Parses a CBOR-LD document into quads.
+pub fn deserialize_ld(cbor: &[u8], registry: &ContextRegistry) -> Result<Vec<Quad>, ParseError> {
+    let document = decompress_ld(cbor, registry)?;
+    let expanded = expand(&document, registry, None)?;
+    Ok(to_rdf(&expanded, &mut BlankNodeScope::default()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cbor_deserialize::{decompress_ld, deserialize, deserialize_ld};
+    use crate::cbor_serialize::{serialize, serialize_ld};
+    use crate::jsonld_deserialize::ContextRegistry;
+    use crate::jsonld_serialize;
+    use crate::parse_error::ParseErrorKind;
+    use crate::quad::Quad;
+    use crate::test_set;
+    use serde_json::json;
+
+    #[test]
+    fn test_round_trip() {
+        let quads: Vec<Quad> = test_set::get_quads().into_iter().collect();
+        assert!(test_set::isomorphic(&deserialize(&serialize(quads.clone())).unwrap(), &quads));
+        let context = json!({ "ex": "http://example.com#", "Person": "ex:Person", "likes": { "@id": "ex:likes", "@type": "@id" } });
+        let mut registry = ContextRegistry::new();
+        registry.register("http://example.com/context.jsonld", context.clone());
+        for context in [context, json!("http://example.com/context.jsonld")] {
+            let cbor = serialize_ld(quads.clone(), &context, &registry);
+            let mut expected = jsonld_serialize::compact(quads.clone(), &registry.resolve(&context));
+            expected["@context"] = context.clone();
+            assert_eq!(decompress_ld(&cbor, &registry).unwrap(), expected);
+            assert!(test_set::isomorphic(&deserialize_ld(&cbor, &registry).unwrap(), &quads));
+        }
+        assert!(deserialize(&[0x82, 0x01]).is_err());
+        let error = deserialize_ld(&serialize_ld(quads, &json!("http://example.com/context.jsonld"), &registry), &ContextRegistry::new()).unwrap_err();
+        assert!(matches!(error.kind, ParseErrorKind::UnknownTermCode(_)));
+    }
+}
@@ -0,0 +1,168 @@
+use crate::jsonld_deserialize::ContextRegistry;
+use crate::jsonld_serialize::{compact_nodes, expand};
+use crate::quad::Quad;
+use serde_cbor::Value as Cbor;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+// CBOR output. Plain CBOR is an array of quads as serialized by serde.
+// CBOR-LD (https://json-ld.github.io/cbor-ld-spec/) is compacted JSON-LD where keywords and the
+// terms of the context are replaced by integer codes, and `@type` values that are terms by theirs.
+// Keywords get even codes below 100 in the order of `KEYWORDS`, terms even codes from 100 in
+// lexicographical order, and a key's code is incremented when its value is an array.
+
+/// Header of compressed CBOR-LD documents: the CBOR tag 0x0501.
+pub const CBOR_LD_TAG: [u8; 3] = [0xd9, 0x05, 0x01];
+
+pub(crate) const KEYWORDS: [&str; 27] = [
+    "@context", "@type", "@id", "@value", "@direction", "@graph", "@included", "@index", "@json", "@language",
+    "@list", "@nest", "@reverse", "@base", "@container", "@default", "@embed", "@explicit", "@none",
+    "@omitDefault", "@prefix", "@preserve", "@protected", "@requireAll", "@set", "@version", "@vocab",
+];
+
+const FIRST_TERM_CODE: i128 = 100;
+
+/// The codes of keywords and of the terms defined by a context.
+pub(crate) struct Codes {
+    terms: Vec<String>,
+}
+
+impl Codes {
+    pub(crate) fn new(context: &Value, registry: &ContextRegistry) -> Codes {
+        let mut terms = vec![];
+        let resolved = registry.resolve(context);
+        let contexts = match &resolved {
+            Value::Array(contexts) => contexts.iter().collect(),
+            context => vec![context],
+        };
+        for definitions in contexts.into_iter().filter_map(Value::as_object) {
+            terms.extend(definitions.keys().filter(|term| !term.starts_with('@')).cloned());
+        }
+        terms.sort();
+        terms.dedup();
+        Codes { terms }
+    }
+
+    pub(crate) fn code(&self, key: &str) -> Option<i128> {
+        match KEYWORDS.iter().position(|keyword| *keyword == key) {
+            Some(index) => Some(2 * index as i128),
+            None => self.terms.binary_search_by(|term| term.as_str().cmp(key)).ok().map(|index| FIRST_TERM_CODE + 2 * index as i128),
+        }
+    }
+
+    pub(crate) fn key(&self, code: i128) -> Option<&str> {
+        let code = code - code % 2;
+        if code < FIRST_TERM_CODE {
+            KEYWORDS.get((code / 2) as usize).copied()
+        } else {
+            self.terms.get(((code - FIRST_TERM_CODE) / 2) as usize).map(String::as_str)
+        }
+    }
+}
+
+// JSON as CBOR, without compression
+fn to_cbor(value: &Value) -> Cbor {
+    match value {
+        Value::Null => Cbor::Null,
+        Value::Bool(value) => Cbor::Bool(*value),
+        Value::Number(number) => match number.as_i64() {
+            Some(integer) => Cbor::Integer(integer.into()),
+            None => match number.as_u64() {
+                Some(integer) => Cbor::Integer(integer.into()),
+                None => Cbor::Float(number.as_f64().unwrap()),
+            },
+        },
+        Value::String(value) => Cbor::Text(value.clone()),
+        Value::Array(items) => Cbor::Array(items.iter().map(to_cbor).collect()),
+        Value::Object(object) => {
+            Cbor::Map(object.iter().map(|(key, value)| (Cbor::Text(key.clone()), to_cbor(value))).collect())
+        }
+    }
+}
+
+fn compress(value: &Value, codes: &Codes) -> Cbor {
+    match value {
+        Value::Array(items) => Cbor::Array(items.iter().map(|item| compress(item, codes)).collect()),
+        Value::Object(object) => {
+            let mut map = BTreeMap::new();
+            for (key, value) in object {
+                let compressed = match key.as_str() {
+                    "@context" => to_cbor(value),
+                    "@type" => compress_types(value, codes),
+                    _ => compress(value, codes),
+                };
+                let key = match codes.code(key) {
+                    Some(code) => Cbor::Integer(code + value.is_array() as i128),
+                    None => Cbor::Text(key.clone()),
+                };
+                map.insert(key, compressed);
+            }
+            Cbor::Map(map)
+        }
+        value => to_cbor(value),
+    }
+}
+
+fn compress_types(value: &Value, codes: &Codes) -> Cbor {
+    match value {
+        Value::String(term) => match codes.code(term).filter(|code| *code >= FIRST_TERM_CODE) {
+            Some(code) => Cbor::Integer(code),
+            None => Cbor::Text(term.clone()),
+        },
+        Value::Array(types) => Cbor::Array(types.iter().map(|t| compress_types(t, codes)).collect()),
+        value => to_cbor(value),
+    }
+}
+
+/// `quads` as a CBOR array.
+pub fn serialize<Q>(quads: Q) -> Vec<u8>
+where
+    Q: IntoIterator<Item = Quad>,
+{
+    let quads: Vec<Quad> = quads.into_iter().collect();
+    serde_cbor::to_vec(&quads).unwrap()
+}
+
+/// `quads` as CBOR-LD, compacted and compressed with `context`, which may be the IRI of a context of `registry`.
+pub fn serialize_ld<Q>(quads: Q, context: &Value, registry: &ContextRegistry) -> Vec<u8>
+where
+    Q: IntoIterator<Item = Quad>,
+{
+    let nodes = match expand(quads) {
+        Value::Array(nodes) => nodes,
+        _ => unreachable!(),
+    };
+    let document = compact_nodes(&nodes, context, &registry.resolve(context));
+    let compressed = compress(&document, &Codes::new(context, registry));
+    let mut cbor = CBOR_LD_TAG.to_vec();
+    cbor.extend(serde_cbor::to_vec(&compressed).unwrap());
+    cbor
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cbor_serialize::{serialize_ld, CBOR_LD_TAG};
+    use crate::jsonld_deserialize::ContextRegistry;
+    use crate::test_set;
+    use serde_cbor::Value as Cbor;
+    use serde_json::json;
+
+    #[test]
+    fn test_serialize_ld() {
+        let context = json!({ "ex": "http://example.com#", "Person": "ex:Person", "likes": { "@id": "ex:likes", "@type": "@id" } });
+        let mut registry = ContextRegistry::new();
+        registry.register("http://example.com/context.jsonld", context.clone());
+        let quads: Vec<_> = test_set::get_quads().into_iter().collect();
+        let cbor = serialize_ld(quads.clone(), &json!("http://example.com/context.jsonld"), &registry);
+        assert_eq!(cbor[..3], CBOR_LD_TAG);
+        let document = match serde_cbor::from_slice(&cbor[3..]).unwrap() {
+            Cbor::Map(document) => document,
+            document => panic!("Unexpected CBOR-LD {:?}", document),
+        };
+        // The context IRI, and the nodes in an array under @graph
+        assert_eq!(document[&Cbor::Integer(0)], Cbor::Text("http://example.com/context.jsonld".to_owned()));
+        assert!(document.contains_key(&Cbor::Integer(11)));
+        let inline = serialize_ld(quads, &context, &registry);
+        assert!(cbor.len() < inline.len());
+    }
+}
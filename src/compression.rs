@@ -0,0 +1,489 @@
+use futures::{try_ready, Async, Poll, Stream};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::Path;
+
+// Transparent gzip, zstd and bzip2 compression of inputs and outputs.
+// Files are recognized by their extension, falling back to their first bytes. HTTP bodies use the
+// push-based `Decompressor` and `Compressor`, which work on chunks as they arrive, the
+// `Decompressor` within limits on its output.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd,
+    Bzip2,
+}
+
+impl Compression {
+    /// The compression of data starting with `bytes`.
+    pub fn from_magic(bytes: &[u8]) -> Compression {
+        if bytes.starts_with(&[0x1f, 0x8b]) {
+            Compression::Gzip
+        } else if bytes.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Compression::Zstd
+        } else if bytes.starts_with(b"BZh") {
+            Compression::Bzip2
+        } else {
+            Compression::None
+        }
+    }
+
+    /// The compression of a file named like `path`, as in `dump.nq.gz`.
+    pub fn from_extension<P: AsRef<Path>>(path: P) -> Compression {
+        match path.as_ref().extension().and_then(|extension| extension.to_str()) {
+            Some("gz") => Compression::Gzip,
+            Some("zst") => Compression::Zstd,
+            Some("bz2") => Compression::Bzip2,
+            _ => Compression::None,
+        }
+    }
+
+    /// The compression of an HTTP content coding, `None` for unsupported codings.
+    pub fn from_encoding(encoding: &str) -> Option<Compression> {
+        match encoding.trim().to_lowercase().as_str() {
+            "identity" => Some(Compression::None),
+            "gzip" | "x-gzip" => Some(Compression::Gzip),
+            "zstd" => Some(Compression::Zstd),
+            "bzip2" | "x-bzip2" => Some(Compression::Bzip2),
+            _ => None,
+        }
+    }
+
+    /// The preferred supported coding of an Accept-Encoding header, by q-value and then order.
+    /// `*` stands for the codings not listed, and no compression is used when none is acceptable.
+    pub fn negotiate(accept_encoding: &str) -> Compression {
+        let mut codings = vec![];
+        for coding in accept_encoding.split(',') {
+            let mut parameters = coding.split(';');
+            let name = parameters.next().unwrap().trim().to_lowercase();
+            let quality = parameters
+                .filter_map(|parameter| parameter.split_once('='))
+                .find(|(name, _)| name.trim().eq_ignore_ascii_case("q"))
+                .map_or(Some(1.0), |(_, quality)| quality.trim().parse::<f32>().ok());
+            if let Some(quality) = quality {
+                codings.push((name, quality));
+            }
+        }
+        let mut best = (Compression::None, 0.0);
+        for compression in [Compression::Gzip, Compression::Zstd, Compression::Bzip2] {
+            let listed = codings.iter().find(|(name, _)| Compression::from_encoding(name) == Some(compression));
+            let quality = match listed.or_else(|| codings.iter().find(|(name, _)| name == "*")) {
+                Some((_, quality)) => *quality,
+                None => continue,
+            };
+            let position = |compression: Compression| codings.iter().position(|(name, _)| Compression::from_encoding(name) == Some(compression));
+            let earlier = position(compression).unwrap_or(usize::MAX) < position(best.0).unwrap_or(usize::MAX);
+            if quality > best.1 || (quality == best.1 && quality > 0.0 && earlier) {
+                best = (compression, quality);
+            }
+        }
+        best.0
+    }
+
+    /// The HTTP content coding of the compression.
+    pub fn encoding(self) -> &'static str {
+        match self {
+            Compression::None => "identity",
+            Compression::Gzip => "gzip",
+            Compression::Zstd => "zstd",
+            Compression::Bzip2 => "bzip2",
+        }
+    }
+}
+
+/// Decompresses `reader` as compressed with `compression`.
+pub fn decompress_with<R: Read + Send + 'static>(reader: R, compression: Compression) -> io::Result<Box<dyn Read + Send>> {
+    Ok(match compression {
+        Compression::None => Box::new(reader),
+        Compression::Gzip => Box::new(flate2::read::MultiGzDecoder::new(reader)),
+        Compression::Zstd => Box::new(zstd::stream::read::Decoder::new(reader)?),
+        Compression::Bzip2 => Box::new(bzip2::read::MultiBzDecoder::new(reader)),
+    })
+}
+
+/// Decompresses `reader`, detecting the compression from its first bytes.
+pub fn decompress<R: Read + Send + 'static>(reader: R) -> io::Result<Box<dyn Read + Send>> {
+    let mut reader = BufReader::new(reader);
+    let compression = Compression::from_magic(reader.fill_buf()?);
+    decompress_with(reader, compression)
+}
+
+/// Compresses what is written to `writer` with `compression`. The compressed stream is completed when the writer is dropped.
+pub fn compress_with<W: Write + Send + 'static>(writer: W, compression: Compression) -> io::Result<Box<dyn Write + Send>> {
+    Ok(match compression {
+        Compression::None => Box::new(writer),
+        Compression::Gzip => Box::new(flate2::write::GzEncoder::new(writer, flate2::Compression::default())),
+        Compression::Zstd => Box::new(zstd::stream::write::Encoder::new(writer, 0)?.auto_finish()),
+        Compression::Bzip2 => Box::new(bzip2::write::BzEncoder::new(writer, bzip2::Compression::default())),
+    })
+}
+
+/// Opens the file at `path` for reading, decompressed by its extension or else its first bytes.
+pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Box<dyn Read + Send>> {
+    let file = File::open(&path)?;
+    match Compression::from_extension(&path) {
+        Compression::None => decompress(file),
+        compression => decompress_with(file, compression),
+    }
+}
+
+/// Creates the file at `path` for writing, compressed by its extension.
+pub fn create<P: AsRef<Path>>(path: P) -> io::Result<Box<dyn Write + Send>> {
+    let file = File::create(&path)?;
+    compress_with(file, Compression::from_extension(&path))
+}
+
+/// The most a `Decompressor` may output for one chunk pushed, against small chunks that decompress
+/// to very much more.
+pub const MAX_PUSH_OUTPUT: usize = 16 << 20;
+
+fn too_large() -> io::Error {
+    io::Error::new(io::ErrorKind::FileTooLarge, "Decompressed body is too large")
+}
+
+// The output of a decompressor, which fails rather than grow beyond the limits
+struct Output {
+    buffer: Vec<u8>,
+    total: usize,
+    limit: usize,
+}
+
+impl Write for Output {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        self.total += bytes.len();
+        if self.buffer.len() + bytes.len() > MAX_PUSH_OUTPUT || self.total > self.limit {
+            return Err(too_large());
+        }
+        self.buffer.extend_from_slice(bytes);
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// bzip2's writing decoder never finishes on truncated input, so its stream is driven directly
+struct Bzip2Decoder {
+    stream: bzip2::Decompress,
+    output: Output,
+    done: bool,
+}
+
+impl Bzip2Decoder {
+    fn write_all(&mut self, mut input: &[u8]) -> io::Result<()> {
+        let mut buffer = Vec::with_capacity(32 * 1024);
+        loop {
+            if self.done {
+                return match input.is_empty() {
+                    true => Ok(()),
+                    false => Err(io::Error::new(io::ErrorKind::InvalidData, "Data after the end of the bzip2 stream")),
+                };
+            }
+            let read = self.stream.total_in();
+            let status = self.stream.decompress_vec(input, &mut buffer).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+            input = &input[(self.stream.total_in() - read) as usize..];
+            self.done = status == bzip2::Status::StreamEnd;
+            self.output.write_all(&buffer)?;
+            let drained = buffer.len() < buffer.capacity();
+            buffer.clear();
+            if input.is_empty() && drained && !self.done {
+                return Ok(());
+            }
+        }
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.write_all(&[])?;
+        match self.done {
+            true => Ok(()),
+            false => Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Truncated bzip2 stream")),
+        }
+    }
+}
+
+enum DecompressorState {
+    None(Output),
+    Gzip(flate2::write::MultiGzDecoder<Output>),
+    Zstd(zstd::stream::zio::Writer<Output, zstd::stream::raw::Decoder<'static>>),
+    Bzip2(Bzip2Decoder),
+}
+
+/// Decompresses input pushed in chunks. Each push fails with `io::ErrorKind::FileTooLarge` rather
+/// than output more than `MAX_PUSH_OUTPUT` bytes, or more than the limit in all.
+pub struct Decompressor {
+    state: DecompressorState,
+}
+
+impl Decompressor {
+    pub fn new(compression: Compression) -> io::Result<Decompressor> {
+        let output = Output {
+            buffer: vec![],
+            total: 0,
+            limit: usize::MAX,
+        };
+        let state = match compression {
+            Compression::None => DecompressorState::None(output),
+            Compression::Gzip => DecompressorState::Gzip(flate2::write::MultiGzDecoder::new(output)),
+            Compression::Zstd => DecompressorState::Zstd(zstd::stream::zio::Writer::new(output, zstd::stream::raw::Decoder::new()?)),
+            Compression::Bzip2 => DecompressorState::Bzip2(Bzip2Decoder {
+                stream: bzip2::Decompress::new(false),
+                output,
+                done: false,
+            }),
+        };
+        Ok(Decompressor { state })
+    }
+
+    /// Limits the whole output to `limit` bytes.
+    pub fn with_limit(mut self, limit: usize) -> Decompressor {
+        self.output().limit = limit;
+        self
+    }
+
+    fn output(&mut self) -> &mut Output {
+        match &mut self.state {
+            DecompressorState::None(output) => output,
+            DecompressorState::Gzip(decoder) => decoder.get_mut(),
+            DecompressorState::Zstd(decoder) => decoder.writer_mut(),
+            DecompressorState::Bzip2(decoder) => &mut decoder.output,
+        }
+    }
+
+    /// Feeds the next chunk, returning what it decompressed to.
+    pub fn push(&mut self, chunk: &[u8]) -> io::Result<Vec<u8>> {
+        match &mut self.state {
+            DecompressorState::None(output) => output.write_all(chunk)?,
+            DecompressorState::Gzip(decoder) => decoder.write_all(chunk)?,
+            DecompressorState::Zstd(decoder) => decoder.write_all(chunk)?,
+            DecompressorState::Bzip2(decoder) => decoder.write_all(chunk)?,
+        }
+        Ok(std::mem::take(&mut self.output().buffer))
+    }
+
+    /// Ends the input, returning the rest of the output. Fails on truncated input.
+    pub fn finish(&mut self) -> io::Result<Vec<u8>> {
+        match &mut self.state {
+            DecompressorState::None(_) => {}
+            DecompressorState::Gzip(decoder) => decoder.try_finish()?,
+            DecompressorState::Zstd(decoder) => decoder.finish()?,
+            DecompressorState::Bzip2(decoder) => decoder.finish()?,
+        }
+        Ok(std::mem::take(&mut self.output().buffer))
+    }
+}
+
+enum CompressorState {
+    None,
+    Gzip(flate2::write::GzEncoder<Vec<u8>>),
+    Zstd(zstd::stream::write::Encoder<'static, Vec<u8>>),
+    Bzip2(bzip2::write::BzEncoder<Vec<u8>>),
+}
+
+/// Compresses output pushed in chunks.
+pub struct Compressor {
+    state: CompressorState,
+}
+
+impl Compressor {
+    pub fn new(compression: Compression) -> io::Result<Compressor> {
+        let state = match compression {
+            Compression::None => CompressorState::None,
+            Compression::Gzip => CompressorState::Gzip(flate2::write::GzEncoder::new(vec![], flate2::Compression::default())),
+            Compression::Zstd => CompressorState::Zstd(zstd::stream::write::Encoder::new(vec![], 0)?),
+            Compression::Bzip2 => CompressorState::Bzip2(bzip2::write::BzEncoder::new(vec![], bzip2::Compression::default())),
+        };
+        Ok(Compressor { state })
+    }
+
+    /// Feeds the next chunk, returning the compressed output available so far.
+    pub fn push(&mut self, chunk: &[u8]) -> io::Result<Vec<u8>> {
+        let output = match &mut self.state {
+            CompressorState::None => return Ok(chunk.to_vec()),
+            CompressorState::Gzip(encoder) => {
+                encoder.write_all(chunk)?;
+                encoder.get_mut()
+            }
+            CompressorState::Zstd(encoder) => {
+                encoder.write_all(chunk)?;
+                encoder.get_mut()
+            }
+            CompressorState::Bzip2(encoder) => {
+                encoder.write_all(chunk)?;
+                encoder.get_mut()
+            }
+        };
+        Ok(std::mem::take(output))
+    }
+
+    /// Ends the output, returning the rest of it.
+    pub fn finish(self) -> io::Result<Vec<u8>> {
+        match self.state {
+            CompressorState::None => Ok(vec![]),
+            CompressorState::Gzip(encoder) => encoder.finish(),
+            CompressorState::Zstd(encoder) => encoder.finish(),
+            CompressorState::Bzip2(encoder) => encoder.finish(),
+        }
+    }
+}
+
+/// Decompresses a stream of chunks, such as a hyper `Body`. Decompression errors become stream errors.
+pub struct DecompressStream<S> {
+    stream: S,
+    decompressor: Decompressor,
+    done: bool,
+}
+
+impl<S> Stream for DecompressStream<S>
+where
+    S: Stream,
+    S::Item: AsRef<[u8]>,
+    S::Error: From<io::Error>,
+{
+    type Item = Vec<u8>;
+    type Error = S::Error;
+
+    fn poll(&mut self) -> Poll<Option<Vec<u8>>, S::Error> {
+        loop {
+            if self.done {
+                return Ok(Async::Ready(None));
+            }
+            let output = match try_ready!(self.stream.poll()) {
+                Some(chunk) => self.decompressor.push(chunk.as_ref())?,
+                None => {
+                    self.done = true;
+                    self.decompressor.finish()?
+                }
+            };
+            if !output.is_empty() {
+                return Ok(Async::Ready(Some(output)));
+            }
+        }
+    }
+}
+
+pub fn decompress_stream<S>(stream: S, compression: Compression) -> io::Result<DecompressStream<S>> {
+    Ok(DecompressStream {
+        stream,
+        decompressor: Decompressor::new(compression)?,
+        done: false,
+    })
+}
+
+/// Compresses a stream of chunks.
+pub struct CompressStream<S> {
+    stream: S,
+    compressor: Option<Compressor>,
+}
+
+impl<S> Stream for CompressStream<S>
+where
+    S: Stream,
+    S::Item: AsRef<[u8]>,
+    S::Error: From<io::Error>,
+{
+    type Item = Vec<u8>;
+    type Error = S::Error;
+
+    fn poll(&mut self) -> Poll<Option<Vec<u8>>, S::Error> {
+        loop {
+            let compressor = match &mut self.compressor {
+                Some(compressor) => compressor,
+                None => return Ok(Async::Ready(None)),
+            };
+            let output = match try_ready!(self.stream.poll()) {
+                Some(chunk) => compressor.push(chunk.as_ref())?,
+                None => self.compressor.take().unwrap().finish()?,
+            };
+            if !output.is_empty() {
+                return Ok(Async::Ready(Some(output)));
+            }
+        }
+    }
+}
+
+pub fn compress_stream<S>(stream: S, compression: Compression) -> io::Result<CompressStream<S>> {
+    Ok(CompressStream {
+        stream,
+        compressor: Some(Compressor::new(compression)?),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::compression::{compress_stream, compress_with, decompress, decompress_stream, Compression, Compressor, Decompressor, MAX_PUSH_OUTPUT};
+    use futures::{stream, Future, Stream};
+    use std::io::{self, Cursor, Read, Write};
+    use std::sync::{Arc, Mutex};
+
+    // A writer whose output stays readable once the compressing writer is dropped
+    #[derive(Clone, Default)]
+    struct Shared(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(bytes)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let text = "<http://example.com#a> <http://example.com#b> \"c\" .\n".repeat(1000);
+        for compression in [Compression::None, Compression::Gzip, Compression::Zstd, Compression::Bzip2] {
+            let output = Shared::default();
+            let mut writer = compress_with(output.clone(), compression).unwrap();
+            writer.write_all(text.as_bytes()).unwrap();
+            drop(writer);
+            let compressed = output.0.lock().unwrap().clone();
+            assert_eq!(Compression::from_magic(&compressed), compression);
+            let mut decompressed = String::new();
+            decompress(Cursor::new(compressed.clone())).unwrap().read_to_string(&mut decompressed).unwrap();
+            assert_eq!(decompressed, text);
+
+            // Streams, in chunks that split the headers
+            let chunks: Vec<Vec<u8>> = text.as_bytes().chunks(7).map(<[u8]>::to_vec).collect();
+            let compressed: Vec<u8> = compress_stream(stream::iter_ok::<_, io::Error>(chunks), compression).unwrap().concat2().wait().unwrap();
+            let chunks: Vec<Vec<u8>> = compressed.chunks(3).map(<[u8]>::to_vec).collect();
+            let decompressed = decompress_stream(stream::iter_ok::<_, io::Error>(chunks), compression).unwrap().concat2().wait().unwrap();
+            assert_eq!(decompressed, text.as_bytes());
+        }
+    }
+
+    #[test]
+    fn test_detection() {
+        assert_eq!(Compression::from_extension("dump.nq.gz"), Compression::Gzip);
+        assert_eq!(Compression::from_extension("dump.nt.zst"), Compression::Zstd);
+        assert_eq!(Compression::from_extension("dump.ttl.bz2"), Compression::Bzip2);
+        assert_eq!(Compression::from_extension("dump.nq"), Compression::None);
+        assert_eq!(Compression::from_encoding("X-GZIP"), Some(Compression::Gzip));
+        assert_eq!(Compression::from_encoding("br"), None);
+        assert_eq!(Compression::negotiate("gzip, deflate, br, zstd"), Compression::Gzip);
+        assert_eq!(Compression::negotiate("gzip;q=0.5, zstd;q=0.8"), Compression::Zstd);
+        assert_eq!(Compression::negotiate("br, *;q=0.1, gzip;q=0"), Compression::Zstd);
+        assert_eq!(Compression::negotiate("identity, br"), Compression::None);
+        // Truncated input is an error
+        let text = "<http://example.com#a> <http://example.com#b> \"c\" .\n".repeat(1000);
+        for compression in [Compression::Gzip, Compression::Zstd, Compression::Bzip2] {
+            let mut writer = Compressor::new(compression).unwrap();
+            let mut compressed = writer.push(text.as_bytes()).unwrap();
+            compressed.extend(writer.finish().unwrap());
+            let mut decompressor = Decompressor::new(compression).unwrap();
+            decompressor.push(&compressed[..compressed.len() - 4]).unwrap();
+            assert!(decompressor.finish().is_err(), "{:?}", compression);
+            // And so is output beyond the limit
+            let mut decompressor = Decompressor::new(compression).unwrap().with_limit(text.len() - 1);
+            let error = decompressor.push(&compressed).and_then(|_| decompressor.finish()).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::FileTooLarge);
+        }
+        let mut writer = Compressor::new(Compression::Gzip).unwrap();
+        let mut zeros = writer.push(&vec![0; 2 * MAX_PUSH_OUTPUT]).unwrap();
+        zeros.extend(writer.finish().unwrap());
+        assert!(Decompressor::new(Compression::Gzip).unwrap().push(&zeros).is_err());
+    }
+}
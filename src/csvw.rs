@@ -0,0 +1,699 @@
+use crate::jsonld_deserialize::parse_json;
+use crate::namespace::{PrefixMap, RDF, XSD};
+use crate::nquads_deserialize::is_absolute_iri;
+use crate::parse_error::{ParseError, ParseErrorKind};
+use crate::quad::{Context, Quad};
+use crate::term::{BlankNode, Identifier, Literal, Node, IRI};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+// CSV on the Web (https://www.w3.org/TR/csv2rdf/) in minimal mode: a table described by CSVW metadata
+// (https://www.w3.org/TR/tabular-metadata/) is converted to a statement per cell, with no statements
+// about the table itself. The metadata describes a table, or a table group with one table.
+// Not supported: referenced table schemas, ordered lists, foreign keys, and date and number patterns.
+// Numbers may still set `decimalChar` and `groupChar`, and booleans a `true|false` format.
+
+// Prefixes of the CSVW initial context that are not built in
+const PREFIXES: &[(&str, &str)] = &[
+    ("csvw", "http://www.w3.org/ns/csvw#"),
+    ("dcat", "http://www.w3.org/ns/dcat#"),
+    ("prov", "http://www.w3.org/ns/prov#"),
+    ("schema", "http://schema.org/"),
+];
+
+const SNIPPET_LENGTH: usize = 20;
+
+// Metadata is parsed to JSON values first, so its errors have no position
+fn error(kind: ParseErrorKind) -> ParseError {
+    ParseError::new(kind, 0, 0, 0, String::new())
+}
+
+fn invalid(message: String) -> ParseError {
+    error(ParseErrorKind::InvalidMetadata(message))
+}
+
+fn string_property(object: &Map<String, Value>, key: &str) -> Result<Option<String>, ParseError> {
+    match object.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(value)) => Ok(Some(value.clone())),
+        Some(_) => Err(invalid(format!("{} must be a string", key))),
+    }
+}
+
+fn bool_property(object: &Map<String, Value>, key: &str) -> Result<bool, ParseError> {
+    match object.get(key) {
+        None | Some(Value::Null) => Ok(false),
+        Some(Value::Bool(value)) => Ok(*value),
+        Some(_) => Err(invalid(format!("{} must be a boolean", key))),
+    }
+}
+
+fn template_property(object: &Map<String, Value>, key: &str) -> Result<Option<String>, ParseError> {
+    let template = string_property(object, key)?;
+    if let Some(template) = &template {
+        let mut open = false;
+        for c in template.chars() {
+            match c {
+                '{' if !open => open = true,
+                '}' if open => open = false,
+                '{' | '}' => return Err(error(ParseErrorKind::InvalidTemplate(format!("{} in {}", template, key)))),
+                _ => {}
+            }
+        }
+        if open {
+            return Err(error(ParseErrorKind::InvalidTemplate(format!("{} in {}", template, key))));
+        }
+    }
+    Ok(template)
+}
+
+// Percent-encodes all but the unreserved characters, and the reserved ones too unless `reserved`
+fn encode(value: &str, reserved: bool) -> String {
+    let mut encoded = String::new();
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => encoded.push(byte as char),
+            b':' | b'/' | b'?' | b'#' | b'[' | b']' | b'@' | b'!' | b'$' | b'&' | b'\'' | b'(' | b')' | b'*'
+            | b'+' | b',' | b';' | b'=' | b'%'
+                if reserved =>
+            {
+                encoded.push(byte as char)
+            }
+            byte => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+// URI templates (RFC 6570) with simple, `+` reserved and `#` fragment expansions
+fn expand_template(template: &str, variables: &HashMap<String, String>) -> String {
+    let mut expanded = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        expanded.push_str(&rest[..start]);
+        let end = start + rest[start..].find('}').unwrap();
+        let expression = &rest[start + 1..end];
+        let (operator, names) = match expression.chars().next() {
+            Some(operator @ '+') | Some(operator @ '#') => (Some(operator), &expression[1..]),
+            _ => (None, expression),
+        };
+        let values: Vec<String> = names
+            .split(',')
+            .filter_map(|name| variables.get(name.trim()))
+            .map(|value| encode(value, operator.is_some()))
+            .collect();
+        if !values.is_empty() {
+            if operator == Some('#') {
+                expanded.push('#');
+            }
+            expanded.push_str(&values.join(","));
+        }
+        rest = &rest[end + 1..];
+    }
+    expanded.push_str(rest);
+    expanded
+}
+
+#[derive(Debug, Clone)]
+struct Datatype {
+    base: String,
+    format: Option<Value>,
+}
+
+impl Datatype {
+    fn iri(&self) -> Option<IRI> {
+        match self.base.as_str() {
+            "number" => Some(XSD.iri("double")),
+            "datetime" => Some(XSD.iri("dateTime")),
+            "any" => Some(XSD.iri("anyAtomicType")),
+            "json" => Some(IRI::new("http://www.w3.org/ns/csvw#JSON")),
+            "xml" => Some(RDF.iri("XMLLiteral")),
+            "html" => Some(RDF.iri("HTML")),
+            "anyAtomicType" | "anyURI" | "base64Binary" | "boolean" | "byte" | "date" | "dateTime" | "dateTimeStamp"
+            | "dayTimeDuration" | "decimal" | "double" | "duration" | "float" | "gDay" | "gMonth" | "gMonthDay"
+            | "gYear" | "gYearMonth" | "hexBinary" | "int" | "integer" | "language" | "long" | "Name" | "NCName"
+            | "negativeInteger" | "NMTOKEN" | "nonNegativeInteger" | "nonPositiveInteger" | "normalizedString"
+            | "positiveInteger" | "QName" | "short" | "string" | "time" | "token" | "unsignedByte" | "unsignedInt"
+            | "unsignedLong" | "unsignedShort" | "yearMonthDuration" => Some(XSD.iri(self.base.as_str())),
+            base if is_absolute_iri(base) => Some(IRI::new(base)),
+            _ => None,
+        }
+    }
+
+    fn integer_range(&self) -> Option<(i128, i128)> {
+        match self.base.as_str() {
+            "integer" => Some((i128::MIN, i128::MAX)),
+            "long" => Some((i64::MIN as i128, i64::MAX as i128)),
+            "int" => Some((i32::MIN as i128, i32::MAX as i128)),
+            "short" => Some((i16::MIN as i128, i16::MAX as i128)),
+            "byte" => Some((i8::MIN as i128, i8::MAX as i128)),
+            "unsignedLong" => Some((0, u64::MAX as i128)),
+            "unsignedInt" => Some((0, u32::MAX as i128)),
+            "unsignedShort" => Some((0, u16::MAX as i128)),
+            "unsignedByte" => Some((0, u8::MAX as i128)),
+            "nonNegativeInteger" => Some((0, i128::MAX)),
+            "positiveInteger" => Some((1, i128::MAX)),
+            "nonPositiveInteger" => Some((i128::MIN, 0)),
+            "negativeInteger" => Some((i128::MIN, -1)),
+            _ => None,
+        }
+    }
+
+    // Removes the group characters and makes the decimal character a dot
+    fn normalize_number(&self, value: &str) -> String {
+        let format = self.format.as_ref().and_then(|format| format.as_object());
+        let character = |key: &str| format.and_then(|format| format.get(key)).and_then(|value| value.as_str());
+        let mut number = value.to_owned();
+        if let Some(group) = character("groupChar") {
+            number = number.replace(group, "");
+        }
+        if let Some(decimal) = character("decimalChar") {
+            number = number.replace(decimal, ".");
+        }
+        number
+    }
+
+    // The lexical form of `value`, which fails when it is not valid for the datatype
+    fn lexical_form(&self, value: &str) -> Option<String> {
+        let is_digits = |digits: &str| !digits.is_empty() && digits.bytes().all(|byte| byte.is_ascii_digit());
+        if let Some((min, max)) = self.integer_range() {
+            let number = self.normalize_number(value);
+            let integer: i128 = number.strip_prefix('+').unwrap_or(&number).parse().ok()?;
+            return if min <= integer && integer <= max { Some(integer.to_string()) } else { None };
+        }
+        match self.base.as_str() {
+            "boolean" => {
+                let (true_value, false_value) = match self.format.as_ref().and_then(|format| format.as_str()) {
+                    Some(format) => format.split_once('|')?,
+                    None => ("true", "false"),
+                };
+                match value {
+                    value if value == true_value || (self.format.is_none() && value == "1") => Some("true".to_owned()),
+                    value if value == false_value || (self.format.is_none() && value == "0") => Some("false".to_owned()),
+                    _ => None,
+                }
+            }
+            "decimal" => {
+                let number = self.normalize_number(value);
+                let unsigned = number.trim_start_matches(['+', '-']);
+                let valid = match unsigned.split_once('.') {
+                    Some((integer, fraction)) => {
+                        (integer.is_empty() || is_digits(integer)) && (fraction.is_empty() || is_digits(fraction)) && unsigned.len() > 1
+                    }
+                    None => is_digits(unsigned),
+                };
+                if valid && number.len() - unsigned.len() <= 1 { Some(number) } else { None }
+            }
+            "double" | "float" | "number" => {
+                let number = self.normalize_number(value);
+                match number.as_str() {
+                    "INF" | "-INF" | "NaN" => Some(number),
+                    // Rust also parses `inf` and `nan`, which XSD does not allow
+                    _ if number.parse::<f64>().is_ok() && number.chars().all(|c| !c.is_alphabetic() || c == 'e' || c == 'E') => {
+                        Some(number)
+                    }
+                    _ => None,
+                }
+            }
+            "date" | "dateTime" | "datetime" | "dateTimeStamp" => {
+                // Compared byte by byte, as a cell may have multibyte characters at any index
+                let bytes = value.as_bytes();
+                let date = bytes.len() >= 10
+                    && bytes[..4].iter().all(u8::is_ascii_digit)
+                    && bytes[4] == b'-'
+                    && bytes[5..7].iter().all(u8::is_ascii_digit)
+                    && bytes[7] == b'-'
+                    && bytes[8..10].iter().all(u8::is_ascii_digit);
+                let time = match self.base.as_str() {
+                    "date" => true,
+                    _ => bytes.get(10) == Some(&b'T'),
+                };
+                if date && time { Some(value.to_owned()) } else { None }
+            }
+            _ => Some(value.to_owned()),
+        }
+    }
+}
+
+// The properties columns inherit from their table schema, table and table group
+#[derive(Debug, Clone, Default)]
+struct Properties {
+    about_url: Option<String>,
+    property_url: Option<String>,
+    value_url: Option<String>,
+    datatype: Option<Datatype>,
+    default: Option<String>,
+    lang: Option<String>,
+    null: Option<Vec<String>>,
+    separator: Option<String>,
+}
+
+impl Properties {
+    fn inherit(&self, object: &Map<String, Value>) -> Result<Properties, ParseError> {
+        let mut properties = self.clone();
+        if let Some(about_url) = template_property(object, "aboutUrl")? {
+            properties.about_url = Some(about_url);
+        }
+        if let Some(property_url) = template_property(object, "propertyUrl")? {
+            properties.property_url = Some(property_url);
+        }
+        if let Some(value_url) = template_property(object, "valueUrl")? {
+            properties.value_url = Some(value_url);
+        }
+        if let Some(default) = string_property(object, "default")? {
+            properties.default = Some(default);
+        }
+        if let Some(lang) = string_property(object, "lang")? {
+            properties.lang = Some(lang);
+        }
+        if let Some(separator) = string_property(object, "separator")? {
+            properties.separator = Some(separator);
+        }
+        match object.get("null") {
+            None => {}
+            Some(Value::String(null)) => properties.null = Some(vec![null.clone()]),
+            Some(Value::Array(nulls)) if nulls.iter().all(Value::is_string) => {
+                properties.null = Some(nulls.iter().filter_map(|null| null.as_str()).map(str::to_owned).collect());
+            }
+            Some(_) => return Err(invalid("null must be a string or an array of strings".to_owned())),
+        }
+        let datatype = match object.get("datatype") {
+            None => None,
+            Some(Value::String(base)) => Some(Datatype { base: base.clone(), format: None }),
+            Some(Value::Object(datatype)) => {
+                let base = match (string_property(datatype, "base")?, string_property(datatype, "@id")?) {
+                    (_, Some(id)) => id,
+                    (Some(base), None) => base,
+                    (None, None) => "string".to_owned(),
+                };
+                Some(Datatype { base, format: datatype.get("format").cloned() })
+            }
+            Some(_) => return Err(invalid("datatype must be a string or an object".to_owned())),
+        };
+        if let Some(datatype) = datatype {
+            if datatype.iri().is_none() {
+                return Err(invalid(format!("Unknown datatype {}", datatype.base)));
+            }
+            properties.datatype = Some(datatype);
+        }
+        Ok(properties)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Column {
+    name: String,
+    properties: Properties,
+    is_virtual: bool,
+    suppress_output: bool,
+}
+
+impl Column {
+    fn new(object: &Map<String, Value>, number: usize, inherited: &Properties) -> Result<Column, ParseError> {
+        let title = match object.get("titles") {
+            Some(Value::String(title)) => Some(title.clone()),
+            Some(Value::Array(titles)) => titles.iter().find_map(|title| title.as_str()).map(str::to_owned),
+            Some(Value::Object(titles)) => titles.values().find_map(|title| match title {
+                Value::Array(titles) => titles.iter().find_map(|title| title.as_str()).map(str::to_owned),
+                title => title.as_str().map(str::to_owned),
+            }),
+            _ => None,
+        };
+        let name = string_property(object, "name")?
+            .or(title)
+            .unwrap_or_else(|| format!("_col.{}", number));
+        Ok(Column {
+            name,
+            properties: inherited.inherit(object)?,
+            is_virtual: bool_property(object, "virtual")?,
+            suppress_output: bool_property(object, "suppressOutput")?,
+        })
+    }
+
+    // The value of a cell, `None` when it is null
+    fn value<'a>(&'a self, cell: &'a str) -> Option<&'a str> {
+        let value = match (cell, &self.properties.default) {
+            ("", Some(default)) => default.as_str(),
+            (cell, _) => cell,
+        };
+        let is_null = match &self.properties.null {
+            Some(nulls) => nulls.iter().any(|null| null == value),
+            None => value.is_empty(),
+        };
+        if is_null { None } else { Some(value) }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Dialect {
+    delimiter: u8,
+    quote: u8,
+    header_rows: usize,
+    skip_rows: usize,
+    trim: bool,
+}
+
+impl Dialect {
+    fn new(object: Option<&Map<String, Value>>) -> Result<Dialect, ParseError> {
+        let mut dialect = Dialect {
+            delimiter: b',',
+            quote: b'"',
+            header_rows: 1,
+            skip_rows: 0,
+            trim: true,
+        };
+        let object = match object {
+            Some(object) => object,
+            None => return Ok(dialect),
+        };
+        let byte = |key: &str| -> Result<Option<u8>, ParseError> {
+            match string_property(object, key)? {
+                Some(value) if value.len() == 1 => Ok(Some(value.as_bytes()[0])),
+                Some(value) => Err(invalid(format!("{} {:?} must be a single ASCII character", key, value))),
+                None => Ok(None),
+            }
+        };
+        let count = |key: &str| -> Result<Option<usize>, ParseError> {
+            match object.get(key) {
+                None => Ok(None),
+                Some(value) => value
+                    .as_u64()
+                    .map(|count| Some(count as usize))
+                    .ok_or_else(|| invalid(format!("{} must be a non negative integer", key))),
+            }
+        };
+        dialect.delimiter = byte("delimiter")?.unwrap_or(dialect.delimiter);
+        dialect.quote = byte("quoteChar")?.unwrap_or(dialect.quote);
+        if let Some(Value::Bool(header)) = object.get("header") {
+            dialect.header_rows = *header as usize;
+        }
+        dialect.header_rows = count("headerRowCount")?.unwrap_or(dialect.header_rows);
+        dialect.skip_rows = count("skipRows")?.unwrap_or(dialect.skip_rows);
+        match object.get("trim") {
+            None => {}
+            Some(Value::Bool(trim)) => dialect.trim = *trim,
+            Some(Value::String(trim)) => dialect.trim = trim != "false",
+            Some(_) => return Err(invalid("trim must be a boolean".to_owned())),
+        }
+        Ok(dialect)
+    }
+}
+
+/// A table described by CSVW metadata.
+#[derive(Debug, Clone)]
+pub struct Table {
+    url: IRI,
+    dialect: Dialect,
+    // Empty when the columns are taken from the header
+    columns: Vec<Column>,
+    properties: Properties,
+    suppress_output: bool,
+    prefixes: PrefixMap,
+}
+
+impl Table {
+    /// Reads the description of a table, resolving its relative URLs against `@base` or else `base`.
+    pub fn from_metadata(metadata: &Value, base: Option<IRI>) -> Result<Table, ParseError> {
+        let object = metadata.as_object().ok_or_else(|| invalid("Metadata must be an object".to_owned()))?;
+        let base = match object.get("@context") {
+            Some(Value::Array(context)) => context
+                .iter()
+                .find_map(|context| context.get("@base").and_then(|base| base.as_str()))
+                .map(|context_base| match &base {
+                    Some(base) => base.resolve(context_base),
+                    None => IRI::new(context_base),
+                })
+                .or(base),
+            _ => base,
+        };
+        let mut properties = Properties::default();
+        let mut dialect = object.get("dialect");
+        let table = match object.get("tables") {
+            Some(Value::Array(tables)) if tables.len() == 1 => {
+                properties = properties.inherit(object)?;
+                dialect = tables[0].get("dialect").or(dialect);
+                tables[0].as_object().ok_or_else(|| invalid("Tables must be objects".to_owned()))?
+            }
+            Some(_) => return Err(error(ParseErrorKind::Unsupported("table groups with more than one table".to_owned()))),
+            None => object,
+        };
+        properties = properties.inherit(table)?;
+        let url = string_property(table, "url")?.ok_or_else(|| invalid("The table has no url".to_owned()))?;
+        let url = match &base {
+            _ if is_absolute_iri(&url) => IRI::new(url),
+            Some(base) => base.resolve(&url),
+            None => return Err(error(ParseErrorKind::RelativeIRI(url))),
+        };
+        let mut columns = vec![];
+        match table.get("tableSchema") {
+            None => {}
+            Some(Value::Object(schema)) => {
+                properties = properties.inherit(schema)?;
+                for (index, column) in schema.get("columns").and_then(|columns| columns.as_array()).into_iter().flatten().enumerate() {
+                    let column = column.as_object().ok_or_else(|| invalid("Columns must be objects".to_owned()))?;
+                    columns.push(Column::new(column, index + 1, &properties)?);
+                }
+            }
+            Some(_) => return Err(error(ParseErrorKind::Unsupported("referenced table schemas".to_owned()))),
+        }
+        let dialect = match dialect {
+            None => Dialect::new(None)?,
+            Some(Value::Object(dialect)) => Dialect::new(Some(dialect))?,
+            Some(_) => return Err(error(ParseErrorKind::Unsupported("referenced dialects".to_owned()))),
+        };
+        let mut prefixes = PrefixMap::default();
+        for (prefix, address) in PREFIXES {
+            prefixes.insert(*prefix, *address);
+        }
+        Ok(Table {
+            url,
+            dialect,
+            columns,
+            properties,
+            suppress_output: bool_property(table, "suppressOutput")?,
+            prefixes,
+        })
+    }
+
+    pub fn url(&self) -> &IRI {
+        &self.url
+    }
+
+    // Prefixed names of the CSVW initial context are expanded, other URLs resolved against the table
+    fn expand_url(&self, template: &str, variables: &HashMap<String, String>) -> IRI {
+        let url = expand_template(template, variables);
+        match self.prefixes.expand(&url) {
+            Ok(iri) if !url.contains("//") => iri,
+            _ => self.url.resolve(&url),
+        }
+    }
+
+    fn columns_from_header(&self, header: &csv::StringRecord) -> Vec<Column> {
+        header
+            .iter()
+            .enumerate()
+            .map(|(index, title)| Column {
+                name: if title.is_empty() { format!("_col.{}", index + 1) } else { title.to_owned() },
+                properties: self.properties.clone(),
+                is_virtual: false,
+                suppress_output: false,
+            })
+            .collect()
+    }
+
+    /// Converts the rows of `csv` to quads in `graph`.
+    pub fn deserialize(&self, csv: &str, graph: Context) -> Result<Vec<Quad>, ParseError> {
+        let mut reader = csv::ReaderBuilder::new()
+            .delimiter(self.dialect.delimiter)
+            .quote(self.dialect.quote)
+            .has_headers(false)
+            .flexible(true)
+            .trim(if self.dialect.trim { csv::Trim::All } else { csv::Trim::None })
+            .from_reader(csv.as_bytes());
+        let mut columns = self.columns.clone();
+        let mut quads = vec![];
+        let mut row = 0;
+        for (index, record) in reader.records().enumerate() {
+            let record = record.map_err(|error| {
+                let position = error.position().cloned().unwrap_or_else(csv::Position::new);
+                ParseError::new(ParseErrorKind::Invalid(error.to_string()), position.line() as u32, 0, position.byte() as usize, String::new())
+            })?;
+            let position = record.position().cloned().unwrap_or_else(csv::Position::new);
+            let line = position.line() as u32;
+            let error = |kind: ParseErrorKind, column: usize, cell: &str| {
+                let snippet = cell.chars().take(SNIPPET_LENGTH).collect();
+                ParseError::new(kind, line, column as u32, position.byte() as usize, snippet)
+            };
+            if index < self.dialect.skip_rows {
+                continue;
+            }
+            if index < self.dialect.skip_rows + self.dialect.header_rows {
+                if columns.is_empty() {
+                    columns = self.columns_from_header(&record);
+                }
+                continue;
+            }
+            if columns.is_empty() {
+                return Err(error(ParseErrorKind::InvalidMetadata("The metadata has no columns and the table no header".to_owned()), 0, ""));
+            }
+            let cells = columns.iter().filter(|column| !column.is_virtual).count();
+            if record.len() != cells {
+                return Err(error(ParseErrorKind::InvalidCell(format!("Row has {} cells, the table has {} columns", record.len(), cells)), 0, ""));
+            }
+            row += 1;
+            if self.suppress_output {
+                continue;
+            }
+            let mut variables: HashMap<String, String> = HashMap::new();
+            let mut values = vec![];
+            let mut cells = record.iter();
+            for column in &columns {
+                let value = if column.is_virtual { None } else { column.value(cells.next().unwrap()) };
+                if let Some(value) = value {
+                    variables.insert(column.name.clone(), value.to_owned());
+                }
+                values.push(value);
+            }
+            variables.insert("_row".to_owned(), row.to_string());
+            variables.insert("_sourceRow".to_owned(), line.to_string());
+            let row_subject = Identifier::BlankNode(BlankNode::new());
+            for (index, (column, value)) in columns.iter().zip(values).enumerate() {
+                if column.suppress_output || (value.is_none() && !column.is_virtual) {
+                    continue;
+                }
+                variables.insert("_column".to_owned(), (index + 1).to_string());
+                variables.insert("_sourceColumn".to_owned(), (index + 1).to_string());
+                variables.insert("_name".to_owned(), column.name.clone());
+                let properties = &column.properties;
+                let subject = match &properties.about_url {
+                    Some(about_url) => Identifier::IRI(self.expand_url(about_url, &variables)),
+                    None => row_subject.clone(),
+                };
+                let predicate = match &properties.property_url {
+                    Some(property_url) => self.expand_url(property_url, &variables),
+                    None => self.url.resolve(&format!("#{}", encode(&column.name, false))),
+                };
+                let objects: Vec<Node> = match (&properties.value_url, value) {
+                    (Some(value_url), _) => vec![Node::IRI(self.expand_url(value_url, &variables))],
+                    (None, None) => continue,
+                    (None, Some(value)) => {
+                        let values: Vec<&str> = match &properties.separator {
+                            Some(separator) => value.split(separator.as_str()).filter(|value| column.value(value).is_some()).collect(),
+                            None => vec![value],
+                        };
+                        let datatype = properties.datatype.clone().unwrap_or(Datatype { base: "string".to_owned(), format: None });
+                        let mut literals = vec![];
+                        for value in values {
+                            let lexical_form = datatype
+                                .lexical_form(value)
+                                .ok_or_else(|| error(ParseErrorKind::InvalidCell(format!("Invalid {} value {:?} in column {}", datatype.base, value, column.name)), index + 1, value))?;
+                            let literal = match (&properties.lang, datatype.base.as_str()) {
+                                (Some(lang), "string") if lang != "und" => Literal::new(lexical_form, None, lang.clone()),
+                                _ => Literal::new(lexical_form, datatype.iri(), None),
+                            };
+                            literals.push(Node::Literal(literal));
+                        }
+                        literals
+                    }
+                };
+                for object in objects {
+                    quads.push(Quad::new(subject.clone(), predicate.clone(), object, graph.clone()));
+                }
+            }
+        }
+        Ok(quads)
+    }
+}
+
+/// Converts `csv` to quads in `graph` as described by the CSVW `metadata` document.
+pub fn deserialize(csv: &str, metadata: &str, graph: Context) -> Result<Vec<Quad>, ParseError> {
+    Table::from_metadata(&parse_json(metadata)?, None)?.deserialize(csv, graph)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::csvw::{deserialize, Table};
+    use crate::namespace::XSD;
+    use crate::nquads_deserialize;
+    use crate::parse_error::ParseErrorKind;
+    use crate::quad::Quad;
+    use crate::term::{Identifier, Literal, Node, IRI};
+    use serde_json::json;
+
+    const TREE_OPS: &str = "GID,On Street,Species,Trim Cycle,Inventory Date\n\
+                            1,ADDISON AV,Celtis australis,Large Tree Routine Prune,10/18/2010\n\
+                            2,EMERSON ST,Liquidambar styraciflua,Large Tree Routine Prune,6/2/2010\n";
+
+    #[test]
+    fn test_deserialize() {
+        let metadata = json!({
+            "@context": ["http://www.w3.org/ns/csvw", {"@language": "en"}],
+            "url": "http://example.org/tree-ops.csv",
+            "tableSchema": {
+                "columns": [
+                    {"name": "GID", "titles": "GID", "datatype": "integer", "suppressOutput": true},
+                    {"name": "on_street", "titles": "On Street", "propertyUrl": "schema:streetAddress"},
+                    {"name": "species", "titles": "Species", "lang": "en"},
+                    {"name": "trim_cycle", "titles": "Trim Cycle", "propertyUrl": "http://example.org/tree-ops#{_name}"},
+                    {"name": "inventory_date", "titles": "Inventory Date", "null": "6/2/2010"},
+                    {"virtual": true, "propertyUrl": "rdf:type", "valueUrl": "schema:Place"}
+                ],
+                "aboutUrl": "#gid-{GID}"
+            }
+        });
+        let graph = Some(Identifier::IRI(IRI::new("http://example.org/trees")));
+        let quads = Table::from_metadata(&metadata, None).unwrap().deserialize(TREE_OPS, graph.clone()).unwrap();
+        let expected: Vec<Quad> = nquads_deserialize::deserialize(
+            r#"<http://example.org/tree-ops.csv#gid-1> <http://schema.org/streetAddress> "ADDISON AV" <http://example.org/trees> .
+<http://example.org/tree-ops.csv#gid-1> <http://example.org/tree-ops.csv#species> "Celtis australis"@en <http://example.org/trees> .
+<http://example.org/tree-ops.csv#gid-1> <http://example.org/tree-ops#trim_cycle> "Large Tree Routine Prune" <http://example.org/trees> .
+<http://example.org/tree-ops.csv#gid-1> <http://example.org/tree-ops.csv#inventory_date> "10/18/2010" <http://example.org/trees> .
+<http://example.org/tree-ops.csv#gid-1> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://schema.org/Place> <http://example.org/trees> .
+<http://example.org/tree-ops.csv#gid-2> <http://schema.org/streetAddress> "EMERSON ST" <http://example.org/trees> .
+<http://example.org/tree-ops.csv#gid-2> <http://example.org/tree-ops.csv#species> "Liquidambar styraciflua"@en <http://example.org/trees> .
+<http://example.org/tree-ops.csv#gid-2> <http://example.org/tree-ops#trim_cycle> "Large Tree Routine Prune" <http://example.org/trees> .
+<http://example.org/tree-ops.csv#gid-2> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://schema.org/Place> <http://example.org/trees> .
+"#,
+        )
+        .map(|quad| quad.unwrap())
+        .collect();
+        assert_eq!(quads, expected);
+
+        // Without columns they come from the header, and rows are blank nodes
+        let metadata = r#"{"url": "trees.tsv", "@context": ["http://www.w3.org/ns/csvw", {"@base": "http://example.org/"}],
+                           "dialect": {"delimiter": "\t"}, "tableSchema": {"datatype": {"base": "decimal", "format": {"decimalChar": ","}}}}"#;
+        let quads = deserialize("height\twidth\n1,5\t2\n", metadata, graph).unwrap();
+        assert_eq!(quads.len(), 2);
+        assert_eq!(quads[0].subject, quads[1].subject);
+        assert_eq!(quads[0].predicate, IRI::new("http://example.org/trees.tsv#height"));
+        assert_eq!(quads[0].object, Node::Literal(Literal::new("1.5", XSD.iri("decimal"), None)));
+        assert_eq!(quads[1].object, Node::Literal(Literal::new("2", XSD.iri("decimal"), None)));
+    }
+
+    #[test]
+    fn test_invalid() {
+        let metadata = r#"{"url": "http://example.org/t.csv", "tableSchema": {"columns": [
+                            {"name": "a", "datatype": "integer", "separator": ";"}, {"name": "b", "datatype": "boolean"}]}}"#;
+        assert_eq!(deserialize("a,b\n1;2,true\n3;4,0\n", metadata, None).unwrap().len(), 6);
+        let error = deserialize("a,b\n1;2,true\n3;x,0\n", metadata, None).unwrap_err();
+        assert_eq!((error.line, error.column, error.snippet.as_str()), (3, 1, "x"));
+        assert_eq!(deserialize("a,b\n1,2,3\n", metadata, None).unwrap_err().line, 2);
+        assert!(matches!(error.kind, ParseErrorKind::InvalidCell(_)));
+        let kind = |metadata: &str| deserialize("a\n1\n", metadata, None).unwrap_err().kind;
+        assert_eq!(kind(r#"{"url": "t.csv"}"#), ParseErrorKind::RelativeIRI("t.csv".to_owned()));
+        assert_eq!(
+            kind(r#"{"url": "http://example.org/t.csv", "datatype": "colour"}"#),
+            ParseErrorKind::InvalidMetadata("Unknown datatype colour".to_owned())
+        );
+        assert_eq!(
+            kind(r##"{"url": "http://example.org/t.csv", "aboutUrl": "#{a"}"##),
+            ParseErrorKind::InvalidTemplate("#{a in aboutUrl".to_owned())
+        );
+        let dates = r#"{"url": "http://example.org/t.csv", "tableSchema": {"columns": [{"name": "d", "datatype": "date"}]}}"#;
+        assert_eq!(deserialize("d\n2020-01-31\n", dates, None).unwrap().len(), 1);
+        for cell in ["202é-01-31", "2020-é1-31", "2020-01-3é"] {
+            let error = deserialize(&format!("d\n{}\n", cell), dates, None).unwrap_err();
+            assert!(matches!(error.kind, ParseErrorKind::InvalidCell(_)), "{}", cell);
+        }
+    }
+}
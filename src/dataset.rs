@@ -3,6 +3,7 @@ use std::collections::HashSet;
 use std::ops::{Add, AddAssign, Sub, SubAssign};
 
 use crate::quad::*;
+use crate::rdf_patch::{Change, Patch};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Dataset {
@@ -21,9 +22,28 @@ impl Dataset {
     pub fn insert(&mut self, quad: Quad) {
         self.quads.insert(quad);
     }
+    pub fn remove(&mut self, quad: &Quad) -> bool {
+        self.quads.remove(quad)
+    }
     pub fn contains(&self, quad: &Quad) -> bool {
         return self.quads.contains(quad);
     }
+    /// The patch that turns this dataset into `other`: its deletions, then its additions.
+    pub fn diff(&self, other: &Dataset) -> Patch {
+        let sorted = |quads: Vec<&Quad>| {
+            let mut quads: Vec<Quad> = quads.into_iter().cloned().collect();
+            quads.sort_by(|a, b| {
+                (&a.subject, &a.predicate, &a.object, &a.context).cmp(&(&b.subject, &b.predicate, &b.object, &b.context))
+            });
+            quads
+        };
+        let deleted = sorted(self.quads.difference(&other.quads).collect());
+        let added = sorted(other.quads.difference(&self.quads).collect());
+        let mut patch = Patch::new();
+        patch.changes.extend(deleted.into_iter().map(Change::Delete));
+        patch.changes.extend(added.into_iter().map(Change::Add));
+        patch
+    }
     pub fn match_quads(
         &self,
         subject: Option<Subject>,
@@ -0,0 +1,579 @@
+use crate::cbor_deserialize;
+use crate::cbor_serialize;
+use crate::jsonld_deserialize::{self, ContextRegistry};
+use crate::jsonld_frame;
+use crate::jsonld_serialize;
+use crate::namespace::PrefixMap;
+use crate::nquads_deserialize;
+use crate::nquads_serialize::serialize_quad;
+use crate::ntriples_deserialize;
+use crate::ntriples_serialize::serialize_triple;
+use crate::parse_error::{ParseError, ParseReport};
+use crate::quad::{Context, Quad};
+use crate::term::IRI;
+use crate::rdfxml_deserialize;
+use crate::rdfxml_serialize;
+use crate::trig_deserialize;
+use crate::trig_serialize;
+use crate::turtle_deserialize;
+use crate::turtle_serialize;
+use std::iter;
+
+// Parsers and serializers of quads by media type, and content negotiation between them.
+// Documents are read whole, and written whole unless the format can be written a statement at a
+// time, as N-Quads and N-Triples can. Formats get the shared state and request parameters they
+// need through `ParseOptions` and `SerializeOptions`.
+
+const JSONLD_EXPANDED: &str = "http://www.w3.org/ns/json-ld#expanded";
+
+/// What a parser may use besides the document.
+pub struct ParseOptions<'a> {
+    /// JSON-LD contexts documents may refer to by IRI
+    pub contexts: &'a ContextRegistry,
+    /// The graph of the statements of formats without named graphs, and of the statements of the
+    /// default graph of JSON-LD and CBOR documents
+    pub graph: Context,
+    /// The IRI relative IRIs are resolved against, unless the document sets its own
+    pub base: Option<IRI>,
+    /// Filled with the prefixes the document declares
+    pub prefixes: PrefixMap,
+}
+
+pub trait QuadParser: Send + Sync {
+    /// Parses `document`. With a `report` invalid statements are recorded in it instead of failing,
+    /// as far as the format can recover from them.
+    fn parse(&self, document: &[u8], options: &mut ParseOptions, report: Option<&mut ParseReport>) -> Result<Vec<Quad>, ParseError>;
+}
+
+/// What a serializer may use besides the quads.
+pub struct SerializeOptions<'a> {
+    pub prefixes: &'a PrefixMap,
+    pub contexts: &'a ContextRegistry,
+    /// The `profile` parameter of the accepted media type
+    pub profile: Option<String>,
+    pub jsonld_context: Option<String>,
+    pub jsonld_frame: Option<String>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SerializeError {
+    /// An option is invalid for the format
+    InvalidOption(String),
+    /// The quads can not be written in the format
+    Unrepresentable(String),
+}
+
+#[derive(Debug)]
+pub struct Serialized {
+    pub document: Vec<u8>,
+    /// Set when the document does not hold all of the quads
+    pub warning: Option<String>,
+}
+
+impl Serialized {
+    fn new<D: Into<Vec<u8>>>(document: D) -> Serialized {
+        Serialized {
+            document: document.into(),
+            warning: None,
+        }
+    }
+}
+
+/// The size of the chunks serialized documents are streamed in, roughly.
+pub const CHUNK_SIZE: usize = 64 * 1024;
+
+pub type Quads = Box<dyn Iterator<Item = Quad> + Send>;
+
+pub type Chunks = Box<dyn Iterator<Item = Vec<u8>> + Send>;
+
+pub struct SerializedStream {
+    pub chunks: Chunks,
+    /// Set when the document does not hold all of the quads
+    pub warning: Option<String>,
+}
+
+pub trait QuadSerializer: Send + Sync {
+    fn serialize(&self, quads: Vec<Quad>, options: &SerializeOptions) -> Result<Serialized, SerializeError>;
+
+    /// Serializes `quads` as the chunks of the document are consumed. Unless the format overrides
+    /// it, the document is serialized whole first.
+    fn serialize_stream(&self, quads: Quads, options: &SerializeOptions) -> Result<SerializedStream, SerializeError> {
+        let Serialized { document, warning } = self.serialize(quads.collect(), options)?;
+        let mut offset = 0;
+        let chunks = iter::from_fn(move || {
+            let end = document.len().min(offset + CHUNK_SIZE);
+            let chunk = document.get(offset..end).filter(|chunk| !chunk.is_empty())?.to_vec();
+            offset = end;
+            Some(chunk)
+        });
+        Ok(SerializedStream {
+            chunks: Box::new(chunks),
+            warning,
+        })
+    }
+}
+
+// The statements of `quads`, serialized one by one and gathered into chunks
+fn statements<F>(mut quads: Quads, serialize: F) -> SerializedStream
+where
+    F: Fn(Quad) -> String + Send + 'static,
+{
+    let chunks = iter::from_fn(move || {
+        let mut chunk = String::new();
+        for quad in quads.by_ref() {
+            chunk += &serialize(quad);
+            chunk.push('\n');
+            if chunk.len() >= CHUNK_SIZE {
+                break;
+            }
+        }
+        if chunk.is_empty() { None } else { Some(chunk.into_bytes()) }
+    });
+    SerializedStream {
+        chunks: Box::new(chunks),
+        warning: None,
+    }
+}
+
+fn collect<I>(results: I, report: Option<&mut ParseReport>) -> Result<Vec<Quad>, ParseError>
+where
+    I: Iterator<Item = Result<Quad, ParseError>>,
+{
+    match report {
+        Some(report) => Ok(report.lenient(results).collect()),
+        None => results.collect(),
+    }
+}
+
+// Moves the quads of the default graph to `graph`
+fn in_graph(quads: Vec<Quad>, graph: &Context) -> Vec<Quad> {
+    quads
+        .into_iter()
+        .map(|quad| match quad.context {
+            None => Quad { context: graph.clone(), ..quad },
+            Some(_) => quad,
+        })
+        .collect()
+}
+
+pub struct NQuadsFormat;
+
+impl QuadParser for NQuadsFormat {
+    fn parse(&self, document: &[u8], _: &mut ParseOptions, report: Option<&mut ParseReport>) -> Result<Vec<Quad>, ParseError> {
+        let nquads = ParseError::decode_utf8(document.to_vec())?;
+        collect(nquads_deserialize::deserialize(&nquads), report)
+    }
+}
+
+impl QuadSerializer for NQuadsFormat {
+    fn serialize(&self, quads: Vec<Quad>, _: &SerializeOptions) -> Result<Serialized, SerializeError> {
+        Ok(Serialized::new(quads.into_iter().map(|quad| serialize_quad(quad) + "\n").collect::<String>()))
+    }
+
+    fn serialize_stream(&self, quads: Quads, _: &SerializeOptions) -> Result<SerializedStream, SerializeError> {
+        Ok(statements(quads, serialize_quad))
+    }
+}
+
+pub struct NTriplesFormat;
+
+impl QuadParser for NTriplesFormat {
+    fn parse(&self, document: &[u8], options: &mut ParseOptions, report: Option<&mut ParseReport>) -> Result<Vec<Quad>, ParseError> {
+        let ntriples = ParseError::decode_utf8(document.to_vec())?;
+        collect(ntriples_deserialize::deserialize(&ntriples, options.graph.clone()), report)
+    }
+}
+
+impl QuadSerializer for NTriplesFormat {
+    fn serialize(&self, quads: Vec<Quad>, _: &SerializeOptions) -> Result<Serialized, SerializeError> {
+        Ok(Serialized::new(quads.into_iter().map(|quad| serialize_triple(quad) + "\n").collect::<String>()))
+    }
+
+    fn serialize_stream(&self, quads: Quads, _: &SerializeOptions) -> Result<SerializedStream, SerializeError> {
+        Ok(statements(quads, serialize_triple))
+    }
+}
+
+/// Turtle, or TriG with `graphs`. In lenient mode invalid statements are skipped and parsing resumes after them.
+/// Named graphs are dropped on Turtle output, with a warning.
+pub struct TurtleFormat {
+    pub graphs: bool,
+}
+
+impl QuadParser for TurtleFormat {
+    fn parse(&self, document: &[u8], options: &mut ParseOptions, report: Option<&mut ParseReport>) -> Result<Vec<Quad>, ParseError> {
+        let turtle = ParseError::decode_utf8(document.to_vec())?;
+        let mut deserializer = if self.graphs {
+            trig_deserialize::deserialize(&turtle)
+        } else {
+            turtle_deserialize::deserialize(&turtle, options.graph.clone())
+        };
+        if let Some(base) = &options.base {
+            deserializer = deserializer.with_base(base.clone());
+        }
+        let quads = collect(deserializer.by_ref(), report);
+        for (prefix, address) in deserializer.prefixes().iter() {
+            options.prefixes.insert(prefix, address);
+        }
+        quads
+    }
+}
+
+impl QuadSerializer for TurtleFormat {
+    fn serialize(&self, quads: Vec<Quad>, options: &SerializeOptions) -> Result<Serialized, SerializeError> {
+        if self.graphs {
+            return Ok(Serialized::new(trig_serialize::serialize(quads, options.prefixes)));
+        }
+        let named_graphs = quads.iter().any(|quad| quad.context.is_some());
+        Ok(Serialized {
+            document: turtle_serialize::serialize(quads, options.prefixes).into_bytes(),
+            warning: if named_graphs { Some("Named graphs dropped, Turtle has no named graphs".to_owned()) } else { None },
+        })
+    }
+}
+
+/// Framed with the `jsonld_frame` option, compacted against the `jsonld_context` option or a context
+/// made of the prefixes, or expanded with the JSON-LD expanded profile.
+pub struct JsonLdFormat;
+
+impl QuadParser for JsonLdFormat {
+    fn parse(&self, document: &[u8], options: &mut ParseOptions, _: Option<&mut ParseReport>) -> Result<Vec<Quad>, ParseError> {
+        let json = ParseError::decode_utf8(document.to_vec())?;
+        let quads = jsonld_deserialize::deserialize(&json, options.contexts, options.base.clone())?;
+        Ok(in_graph(quads, &options.graph))
+    }
+}
+
+impl QuadSerializer for JsonLdFormat {
+    fn serialize(&self, quads: Vec<Quad>, options: &SerializeOptions) -> Result<Serialized, SerializeError> {
+        let expanded = options.profile.as_deref().is_some_and(|profile| profile.split_whitespace().any(|profile| profile == JSONLD_EXPANDED));
+        let document = if let Some(frame) = &options.jsonld_frame {
+            serde_json::from_str(frame)
+                .map_err(|error| error.to_string())
+                .and_then(|frame| jsonld_frame::frame(quads, &frame, options.contexts).map_err(|error| error.to_string()))
+                .map_err(|error| SerializeError::InvalidOption(format!("Invalid jsonld_frame: {}", error)))?
+        } else if expanded {
+            jsonld_serialize::expand(quads)
+        } else {
+            match &options.jsonld_context {
+                Some(context) => {
+                    let context = serde_json::from_str(context)
+                        .map_err(|error| SerializeError::InvalidOption(format!("Invalid jsonld_context: {}", error)))?;
+                    jsonld_serialize::compact(quads, &context)
+                }
+                None => jsonld_serialize::compact_with_prefixes(quads, options.prefixes),
+            }
+        };
+        Ok(Serialized::new(document.to_string()))
+    }
+}
+
+/// CBOR quads, or CBOR-LD with `ld`, compressed with the `jsonld_context` option or a context made of the prefixes.
+pub struct CborFormat {
+    pub ld: bool,
+}
+
+impl QuadParser for CborFormat {
+    fn parse(&self, document: &[u8], options: &mut ParseOptions, _: Option<&mut ParseReport>) -> Result<Vec<Quad>, ParseError> {
+        let quads = if self.ld {
+            cbor_deserialize::deserialize_ld(document, options.contexts)?
+        } else {
+            cbor_deserialize::deserialize(document)?
+        };
+        Ok(in_graph(quads, &options.graph))
+    }
+}
+
+impl QuadSerializer for CborFormat {
+    fn serialize(&self, quads: Vec<Quad>, options: &SerializeOptions) -> Result<Serialized, SerializeError> {
+        if !self.ld {
+            return Ok(Serialized::new(cbor_serialize::serialize(quads)));
+        }
+        let context = match &options.jsonld_context {
+            Some(context) => serde_json::from_str(context)
+                .map_err(|error| SerializeError::InvalidOption(format!("Invalid jsonld_context: {}", error)))?,
+            None => jsonld_serialize::context_from_prefixes(options.prefixes),
+        };
+        Ok(Serialized::new(cbor_serialize::serialize_ld(quads, &context, options.contexts)))
+    }
+}
+
+/// RDF/XML, whose statements are read into the `graph` option. Named graphs are dropped on output, with a warning.
+pub struct RdfXmlFormat;
+
+impl QuadParser for RdfXmlFormat {
+    fn parse(&self, document: &[u8], options: &mut ParseOptions, report: Option<&mut ParseReport>) -> Result<Vec<Quad>, ParseError> {
+        let xml = ParseError::decode_utf8(document.to_vec())?;
+        let mut deserializer = rdfxml_deserialize::deserialize(&xml, options.graph.clone());
+        if let Some(base) = &options.base {
+            deserializer = deserializer.with_base(base.clone());
+        }
+        collect(deserializer, report)
+    }
+}
+
+impl QuadSerializer for RdfXmlFormat {
+    fn serialize(&self, quads: Vec<Quad>, _: &SerializeOptions) -> Result<Serialized, SerializeError> {
+        let named_graphs = quads.iter().any(|quad| quad.context.is_some());
+        let document = rdfxml_serialize::serialize(quads).map_err(SerializeError::Unrepresentable)?;
+        Ok(Serialized {
+            document: document.into_bytes(),
+            warning: if named_graphs { Some("Named graphs dropped, RDF/XML has no named graphs".to_owned()) } else { None },
+        })
+    }
+}
+
+// A media range of an Accept header
+struct MediaRange {
+    media_type: String,
+    quality: f32,
+    profile: Option<String>,
+}
+
+impl MediaRange {
+    // How specifically the range matches `media_type`, if at all
+    fn specificity(&self, media_type: &str) -> Option<u8> {
+        if self.media_type == "*/*" {
+            Some(0)
+        } else if self.media_type.eq_ignore_ascii_case(media_type) {
+            Some(2)
+        } else {
+            let family = self.media_type.strip_suffix("/*")?;
+            let (candidate, _) = media_type.split_once('/')?;
+            if family.eq_ignore_ascii_case(candidate) {
+                Some(1)
+            } else {
+                None
+            }
+        }
+    }
+}
+
+// Ranges with an invalid quality are left out
+fn media_ranges(accept: &str) -> Vec<MediaRange> {
+    let mut ranges = vec![];
+    for range in accept.split(',') {
+        let mut parameters = range.split(';');
+        let media_type = parameters.next().unwrap().trim().to_lowercase();
+        if media_type.is_empty() {
+            continue;
+        }
+        let mut quality = Some(1.0);
+        let mut profile = None;
+        for parameter in parameters {
+            let (name, value) = match parameter.split_once('=') {
+                Some((name, value)) => (name.trim().to_lowercase(), value.trim().trim_matches('"')),
+                None => continue,
+            };
+            match name.as_str() {
+                "q" => quality = value.parse::<f32>().ok().filter(|quality| (0.0..=1.0).contains(quality)),
+                "profile" => profile = Some(value.to_owned()),
+                _ => {}
+            }
+        }
+        if let Some(quality) = quality {
+            ranges.push(MediaRange {
+                media_type,
+                quality,
+                profile,
+            });
+        }
+    }
+    ranges
+}
+
+/// The parsers and serializers available for content negotiation, by media type.
+pub struct FormatRegistry {
+    parsers: Vec<(String, Box<dyn QuadParser>)>,
+    serializers: Vec<(String, Box<dyn QuadSerializer>)>,
+}
+
+impl FormatRegistry {
+    pub fn new() -> FormatRegistry {
+        FormatRegistry {
+            parsers: vec![],
+            serializers: vec![],
+        }
+    }
+
+    /// Registers `parser` for `media_type`, replacing the previous one.
+    pub fn register_parser<P: QuadParser + 'static>(&mut self, media_type: &str, parser: P) {
+        let media_type = media_type.to_lowercase();
+        self.parsers.retain(|(registered, _)| *registered != media_type);
+        self.parsers.push((media_type, Box::new(parser)));
+    }
+
+    /// Registers `serializer` for `media_type`, replacing the previous one. Without an Accept header the
+    /// first registered serializer is used.
+    pub fn register_serializer<S: QuadSerializer + 'static>(&mut self, media_type: &str, serializer: S) {
+        let media_type = media_type.to_lowercase();
+        match self.serializers.iter_mut().find(|(registered, _)| *registered == media_type) {
+            Some(registered) => registered.1 = Box::new(serializer),
+            None => self.serializers.push((media_type, Box::new(serializer))),
+        }
+    }
+
+    pub fn parser(&self, media_type: &str) -> Option<&dyn QuadParser> {
+        let media_type = media_type.to_lowercase();
+        self.parsers.iter().find(|(registered, _)| *registered == media_type).map(|(_, parser)| parser.as_ref())
+    }
+
+    pub fn serializer(&self, media_type: &str) -> Option<&dyn QuadSerializer> {
+        let media_type = media_type.to_lowercase();
+        self.serializers.iter().find(|(registered, _)| *registered == media_type).map(|(_, serializer)| serializer.as_ref())
+    }
+
+    pub fn parser_media_types(&self) -> impl Iterator<Item = &str> {
+        self.parsers.iter().map(|(media_type, _)| media_type.as_str())
+    }
+
+    pub fn serializer_media_types(&self) -> impl Iterator<Item = &str> {
+        self.serializers.iter().map(|(media_type, _)| media_type.as_str())
+    }
+
+    /// The registered media type `accept` prefers, with the `profile` parameter it was accepted with.
+    /// The quality of a media type is that of the most specific range matching it, ties are broken by
+    /// the order of the ranges and then of registration, and a quality of 0 means not acceptable.
+    pub fn negotiate(&self, accept: Option<&str>) -> Option<(&str, Option<String>)> {
+        let ranges = media_ranges(accept.unwrap_or("*/*"));
+        let mut best: Option<(f32, usize, &str, Option<String>)> = None;
+        for (media_type, _) in self.serializers.iter() {
+            let matching = ranges
+                .iter()
+                .enumerate()
+                .filter_map(|(position, range)| range.specificity(media_type).map(|specificity| (specificity, position, range)))
+                .max_by_key(|(specificity, position, _)| (*specificity, std::cmp::Reverse(*position)));
+            let (position, range) = match matching {
+                Some((_, position, range)) if range.quality > 0.0 => (position, range),
+                _ => continue,
+            };
+            let better = match &best {
+                Some((quality, best_position, _, _)) => range.quality > *quality || (range.quality == *quality && position < *best_position),
+                None => true,
+            };
+            if better {
+                best = Some((range.quality, position, media_type, range.profile.clone()));
+            }
+        }
+        best.map(|(_, _, media_type, profile)| (media_type, profile))
+    }
+}
+
+/// The built-in formats, with N-Quads as the default.
+impl Default for FormatRegistry {
+    fn default() -> FormatRegistry {
+        let mut registry = FormatRegistry::new();
+        registry.register_parser("application/n-quads", NQuadsFormat);
+        registry.register_parser("application/n-triples", NTriplesFormat);
+        registry.register_parser("text/turtle", TurtleFormat { graphs: false });
+        registry.register_parser("application/trig", TurtleFormat { graphs: true });
+        registry.register_parser("application/ld+json", JsonLdFormat);
+        registry.register_parser("application/cbor", CborFormat { ld: false });
+        registry.register_parser("application/cbor-ld", CborFormat { ld: true });
+        registry.register_parser("application/rdf+xml", RdfXmlFormat);
+        registry.register_serializer("application/n-quads", NQuadsFormat);
+        registry.register_serializer("application/n-triples", NTriplesFormat);
+        registry.register_serializer("text/turtle", TurtleFormat { graphs: false });
+        registry.register_serializer("application/trig", TurtleFormat { graphs: true });
+        registry.register_serializer("application/ld+json", JsonLdFormat);
+        registry.register_serializer("application/cbor", CborFormat { ld: false });
+        registry.register_serializer("application/cbor-ld", CborFormat { ld: true });
+        registry.register_serializer("application/rdf+xml", RdfXmlFormat);
+        registry
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::format_registry::{FormatRegistry, ParseOptions, SerializeOptions};
+    use crate::jsonld_deserialize::ContextRegistry;
+    use crate::namespace::PrefixMap;
+    use crate::parse_error::ParseReport;
+    use crate::quad::Quad;
+    use crate::term::{Identifier, IRI};
+    use crate::test_set;
+
+    #[test]
+    fn test_negotiate() {
+        let registry = FormatRegistry::default();
+        let negotiate = |accept| registry.negotiate(accept).map(|(media_type, _)| media_type.to_owned());
+        assert_eq!(negotiate(None).as_deref(), Some("application/n-quads"));
+        assert_eq!(negotiate(Some("*/*")).as_deref(), Some("application/n-quads"));
+        assert_eq!(negotiate(Some("text/turtle;q=0.5, application/trig")).as_deref(), Some("application/trig"));
+        assert_eq!(negotiate(Some("text/turtle, application/trig")).as_deref(), Some("text/turtle"));
+        assert_eq!(negotiate(Some("text/*;q=0.8, application/rdf+xml;q=0.9")).as_deref(), Some("application/rdf+xml"));
+        // The most specific range decides, so an explicit q=0 excludes a type a wildcard accepts
+        assert_eq!(negotiate(Some("application/*, application/n-quads;q=0")).as_deref(), Some("application/n-triples"));
+        assert_eq!(negotiate(Some("text/html, image/*")), None);
+        assert_eq!(negotiate(Some("text/turtle;q=0")), None);
+        assert_eq!(negotiate(Some("text/turtle;q=2")), None);
+        let (_, profile) = registry.negotiate(Some("application/ld+json;profile=\"http://www.w3.org/ns/json-ld#expanded\"")).unwrap();
+        assert_eq!(profile.as_deref(), Some("http://www.w3.org/ns/json-ld#expanded"));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let registry = FormatRegistry::default();
+        let contexts = ContextRegistry::new();
+        let prefixes = PrefixMap::default();
+        let quads: Vec<Quad> = test_set::get_quads().into_iter().collect();
+        for media_type in ["application/n-quads", "application/trig", "application/ld+json", "application/cbor", "application/cbor-ld"] {
+            let options = SerializeOptions {
+                prefixes: &prefixes,
+                contexts: &contexts,
+                profile: None,
+                jsonld_context: None,
+                jsonld_frame: None,
+            };
+            let serialized = registry.serializer(media_type).unwrap().serialize(quads.clone(), &options).unwrap();
+            let streamed: Vec<u8> = registry.serializer(media_type).unwrap()
+                .serialize_stream(Box::new(quads.clone().into_iter()), &options)
+                .unwrap()
+                .chunks
+                .flatten()
+                .collect();
+            for document in [&serialized.document, &streamed] {
+                let mut options = ParseOptions {
+                    contexts: &contexts,
+                    graph: None,
+                    base: None,
+                    prefixes: PrefixMap::new(),
+                };
+                let parsed = registry.parser(media_type).unwrap().parse(document, &mut options, None).unwrap();
+                assert!(test_set::isomorphic(&parsed, &quads), "{} round trip", media_type);
+            }
+        }
+        // Formats without named graphs say they dropped them
+        let options = SerializeOptions {
+            prefixes: &prefixes,
+            contexts: &contexts,
+            profile: None,
+            jsonld_context: None,
+            jsonld_frame: None,
+        };
+        for media_type in ["text/turtle", "application/rdf+xml"] {
+            let serialized = registry.serializer(media_type).unwrap().serialize(quads.clone(), &options).unwrap();
+            assert!(serialized.warning.is_some(), "{} warning", media_type);
+        }
+        let mut options = ParseOptions {
+            contexts: &contexts,
+            graph: None,
+            base: None,
+            prefixes: PrefixMap::new(),
+        };
+        // Statements of the default graph go to the `graph` option
+        let graph = Some(Identifier::IRI(IRI::new("http://example.com#graph")));
+        options.graph = graph.clone();
+        let json = br#"{"@id": "http://example.com#a", "http://example.com#b": {"@id": "http://example.com#c"}}"#;
+        let parsed = registry.parser("application/ld+json").unwrap().parse(json, &mut options, None).unwrap();
+        assert_eq!(parsed[0].context, graph);
+        options.graph = None;
+        let turtle = registry.parser("Text/Turtle").unwrap();
+        let document = b"@prefix ex: <http://example.com#> .\nex:a ex:b ex:c .\nex:a ex:b .\n";
+        assert!(turtle.parse(document, &mut options, None).is_err());
+        let mut report = ParseReport::new();
+        assert_eq!(turtle.parse(document, &mut options, Some(&mut report)).unwrap().len(), 1);
+        assert_eq!(report.rejected, 1);
+        assert_eq!(options.prefixes.get("ex"), Some("http://example.com#"));
+    }
+}
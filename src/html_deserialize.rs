@@ -0,0 +1,286 @@
+use crate::blank_node_id::{BlankNodeScope, Counter};
+use crate::jsonld_deserialize::{expand, parse_json, to_rdf, ContextRegistry};
+use crate::namespace::{PrefixMap, RDF};
+use crate::nquads_deserialize::is_absolute_iri;
+use crate::parse_error::ParseError;
+use crate::quad::{Context, Quad};
+use crate::term::{Identifier, Literal, Node, IRI};
+use scraper::{ElementRef, Html};
+use std::collections::HashSet;
+
+// Structured data embedded in HTML pages: JSON-LD in `<script type="application/ld+json">` elements,
+// and RDFa Lite 1.1 (https://www.w3.org/TR/rdfa-lite/) attributes: `vocab`, `typeof`, `property`,
+// `resource` and `prefix`, with `href`, `src` and `content` giving property values as in RDFa.
+// All the quads of a page go to the graph named by the page's IRI, except for the named graphs of
+// its JSON-LD. A `<base>` element changes the base of relative IRIs, not the graph.
+
+// Prefixes of the RDFa initial context that are not in the default prefix map
+const PREFIXES: &[(&str, &str)] = &[
+    ("dc", "http://purl.org/dc/terms/"),
+    ("og", "http://ogp.me/ns#"),
+    ("schema", "http://schema.org/"),
+    ("sioc", "http://rdfs.org/sioc/ns#"),
+    ("vcard", "http://www.w3.org/2006/vcard/ns#"),
+];
+
+// The evaluation context of an element
+#[derive(Clone)]
+struct Scope {
+    parent_object: Identifier,
+    vocabulary: Option<String>,
+    prefixes: PrefixMap,
+    language: Option<String>,
+}
+
+struct Extractor<'a> {
+    base: IRI,
+    graph: Context,
+    registry: &'a ContextRegistry,
+    blank_nodes: BlankNodeScope,
+    quads: Vec<Quad>,
+}
+
+fn is_json_ld(element: &ElementRef) -> bool {
+    let media_type = element.value().attr("type").unwrap_or("").split(';').next().unwrap_or("");
+    element.value().name() == "script" && media_type.trim().eq_ignore_ascii_case("application/ld+json")
+}
+
+impl<'a> Extractor<'a> {
+    // Each block has its own blank node labels
+    fn json_ld(&mut self, element: ElementRef) -> Result<(), ParseError> {
+        let document = parse_json(&element.text().collect::<String>())?;
+        let expanded = expand(&document, self.registry, Some(self.base.clone()))?;
+        for quad in to_rdf(&expanded, &mut BlankNodeScope::new(Counter)) {
+            match quad.context {
+                None => self.quads.push(Quad { context: self.graph.clone(), ..quad }),
+                Some(_) => self.quads.push(quad),
+            }
+        }
+        Ok(())
+    }
+
+    // A term, a CURIE or an absolute IRI, as in `property` and `typeof`
+    fn term(&self, value: &str, scope: &Scope) -> Option<IRI> {
+        if value.contains(':') {
+            return scope.prefixes.expand(value).ok().or_else(|| Some(IRI::new(value)).filter(|_| is_absolute_iri(value)));
+        }
+        scope.vocabulary.as_ref().map(|vocabulary| IRI::new(format!("{}{}", vocabulary, value)))
+    }
+
+    // A safe CURIE, a CURIE or an IRI, as in `resource`
+    fn resource(&mut self, value: &str, scope: &Scope) -> Identifier {
+        let curie = value.strip_prefix('[').and_then(|value| value.strip_suffix(']'));
+        let value = curie.unwrap_or(value);
+        if let Some(label) = value.strip_prefix("_:") {
+            return Identifier::BlankNode(self.blank_nodes.labeled(label));
+        }
+        match scope.prefixes.expand(value) {
+            Ok(iri) if !value.starts_with("//") => Identifier::IRI(iri),
+            _ => Identifier::IRI(self.base.resolve(value)),
+        }
+    }
+
+    fn push<O: Into<Node>>(&mut self, subject: &Identifier, predicate: IRI, object: O) {
+        self.quads.push(Quad::new(subject.clone(), predicate, object.into(), self.graph.clone()));
+    }
+
+    // RDFa Core processing (https://www.w3.org/TR/rdfa-core/#s_sequence) without `about`, `rel`,
+    // `rev` and `datatype`
+    fn element(&mut self, element: ElementRef, mut scope: Scope) -> Result<(), ParseError> {
+        if is_json_ld(&element) {
+            return self.json_ld(element);
+        }
+        let attributes = element.value();
+        if let Some(vocabulary) = attributes.attr("vocab") {
+            let vocabulary = vocabulary.trim();
+            scope.vocabulary = if vocabulary.is_empty() { None } else { Some(self.base.resolve(vocabulary).value) };
+        }
+        if let Some(prefixes) = attributes.attr("prefix") {
+            let words: Vec<&str> = prefixes.split_whitespace().collect();
+            for pair in words.chunks(2) {
+                if let [prefix, address] = pair {
+                    if let Some(prefix) = prefix.strip_suffix(':') {
+                        scope.prefixes.insert(prefix.to_lowercase(), *address);
+                    }
+                }
+            }
+        }
+        if let Some(language) = attributes.attr("lang") {
+            scope.language = Some(language.to_owned()).filter(|language| !language.is_empty());
+        }
+        let root = matches!(attributes.name(), "html" | "head" | "body");
+        let resource = attributes
+            .attr("resource")
+            .or_else(|| attributes.attr("href"))
+            .or_else(|| attributes.attr("src"))
+            .map(|value| value.to_owned());
+        let types: Vec<IRI> = attributes
+            .attr("typeof")
+            .map(|types| types.split_whitespace().filter_map(|value| self.term(value, &scope)).collect())
+            .unwrap_or_default();
+        let properties: Vec<IRI> = attributes
+            .attr("property")
+            .map(|properties| properties.split_whitespace().filter_map(|value| self.term(value, &scope)).collect())
+            .unwrap_or_default();
+        let has_typeof = attributes.attr("typeof").is_some();
+        let has_property = attributes.attr("property").is_some();
+        let content = attributes.attr("content");
+
+        let (new_subject, typed_resource) = if has_property && content.is_none() {
+            let new_subject = if root { Identifier::IRI(self.base.clone()) } else { scope.parent_object.clone() };
+            let typed_resource = match (&resource, root) {
+                _ if !has_typeof => None,
+                (_, true) => Some(Identifier::IRI(self.base.clone())),
+                (Some(resource), false) => Some(self.resource(resource, &scope)),
+                (None, false) => Some(Identifier::BlankNode(self.blank_nodes.fresh())),
+            };
+            (Some(new_subject), typed_resource)
+        } else {
+            let new_subject = match (&resource, root) {
+                (Some(resource), _) => Some(self.resource(resource, &scope)),
+                (None, true) => Some(Identifier::IRI(self.base.clone())),
+                (None, false) if has_typeof => Some(Identifier::BlankNode(self.blank_nodes.fresh())),
+                (None, false) => None,
+            };
+            (new_subject.clone(), new_subject.filter(|_| has_typeof))
+        };
+        if let Some(typed_resource) = &typed_resource {
+            for class in types {
+                self.push(typed_resource, RDF.iri("type"), class);
+            }
+        }
+        if has_property {
+            let subject = new_subject.clone().unwrap_or_else(|| scope.parent_object.clone());
+            let object: Node = match (content, &resource, &typed_resource) {
+                (Some(content), _, _) => Literal::new(content, None, scope.language.clone()).into(),
+                (None, Some(resource), _) if !has_typeof => Node::from(self.resource(resource, &scope)),
+                (None, _, Some(typed_resource)) => Node::from(typed_resource.clone()),
+                _ => Literal::new(element.text().collect::<String>(), None, scope.language.clone()).into(),
+            };
+            for property in properties {
+                self.push(&subject, property, object.clone());
+            }
+        }
+
+        // A property without a typeof ends at this element, and descendants describe the parent object
+        let subject = match (&new_subject, has_property && content.is_none()) {
+            (Some(subject), false) => subject.clone(),
+            _ => scope.parent_object.clone(),
+        };
+        let child_scope = Scope {
+            parent_object: typed_resource.filter(|_| has_property).unwrap_or(subject),
+            ..scope
+        };
+        for child in element.children().filter_map(ElementRef::wrap) {
+            self.element(child, child_scope.clone())?;
+        }
+        Ok(())
+    }
+}
+
+/// Extracts the JSON-LD and RDFa Lite of the HTML page at `base` into the graph named `base`.
+/// Contexts of the JSON-LD are looked up in `registry`.
+pub fn deserialize(html: &str, base: IRI, registry: &ContextRegistry) -> Result<Vec<Quad>, ParseError> {
+    let document = Html::parse_document(html);
+    let graph = Some(Identifier::IRI(base.clone()));
+    let base = document
+        .root_element()
+        .descendants()
+        .filter_map(ElementRef::wrap)
+        .find(|element| element.value().name() == "base")
+        .and_then(|element| element.value().attr("href"))
+        .map_or(base.clone(), |href| base.resolve(href));
+    let mut prefixes = PrefixMap::default();
+    for (prefix, address) in PREFIXES {
+        prefixes.insert(*prefix, *address);
+    }
+    let scope = Scope {
+        parent_object: Identifier::IRI(base.clone()),
+        vocabulary: None,
+        prefixes,
+        language: None,
+    };
+    let mut extractor = Extractor {
+        base,
+        graph,
+        registry,
+        blank_nodes: BlankNodeScope::new(Counter),
+        quads: vec![],
+    };
+    extractor.element(document.root_element(), scope)?;
+    // Pages often state the same thing in JSON-LD and RDFa
+    let mut seen = HashSet::new();
+    Ok(extractor.quads.into_iter().filter(|quad| seen.insert(quad.clone())).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::html_deserialize::deserialize;
+    use crate::jsonld_deserialize::ContextRegistry;
+    use crate::nquads_deserialize;
+    use crate::parse_error::ParseErrorKind;
+    use crate::quad::Quad;
+    use crate::term::{Identifier, Literal, IRI};
+    use crate::test_set;
+
+    const PAGE: &str = r#"<!DOCTYPE html>
+<html lang="en" prefix="ex: http://example.com/ns#">
+  <head>
+    <base href="http://example.com/people/">
+    <title>Iddan</title>
+    <script type="application/ld+json">
+      {"@context": {"@vocab": "http://schema.org/"}, "@id": "iddan", "@type": "Person", "knows": {"@id": "_:tamir", "name": "Tamir"}}
+    </script>
+  </head>
+  <body vocab="http://schema.org/">
+    <div resource="iddan" typeof="Person">
+      <span property="name">Iddan</span>
+      <a property="url" href="https://aniddan.com">Home</a>
+      <meta property="ex:nick" content="id" lang="he">
+      <div property="address" typeof="PostalAddress">
+        <span property="addressLocality">Tel Aviv</span>
+      </div>
+      <span property="worksFor" resource="[ex:minerva]"></span>
+    </div>
+    <p typeof="CreativeWork"><span property="unknown:term">Text</span></p>
+  </body>
+</html>"#;
+
+    const EXPECTED: &str = r#"
+<http://example.com/people/iddan> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://schema.org/Person> <http://example.com/pages/iddan.html> .
+<http://example.com/people/iddan> <http://schema.org/knows> _:b0 <http://example.com/pages/iddan.html> .
+_:b0 <http://schema.org/name> "Tamir" <http://example.com/pages/iddan.html> .
+<http://example.com/people/iddan> <http://schema.org/name> "Iddan"@en <http://example.com/pages/iddan.html> .
+<http://example.com/people/iddan> <http://schema.org/url> <https://aniddan.com> <http://example.com/pages/iddan.html> .
+<http://example.com/people/iddan> <http://example.com/ns#nick> "id"@he <http://example.com/pages/iddan.html> .
+<http://example.com/people/iddan> <http://schema.org/address> _:b1 <http://example.com/pages/iddan.html> .
+_:b1 <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://schema.org/PostalAddress> <http://example.com/pages/iddan.html> .
+_:b1 <http://schema.org/addressLocality> "Tel Aviv"@en <http://example.com/pages/iddan.html> .
+<http://example.com/people/iddan> <http://schema.org/worksFor> <http://example.com/ns#minerva> <http://example.com/pages/iddan.html> .
+_:b2 <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://schema.org/CreativeWork> <http://example.com/pages/iddan.html> .
+_:b2 <unknown:term> "Text"@en <http://example.com/pages/iddan.html> .
+"#;
+
+    #[test]
+    fn test_deserialize() {
+        let quads = deserialize(PAGE, IRI::new("http://example.com/pages/iddan.html"), &ContextRegistry::new()).unwrap();
+        let expected: Vec<Quad> = nquads_deserialize::deserialize(EXPECTED).map(|quad| quad.unwrap()).collect();
+        assert!(test_set::isomorphic(&quads, &expected), "{:#?}", quads);
+    }
+
+    #[test]
+    fn test_invalid_json_ld() {
+        let page = r#"<html><body><p property="http://schema.org/name">A</p><script type="application/ld+json">{"@id": </script></body></html>"#;
+        let error = deserialize(page, IRI::new("http://example.com/a.html"), &ContextRegistry::new()).unwrap_err();
+        assert!(matches!(error.kind, ParseErrorKind::Invalid(_)));
+        let page = r#"<html><body><p property="http://schema.org/name">A</p></body></html>"#;
+        let quads = deserialize(page, IRI::new("http://example.com/a.html"), &ContextRegistry::new()).unwrap();
+        let expected = Quad::new(
+            IRI::new("http://example.com/a.html"),
+            IRI::new("http://schema.org/name"),
+            Literal::new("A", None, None),
+            Identifier::IRI(IRI::new("http://example.com/a.html")),
+        );
+        assert_eq!(quads, vec![expected]);
+    }
+}
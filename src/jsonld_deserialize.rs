@@ -0,0 +1,873 @@
+use crate::blank_node_id::BlankNodeScope;
+use crate::namespace::{RDF, XSD};
+use crate::nquads_deserialize::is_absolute_iri;
+use crate::parse_error::{JsonLdErrorCode, ParseError, ParseErrorKind};
+use crate::quad::{Context, Quad};
+use crate::term::{Identifier, Literal, Node, IRI};
+use serde_json::{json, Map, Number, Value};
+use std::collections::{BTreeSet, HashMap};
+
+// JSON-LD input (https://www.w3.org/TR/json-ld11-api/): documents are expanded, then converted to quads.
+// Remote contexts are never fetched, a context given by IRI must have been registered beforehand.
+// Not supported: @nest, @included, @json literals, @direction and index, id and type maps.
+
+/// Contexts that documents may refer to by IRI.
+#[derive(Debug, Clone, Default)]
+pub struct ContextRegistry {
+    contexts: HashMap<String, Value>,
+}
+
+impl ContextRegistry {
+    pub fn new() -> ContextRegistry {
+        ContextRegistry::default()
+    }
+
+    /// Registers `context`, either a context document with an `@context` member or the context itself.
+    pub fn register<S>(&mut self, iri: S, context: Value)
+    where
+        S: Into<String>,
+    {
+        let context = match context {
+            Value::Object(mut document) if document.contains_key("@context") => document.remove("@context").unwrap(),
+            context => context,
+        };
+        self.contexts.insert(iri.into(), context);
+    }
+
+    pub fn get(&self, iri: &str) -> Option<&Value> {
+        self.contexts.get(iri)
+    }
+
+    /// `context` with the registered contexts it refers to by IRI inlined.
+    pub fn resolve(&self, context: &Value) -> Value {
+        match context {
+            Value::String(iri) => self.get(iri).map_or(Value::Null, |context| self.resolve(context)),
+            Value::Array(contexts) => Value::Array(contexts.iter().map(|context| self.resolve(context)).collect()),
+            context => context.clone(),
+        }
+    }
+}
+
+// Documents are parsed to JSON values first, so processing errors have no position
+fn jsonld_error<D: Into<String>>(code: JsonLdErrorCode, detail: D) -> ParseError {
+    ParseError::new(ParseErrorKind::JsonLd(code, detail.into()), 0, 0, 0, String::new())
+}
+
+fn is_keyword(value: &str) -> bool {
+    matches!(
+        value,
+        "@base" | "@container" | "@context" | "@direction" | "@graph" | "@id" | "@import" | "@included"
+            | "@index" | "@json" | "@language" | "@list" | "@nest" | "@none" | "@prefix" | "@propagate"
+            | "@protected" | "@reverse" | "@set" | "@type" | "@value" | "@version" | "@vocab"
+    )
+}
+
+#[derive(Debug, Clone, Default)]
+struct TermDefinition {
+    // None for terms explicitly defined as null
+    iri: Option<String>,
+    reverse: bool,
+    datatype: Option<String>,
+    // Some(None) for terms without a language, overriding the default one
+    language: Option<Option<String>>,
+    container: BTreeSet<String>,
+    context: Option<Value>,
+    prefix: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+struct ActiveContext {
+    terms: HashMap<String, TermDefinition>,
+    base: Option<IRI>,
+    vocab: Option<String>,
+    language: Option<String>,
+    // Frames keep framing keywords, wildcards and empty nodes
+    framing: bool,
+}
+
+impl ActiveContext {
+    fn process(&self, local: &Value, registry: &ContextRegistry, depth: usize) -> Result<ActiveContext, ParseError> {
+        if depth > 32 {
+            return Err(jsonld_error(JsonLdErrorCode::RecursiveContextInclusion, ""));
+        }
+        let mut result = self.clone();
+        let contexts = match local {
+            Value::Array(contexts) => contexts.iter().collect(),
+            local => vec![local],
+        };
+        for context in contexts {
+            match context {
+                Value::Null => {
+                    result = ActiveContext {
+                        base: self.base.clone(),
+                        framing: self.framing,
+                        ..ActiveContext::default()
+                    }
+                }
+                Value::String(iri) => {
+                    let iri = match &result.base {
+                        Some(base) => base.resolve(iri).value,
+                        None => iri.clone(),
+                    };
+                    match registry.get(&iri) {
+                        Some(remote) => result = result.process(remote, registry, depth + 1)?,
+                        None => return Err(jsonld_error(JsonLdErrorCode::LoadingRemoteContextFailed, format!("{} is not a known context", iri))),
+                    }
+                }
+                Value::Object(definitions) => {
+                    if let Some(base) = definitions.get("@base") {
+                        result.base = match (base, &result.base) {
+                            (Value::Null, _) => None,
+                            (Value::String(base), Some(current)) => Some(current.resolve(base)),
+                            (Value::String(base), None) => Some(IRI::new(base.clone())),
+                            _ => return Err(jsonld_error(JsonLdErrorCode::InvalidBaseIri, "")),
+                        };
+                    }
+                    if let Some(vocab) = definitions.get("@vocab") {
+                        result.vocab = match vocab {
+                            Value::Null => None,
+                            Value::String(vocab) => Some(result.expand_iri(vocab, true, true)),
+                            _ => return Err(jsonld_error(JsonLdErrorCode::InvalidVocabMapping, "")),
+                        };
+                    }
+                    if let Some(language) = definitions.get("@language") {
+                        result.language = match language {
+                            Value::Null => None,
+                            Value::String(language) => Some(language.to_lowercase()),
+                            _ => return Err(jsonld_error(JsonLdErrorCode::InvalidDefaultLanguage, "")),
+                        };
+                    }
+                    let mut defined = HashMap::new();
+                    for term in definitions.keys().filter(|term| !is_keyword(term)) {
+                        result.define(definitions, term, &mut defined)?;
+                    }
+                }
+                _ => return Err(jsonld_error(JsonLdErrorCode::InvalidLocalContext, "")),
+            }
+        }
+        Ok(result)
+    }
+
+    // Creates the definition of `term`, and first those of the terms of `local` it depends on
+    fn define(
+        &mut self,
+        local: &Map<String, Value>,
+        term: &str,
+        defined: &mut HashMap<String, bool>,
+    ) -> Result<(), ParseError> {
+        match defined.get(term) {
+            Some(true) => return Ok(()),
+            Some(false) => return Err(jsonld_error(JsonLdErrorCode::CyclicIriMapping, term)),
+            None => {}
+        }
+        defined.insert(term.to_owned(), false);
+        let value = local[term].clone();
+        let simple = value.is_string();
+        let definition = match value {
+            Value::Null => Map::new(),
+            Value::String(iri) => {
+                let mut definition = Map::new();
+                definition.insert("@id".to_owned(), Value::String(iri));
+                definition
+            }
+            Value::Object(definition) => definition,
+            _ => return Err(jsonld_error(JsonLdErrorCode::InvalidTermDefinition, term)),
+        };
+        // The terms used in the definition must be defined first
+        let dependencies: Vec<String> = ["@id", "@reverse", "@type"]
+            .iter()
+            .filter_map(|key| definition.get(*key).and_then(Value::as_str))
+            .chain(std::iter::once(term))
+            .filter_map(|iri| iri.find(':').map(|index| iri[..index].to_owned()))
+            .chain(definition.get("@id").and_then(Value::as_str).map(str::to_owned))
+            .filter(|dependency| dependency != term && local.contains_key(dependency))
+            .collect();
+        for dependency in dependencies {
+            self.define(local, &dependency, defined)?;
+        }
+
+        let mut result = TermDefinition::default();
+        if local[term].is_null() || definition.get("@id") == Some(&Value::Null) {
+            // Explicitly not mapped
+        } else if let Some(reverse) = definition.get("@reverse").and_then(Value::as_str) {
+            result.iri = Some(self.expand_iri(reverse, false, true));
+            result.reverse = true;
+        } else if let Some(id) = definition.get("@id").and_then(Value::as_str) {
+            if id != term {
+                result.iri = Some(self.expand_iri(id, false, true));
+            } else {
+                result.iri = Some(self.expand_term(term));
+            }
+            result.prefix = match definition.get("@prefix") {
+                Some(prefix) => prefix.as_bool().unwrap_or(false),
+                None => simple && !term.contains(':') && result.iri.as_ref().is_some_and(|iri| iri.ends_with(['/', '#', '?', '[', ']', '@', ':'])),
+            };
+        } else {
+            result.iri = Some(self.expand_term(term));
+        }
+        if let Some(datatype) = definition.get("@type").and_then(Value::as_str) {
+            result.datatype = Some(self.expand_iri(datatype, false, true));
+        }
+        result.container = match definition.get("@container") {
+            Some(Value::String(container)) => std::iter::once(container.clone()).collect(),
+            Some(Value::Array(containers)) => containers.iter().filter_map(Value::as_str).map(str::to_owned).collect(),
+            _ => BTreeSet::new(),
+        };
+        if let Some(language) = definition.get("@language") {
+            result.language = Some(language.as_str().map(str::to_lowercase));
+        }
+        result.context = definition.get("@context").cloned();
+        self.terms.insert(term.to_owned(), result);
+        defined.insert(term.to_owned(), true);
+        Ok(())
+    }
+
+    // The IRI of a term defined without `@id`
+    fn expand_term(&self, term: &str) -> String {
+        if term.contains(':') {
+            self.expand_iri(term, false, true)
+        } else {
+            match &self.vocab {
+                Some(vocab) => format!("{}{}", vocab, term),
+                None => term.to_owned(),
+            }
+        }
+    }
+
+    fn expand_iri(&self, value: &str, document_relative: bool, vocab: bool) -> String {
+        if is_keyword(value) {
+            return value.to_owned();
+        }
+        if vocab {
+            if let Some(definition) = self.terms.get(value) {
+                return definition.iri.clone().unwrap_or_default();
+            }
+        }
+        if let Some(index) = value.find(':') {
+            let (prefix, suffix) = (&value[..index], &value[index + 1..]);
+            if prefix == "_" || suffix.starts_with("//") {
+                return value.to_owned();
+            }
+            if let Some(TermDefinition { iri: Some(iri), prefix: true, .. }) = self.terms.get(prefix) {
+                return format!("{}{}", iri, suffix);
+            }
+            if is_absolute_iri(value) {
+                return value.to_owned();
+            }
+        }
+        if vocab {
+            if let Some(vocab) = &self.vocab {
+                return format!("{}{}", vocab, value);
+            }
+        }
+        if document_relative {
+            if let Some(base) = &self.base {
+                return base.resolve(value).value;
+            }
+        }
+        value.to_owned()
+    }
+
+    fn container(&self, term: &str) -> BTreeSet<String> {
+        self.terms.get(term).map(|definition| definition.container.clone()).unwrap_or_default()
+    }
+
+    fn expand_value(&self, property: &str, value: &Value) -> Value {
+        let definition = self.terms.get(property);
+        let datatype = definition.and_then(|definition| definition.datatype.as_deref());
+        match (datatype, value) {
+            (Some("@id"), Value::String(id)) => return json!({ "@id": self.expand_iri(id, true, false) }),
+            (Some("@vocab"), Value::String(id)) => return json!({ "@id": self.expand_iri(id, true, true) }),
+            _ => {}
+        }
+        let mut result = Map::new();
+        result.insert("@value".to_owned(), value.clone());
+        match datatype {
+            Some("@id") | Some("@vocab") | Some("@none") => {}
+            Some(datatype) => {
+                result.insert("@type".to_owned(), Value::from(datatype));
+            }
+            None if value.is_string() => {
+                let language = match definition.and_then(|definition| definition.language.clone()) {
+                    Some(language) => language,
+                    None => self.language.clone(),
+                };
+                if let Some(language) = language {
+                    result.insert("@language".to_owned(), Value::from(language));
+                }
+            }
+            None => {}
+        }
+        Value::Object(result)
+    }
+
+    fn expand(&self, property: Option<&str>, element: &Value, registry: &ContextRegistry) -> Result<Value, ParseError> {
+        match element {
+            Value::Null => Ok(Value::Null),
+            Value::Array(items) => {
+                let list = property.is_some_and(|property| self.container(property).contains("@list"));
+                let mut result = vec![];
+                for item in items {
+                    match self.expand(property, item, registry)? {
+                        Value::Array(items) if !list => result.extend(items),
+                        Value::Null => {}
+                        item => result.push(item),
+                    }
+                }
+                Ok(Value::Array(result))
+            }
+            Value::Object(object) => self.expand_object(property, object, registry),
+            scalar => match property {
+                None | Some("@graph") => Ok(Value::Null),
+                Some(property) => Ok(self.expand_value(property, scalar)),
+            },
+        }
+    }
+
+    fn expand_object(
+        &self,
+        property: Option<&str>,
+        object: &Map<String, Value>,
+        registry: &ContextRegistry,
+    ) -> Result<Value, ParseError> {
+        let mut context = self.clone();
+        if let Some(local) = object.get("@context") {
+            context = context.process(local, registry, 0)?;
+        }
+        // Type scoped contexts apply to the node they type
+        let type_key = object.keys().find(|key| context.expand_iri(key, false, true) == "@type");
+        if let Some(types) = type_key.map(|key| &object[key]) {
+            let mut types: Vec<&str> = match types {
+                Value::Array(types) => types.iter().filter_map(Value::as_str).collect(),
+                types => types.as_str().into_iter().collect(),
+            };
+            types.sort_unstable();
+            for term in types {
+                if let Some(local) = self.terms.get(term).and_then(|definition| definition.context.clone()) {
+                    context = context.process(&local, registry, 0)?;
+                }
+            }
+        }
+
+        let mut result = Map::new();
+        let mut keys: Vec<&String> = object.keys().collect();
+        keys.sort();
+        for key in keys.into_iter().filter(|key| key.as_str() != "@context") {
+            let value = &object[key];
+            let expanded_property = context.expand_iri(key, false, true);
+            if context.framing && is_framing_keyword(&expanded_property) {
+                result.insert(expanded_property, value.clone());
+                continue;
+            }
+            if !expanded_property.contains(':') && !is_keyword(&expanded_property) {
+                continue;
+            }
+            let expanded = match expanded_property.as_str() {
+                "@id" => match value {
+                    Value::String(id) => Value::from(context.expand_iri(id, true, false)),
+                    Value::Object(wildcard) if context.framing && wildcard.is_empty() => json!([{}]),
+                    Value::Array(ids) if context.framing => {
+                        let ids: Option<Vec<Value>> = ids
+                            .iter()
+                            .map(|id| id.as_str().map(|id| Value::from(context.expand_iri(id, true, false))))
+                            .collect();
+                        Value::Array(ids.ok_or_else(|| jsonld_error(JsonLdErrorCode::InvalidIdValue, ""))?)
+                    }
+                    _ => return Err(jsonld_error(JsonLdErrorCode::InvalidIdValue, "")),
+                },
+                "@type" => {
+                    let types = match value {
+                        Value::Array(types) => types.clone(),
+                        value => vec![value.clone()],
+                    };
+                    let types: Result<Vec<Value>, ParseError> = types
+                        .iter()
+                        .map(|t| match t {
+                            Value::String(t) => Ok(Value::from(context.expand_iri(t, true, true))),
+                            Value::Object(wildcard) if context.framing && wildcard.is_empty() => Ok(json!({})),
+                            _ => Err(jsonld_error(JsonLdErrorCode::InvalidTypeValue, "")),
+                        })
+                        .collect();
+                    Value::Array(types?)
+                }
+                "@graph" => match context.expand(Some("@graph"), value, registry)? {
+                    Value::Array(items) => Value::Array(items),
+                    Value::Null => Value::Array(vec![]),
+                    item => Value::Array(vec![item]),
+                },
+                "@value" => match value {
+                    Value::Object(_) | Value::Array(_) => return Err(jsonld_error(JsonLdErrorCode::InvalidValueObjectValue, "")),
+                    value => value.clone(),
+                },
+                "@language" => match value {
+                    Value::String(language) => Value::from(language.to_lowercase()),
+                    _ => return Err(jsonld_error(JsonLdErrorCode::InvalidLanguageTaggedString, "")),
+                },
+                "@index" => match value {
+                    Value::String(_) => value.clone(),
+                    _ => return Err(jsonld_error(JsonLdErrorCode::InvalidIndexValue, "")),
+                },
+                "@list" => match property {
+                    None | Some("@graph") => continue,
+                    _ => match context.expand(property, value, registry)? {
+                        Value::Array(items) => Value::Array(items),
+                        Value::Null => Value::Array(vec![]),
+                        item => Value::Array(vec![item]),
+                    },
+                },
+                "@set" => context.expand(property, value, registry)?,
+                "@reverse" => {
+                    let reverse = match value {
+                        Value::Object(reverse) => reverse,
+                        _ => return Err(jsonld_error(JsonLdErrorCode::InvalidReverseValue, "")),
+                    };
+                    let mut properties = Map::new();
+                    for (key, value) in reverse {
+                        let reverse_property = context.expand_iri(key, false, true);
+                        if let Value::Array(items) = context.expand(Some(key.as_str()), value, registry)?.into_array_items() {
+                            add_values(&mut properties, &reverse_property, items);
+                        }
+                    }
+                    add_values(result.entry("@reverse").or_insert_with(|| json!({})).as_object_mut().unwrap(), "", vec![]);
+                    let target = result.get_mut("@reverse").unwrap().as_object_mut().unwrap();
+                    for (key, values) in properties {
+                        add_values(target, &key, values.as_array().cloned().unwrap_or_default());
+                    }
+                    continue;
+                }
+                keyword if is_keyword(keyword) => continue,
+                _ => {
+                    let container = context.container(key);
+                    let term_context = match context.terms.get(key.as_str()).and_then(|definition| definition.context.clone()) {
+                        Some(local) => context.process(&local, registry, 0)?,
+                        None => context.clone(),
+                    };
+                    let expanded = match value {
+                        Value::Object(map) if container.contains("@language") => {
+                            let mut items = vec![];
+                            let mut languages: Vec<&String> = map.keys().collect();
+                            languages.sort();
+                            for language in languages {
+                                let values = match &map[language] {
+                                    Value::Array(values) => values.clone(),
+                                    value => vec![value.clone()],
+                                };
+                                for value in values {
+                                    match value {
+                                        Value::Null => {}
+                                        Value::String(value) if language == "@none" => items.push(json!({ "@value": value })),
+                                        Value::String(value) => {
+                                            items.push(json!({ "@value": value, "@language": language.to_lowercase() }))
+                                        }
+                                        _ => return Err(jsonld_error(JsonLdErrorCode::InvalidLanguageMapValue, "")),
+                                    }
+                                }
+                            }
+                            Value::Array(items)
+                        }
+                        Value::Object(map) if container.contains("@index") => {
+                            let mut items = vec![];
+                            let mut indexes: Vec<&String> = map.keys().collect();
+                            indexes.sort();
+                            for index in indexes {
+                                if let Value::Array(values) = term_context.expand(Some(key), &map[index], registry)?.into_array_items() {
+                                    for mut value in values {
+                                        if let Some(value) = value.as_object_mut() {
+                                            if index != "@none" && !value.contains_key("@index") {
+                                                value.insert("@index".to_owned(), Value::from(index.clone()));
+                                            }
+                                        }
+                                        items.push(value);
+                                    }
+                                }
+                            }
+                            Value::Array(items)
+                        }
+                        value => term_context.expand(Some(key), value, registry)?,
+                    };
+                    if expanded.is_null() {
+                        continue;
+                    }
+                    let mut items = match expanded.into_array_items() {
+                        Value::Array(items) => items,
+                        _ => unreachable!(),
+                    };
+                    if container.contains("@list") && !(items.len() == 1 && items[0].get("@list").is_some()) {
+                        items = vec![json!({ "@list": items })];
+                    }
+                    if container.contains("@graph") {
+                        items = items.into_iter().map(|item| json!({ "@graph": [item] })).collect();
+                    }
+                    if context.terms.get(key.as_str()).is_some_and(|definition| definition.reverse) {
+                        let reverse = result.entry("@reverse").or_insert_with(|| json!({}));
+                        add_values(reverse.as_object_mut().unwrap(), &expanded_property, items);
+                    } else {
+                        add_values(&mut result, &expanded_property, items);
+                    }
+                    continue;
+                }
+            };
+            result.insert(expanded_property, expanded);
+        }
+
+        if result.contains_key("@value") {
+            if result["@value"].is_null() {
+                return Ok(Value::Null);
+            }
+            if result.contains_key("@type") {
+                result.remove("@language");
+                let datatype = result["@type"].as_array().and_then(|types| types.first()).cloned();
+                result.insert("@type".to_owned(), datatype.unwrap_or(Value::Null));
+            }
+            return Ok(Value::Object(result));
+        }
+        if let Some(set) = result.remove("@set") {
+            return Ok(set);
+        }
+        if result.len() == 1 && result.contains_key("@language") {
+            return Ok(Value::Null);
+        }
+        if context.framing {
+            return Ok(Value::Object(result));
+        }
+        if let None | Some("@graph") = property {
+            // Free floating values and empty nodes are dropped
+            let is_empty_node = result.is_empty() || (result.len() == 1 && result.contains_key("@id"));
+            if is_empty_node || result.contains_key("@list") {
+                return Ok(Value::Null);
+            }
+        }
+        Ok(Value::Object(result))
+    }
+}
+
+fn is_framing_keyword(value: &str) -> bool {
+    matches!(value, "@default" | "@embed" | "@explicit" | "@omitDefault" | "@requireAll")
+}
+
+fn add_values(object: &mut Map<String, Value>, property: &str, values: Vec<Value>) {
+    if property.is_empty() {
+        return;
+    }
+    let existing = object.entry(property).or_insert_with(|| Value::Array(vec![]));
+    existing.as_array_mut().unwrap().extend(values);
+}
+
+trait IntoArrayItems {
+    fn into_array_items(self) -> Value;
+}
+
+impl IntoArrayItems for Value {
+    // The value as an array, wrapping it if it is a single item
+    fn into_array_items(self) -> Value {
+        match self {
+            Value::Array(items) => Value::Array(items),
+            Value::Null => Value::Array(vec![]),
+            item => Value::Array(vec![item]),
+        }
+    }
+}
+
+/// Expands `document` (JSON-LD 1.1 Expansion algorithm), resolving relative IRIs against `base`.
+pub fn expand(document: &Value, registry: &ContextRegistry, base: Option<IRI>) -> Result<Value, ParseError> {
+    let context = ActiveContext {
+        base,
+        ..ActiveContext::default()
+    };
+    let expanded = match context.expand(None, document, registry)? {
+        Value::Object(mut object) if object.len() == 1 && object.contains_key("@graph") => object.remove("@graph").unwrap(),
+        expanded => expanded,
+    };
+    Ok(expanded.into_array_items())
+}
+
+/// Expands a JSON-LD frame (JSON-LD 1.1 Framing), keeping its framing keywords and wildcards.
+pub(crate) fn expand_frame(frame: &Value, registry: &ContextRegistry) -> Result<Map<String, Value>, ParseError> {
+    let context = ActiveContext {
+        framing: true,
+        ..ActiveContext::default()
+    };
+    match context.expand(None, frame, registry)? {
+        Value::Object(frame) => Ok(frame),
+        Value::Array(mut frames) if frames.len() == 1 && frames[0].is_object() => match frames.remove(0) {
+            Value::Object(frame) => Ok(frame),
+            _ => unreachable!(),
+        },
+        _ => Err(jsonld_error(JsonLdErrorCode::InvalidFrame, "A frame must be a single object")),
+    }
+}
+
+fn canonical_double(value: f64) -> String {
+    let formatted = format!("{:E}", value);
+    match formatted.find('E') {
+        Some(index) if !formatted[..index].contains('.') => format!("{}.0{}", &formatted[..index], &formatted[index..]),
+        _ => formatted,
+    }
+}
+
+fn literal(value: &Value, datatype: Option<&str>, language: Option<&str>) -> Option<Literal> {
+    let (lexical, default_datatype) = match value {
+        Value::String(value) => (value.clone(), XSD.iri("string")),
+        Value::Bool(value) => (value.to_string(), XSD.iri("boolean")),
+        Value::Number(number) => number_literal(number, datatype),
+        _ => return None,
+    };
+    match (datatype, language) {
+        (_, Some(language)) => Some(Literal::new(lexical, None, Some(language.to_owned()))),
+        (Some(datatype), None) => Some(Literal::new(lexical, IRI::new(datatype), None)),
+        (None, None) => Some(Literal::new(lexical, default_datatype, None)),
+    }
+}
+
+fn number_literal(number: &Number, datatype: Option<&str>) -> (String, IRI) {
+    let double = XSD.iri("double");
+    match number.as_i64().map(|n| n.to_string()).or_else(|| number.as_u64().map(|n| n.to_string())) {
+        Some(integer) if datatype != Some(double.value.as_str()) => (integer, XSD.iri("integer")),
+        _ => {
+            let value = number.as_f64().unwrap();
+            if value.fract() == 0.0 && value.abs() < 1e21 && datatype != Some(double.value.as_str()) {
+                (format!("{:.0}", value), XSD.iri("integer"))
+            } else {
+                (canonical_double(value), double)
+            }
+        }
+    }
+}
+
+struct RdfWriter<'a> {
+    blank_nodes: &'a mut BlankNodeScope,
+    quads: Vec<Quad>,
+}
+
+impl<'a> RdfWriter<'a> {
+    fn identifier(&mut self, id: &str) -> Option<Identifier> {
+        if let Some(label) = id.strip_prefix("_:") {
+            return Some(Identifier::BlankNode(self.blank_nodes.labeled(label)));
+        }
+        if is_absolute_iri(id) {
+            return Some(Identifier::IRI(IRI::new(id)));
+        }
+        None
+    }
+
+    fn node(&mut self, node: &Map<String, Value>, graph: &Context) -> Option<Identifier> {
+        let subject = match node.get("@id").and_then(Value::as_str) {
+            Some(id) => self.identifier(id)?,
+            None => Identifier::BlankNode(self.blank_nodes.fresh()),
+        };
+        for (property, values) in node {
+            let values = match values {
+                Value::Array(values) => values,
+                _ => continue,
+            };
+            match property.as_str() {
+                "@type" => {
+                    for value in values.iter().filter_map(Value::as_str) {
+                        if let Some(object) = self.identifier(value) {
+                            self.emit(&subject, RDF.iri("type"), object.into(), graph);
+                        }
+                    }
+                }
+                "@graph" => {
+                    let named_graph = Some(subject.clone());
+                    for value in values.iter().filter_map(Value::as_object) {
+                        self.node(value, &named_graph);
+                    }
+                }
+                property if property.starts_with('@') => {}
+                property if is_absolute_iri(property) && !property.starts_with("_:") => {
+                    for value in values {
+                        if let Some(object) = self.object(value, graph) {
+                            self.emit(&subject, IRI::new(property), object, graph);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        if let Some(Value::Object(reverse)) = node.get("@reverse") {
+            for (property, values) in reverse {
+                if !is_absolute_iri(property) || property.starts_with("_:") {
+                    continue;
+                }
+                for value in values.as_array().into_iter().flatten().filter_map(Value::as_object) {
+                    if let Some(reverse_subject) = self.node(value, graph) {
+                        self.emit(&reverse_subject, IRI::new(property.clone()), subject.clone().into(), graph);
+                    }
+                }
+            }
+        }
+        Some(subject)
+    }
+
+    fn object(&mut self, value: &Value, graph: &Context) -> Option<Node> {
+        let object = value.as_object()?;
+        if let Some(literal_value) = object.get("@value") {
+            let datatype = object.get("@type").and_then(Value::as_str);
+            if datatype.is_some_and(|datatype| !is_absolute_iri(datatype)) {
+                return None;
+            }
+            let language = object.get("@language").and_then(Value::as_str);
+            return literal(literal_value, datatype, language).map(Node::Literal);
+        }
+        if let Some(items) = object.get("@list") {
+            return self.list(items.as_array()?, graph);
+        }
+        self.node(object, graph).map(Node::from)
+    }
+
+    fn list(&mut self, items: &[Value], graph: &Context) -> Option<Node> {
+        let objects: Vec<Node> = items.iter().filter_map(|item| self.object(item, graph)).collect();
+        let mut list = Node::IRI(RDF.iri("nil"));
+        let cells: Vec<Identifier> = objects.iter().map(|_| Identifier::BlankNode(self.blank_nodes.fresh())).collect();
+        for (object, cell) in objects.into_iter().zip(cells).rev() {
+            self.emit(&cell, RDF.iri("first"), object, graph);
+            self.emit(&cell, RDF.iri("rest"), list, graph);
+            list = cell.into();
+        }
+        Some(list)
+    }
+
+    fn emit(&mut self, subject: &Identifier, predicate: IRI, object: Node, graph: &Context) {
+        self.quads.push(Quad::new(subject.clone(), predicate, object, graph.clone()));
+    }
+}
+
+impl From<Identifier> for Node {
+    fn from(identifier: Identifier) -> Node {
+        match identifier {
+            Identifier::IRI(iri) => Node::IRI(iri),
+            Identifier::BlankNode(blank_node) => Node::BlankNode(blank_node),
+        }
+    }
+}
+
+/// Converts an expanded document to quads (JSON-LD 1.1 Deserialize JSON-LD to RDF algorithm).
+/// Nodes with relative IRIs are skipped, as RDF has no relative IRIs.
+pub fn to_rdf(expanded: &Value, blank_nodes: &mut BlankNodeScope) -> Vec<Quad> {
+    let mut writer = RdfWriter {
+        blank_nodes,
+        quads: vec![],
+    };
+    for node in expanded.as_array().into_iter().flatten().filter_map(Value::as_object) {
+        writer.node(node, &None);
+    }
+    writer.quads
+}
+
+/// Parses `json`, with the position of syntax errors.
+pub(crate) fn parse_json(json: &str) -> Result<Value, ParseError> {
+    serde_json::from_str(json).map_err(|error| {
+        let line = error.line() as u32;
+        let column = error.column() as u32;
+        let offset: usize = json.split('\n').take(line.saturating_sub(1) as usize).map(|line| line.len() + 1).sum();
+        let snippet = json.split('\n').nth(line.saturating_sub(1) as usize).unwrap_or("").chars().take(20).collect();
+        ParseError::new(ParseErrorKind::Invalid(error.to_string()), line, column, offset + column.saturating_sub(1) as usize, snippet)
+    })
+}
+
+/// Parses a JSON-LD document into quads.
+pub fn deserialize(json: &str, registry: &ContextRegistry, base: Option<IRI>) -> Result<Vec<Quad>, ParseError> {
+    let document = parse_json(json)?;
+    let expanded = expand(&document, registry, base)?;
+    Ok(to_rdf(&expanded, &mut BlankNodeScope::default()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::jsonld_deserialize::{deserialize, expand, ContextRegistry};
+    use crate::jsonld_serialize;
+    use crate::namespace::{FOAF, RDF, XSD};
+    use crate::parse_error::{JsonLdErrorCode, ParseErrorKind};
+    use crate::quad::Quad;
+    use crate::term::{Identifier, Literal, Node, IRI};
+    use crate::test_set;
+    use serde_json::json;
+
+    #[test]
+    fn test_expand() {
+        let document = json!({
+            "@context": {
+                "@vocab": "http://example.com/vocab#",
+                "foaf": "http://xmlns.com/foaf/0.1/",
+                "name": { "@id": "foaf:name", "@container": "@language" },
+                "knows": { "@id": "foaf:knows", "@type": "@id" },
+                "age": { "@id": "foaf:age", "@type": "http://www.w3.org/2001/XMLSchema#integer" },
+            },
+            "@id": "iddan",
+            "@type": "foaf:Person",
+            "name": { "en": "Iddan", "he": "עידן" },
+            "knows": ["tamir", "lior"],
+            "age": "20",
+            "nick": "id",
+        });
+        let expanded = expand(&document, &ContextRegistry::new(), Some(IRI::new("http://example.com/people/"))).unwrap();
+        assert_eq!(
+            expanded,
+            json!([{
+                "@id": "http://example.com/people/iddan",
+                "@type": ["http://xmlns.com/foaf/0.1/Person"],
+                "http://xmlns.com/foaf/0.1/age": [{ "@value": "20", "@type": "http://www.w3.org/2001/XMLSchema#integer" }],
+                "http://xmlns.com/foaf/0.1/knows": [
+                    { "@id": "http://example.com/people/tamir" },
+                    { "@id": "http://example.com/people/lior" },
+                ],
+                "http://xmlns.com/foaf/0.1/name": [
+                    { "@value": "Iddan", "@language": "en" },
+                    { "@value": "עידן", "@language": "he" },
+                ],
+                "http://example.com/vocab#nick": [{ "@value": "id" }],
+            }])
+        );
+    }
+
+    #[test]
+    fn test_deserialize_graphs_and_lists() {
+        let mut registry = ContextRegistry::new();
+        registry.register(
+            "http://example.com/context.jsonld",
+            json!({ "@context": {
+                "ex": "http://example.com#",
+                "likes": { "@id": "ex:likes", "@container": "@list", "@type": "@id" },
+            }}),
+        );
+        let document = r#"{
+            "@context": "http://example.com/context.jsonld",
+            "@id": "ex:ontology",
+            "@graph": [
+                { "@id": "ex:iddan", "likes": ["ex:tamir", "ex:lior"], "ex:age": 20, "ex:height": 1.8 },
+                { "@id": "_:b", "ex:active": true }
+            ]
+        }"#;
+        let quads = deserialize(document, &registry, None).unwrap();
+        let graph = Some(Identifier::IRI(IRI::new("http://example.com#ontology")));
+        assert!(quads.iter().all(|quad| quad.context == graph));
+        assert_eq!(quads.len(), 8);
+        let objects: Vec<&Node> = quads.iter().map(|quad| &quad.object).collect();
+        assert!(objects.contains(&&Node::from(Literal::new("20", XSD.iri("integer"), None))));
+        assert!(objects.contains(&&Node::from(Literal::new("1.8E0", XSD.iri("double"), None))));
+        assert!(objects.contains(&&Node::from(Literal::new("true", XSD.iri("boolean"), None))));
+        assert!(objects.contains(&&Node::from(RDF.iri("nil"))));
+        let code = |document: &str| match deserialize(document, &registry, None).unwrap_err().kind {
+            ParseErrorKind::JsonLd(code, _) => code,
+            kind => panic!("Unexpected error {:?}", kind),
+        };
+        assert_eq!(code(r#"{ "@context": "http://example.com/other.jsonld" }"#), JsonLdErrorCode::LoadingRemoteContextFailed);
+        assert_eq!(code(r#"{ "@context": { "a": "b:x", "b": "a:y" } }"#), JsonLdErrorCode::CyclicIriMapping);
+        assert_eq!(code(r#"{ "@id": 1 }"#), JsonLdErrorCode::InvalidIdValue);
+        assert_eq!(deserialize("{\n  \"a\": ", &registry, None).unwrap_err().line, 2);
+    }
+
+    // The JSON-LD written by `jsonld_serialize` reads back to the same quads
+    #[test]
+    fn test_round_trip() {
+        let mut quads: Vec<Quad> = test_set::get_quads().into_iter().collect();
+        quads.push(Quad::new(IRI::new("http://example.com#iddan"), FOAF.iri("name"), Literal::new("Iddan", None, None), None));
+        let mut prefixes = crate::namespace::PrefixMap::default();
+        prefixes.insert("ex", "http://example.com#");
+        for document in [jsonld_serialize::expand(quads.clone()), jsonld_serialize::compact_with_prefixes(quads.clone(), &prefixes)] {
+            let parsed = deserialize(&document.to_string(), &ContextRegistry::new(), None).unwrap();
+            assert!(test_set::isomorphic(&parsed, &quads), "{}", document);
+        }
+    }
+}
@@ -0,0 +1,300 @@
+use crate::jsonld_deserialize::{expand_frame, ContextRegistry};
+use crate::jsonld_serialize::{compact_nodes, expand_graph};
+use crate::parse_error::ParseError;
+use crate::quad::Quad;
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+// JSON-LD framing (https://www.w3.org/TR/json-ld11-framing/) of the merged graph of the quads.
+// Matches on @id, @type, properties and value patterns, with the @embed (@once, @always, @never),
+// @explicit, @omitDefault, @requireAll and @default keywords. Embedded nodes are not filtered by their frame.
+
+#[derive(PartialEq)]
+enum Embed {
+    Once,
+    Always,
+    Never,
+}
+
+fn embed_flag(frame: &Map<String, Value>) -> Embed {
+    match frame.get("@embed") {
+        Some(Value::String(embed)) if embed == "@always" => Embed::Always,
+        Some(Value::String(embed)) if embed == "@never" => Embed::Never,
+        Some(Value::Bool(false)) => Embed::Never,
+        _ => Embed::Once,
+    }
+}
+
+fn flag(frame: &Map<String, Value>, keyword: &str) -> bool {
+    frame.get(keyword).and_then(Value::as_bool).unwrap_or(false)
+}
+
+fn values(value: Option<&Value>) -> &[Value] {
+    value.and_then(Value::as_array).map_or(&[], Vec::as_slice)
+}
+
+fn property_frames(frame: &Map<String, Value>) -> impl Iterator<Item = (&String, &[Value])> {
+    frame.iter().filter(|(key, _)| !key.starts_with('@')).map(|(key, patterns)| (key, values(Some(patterns))))
+}
+
+struct Framer {
+    nodes: BTreeMap<String, Map<String, Value>>,
+}
+
+impl Framer {
+    fn matches(&self, node: &Map<String, Value>, frame: &Map<String, Value>) -> bool {
+        if let Some(ids) = frame.get("@id") {
+            let ids = match ids {
+                Value::Array(ids) => ids.as_slice(),
+                id => std::slice::from_ref(id),
+            };
+            if !ids.iter().any(Value::is_object) && !ids.contains(&node["@id"]) {
+                return false;
+            }
+        }
+        if let Some(types) = frame.get("@type") {
+            let types = values(Some(types));
+            let node_types = values(node.get("@type"));
+            let matching = if types.is_empty() {
+                node_types.is_empty()
+            } else if types.iter().any(Value::is_object) {
+                !node_types.is_empty()
+            } else {
+                types.iter().any(|t| node_types.contains(t))
+            };
+            if !matching {
+                return false;
+            }
+        }
+        let mut properties = vec![];
+        for (property, patterns) in property_frames(frame) {
+            let node_values = values(node.get(property));
+            // An empty array only matches nodes without the property
+            if patterns.is_empty() {
+                if !node_values.is_empty() {
+                    return false;
+                }
+                continue;
+            }
+            properties.push(patterns.iter().any(|pattern| {
+                pattern.get("@default").is_some() || node_values.iter().any(|value| self.value_matches(value, pattern))
+            }));
+        }
+        if properties.is_empty() {
+            true
+        } else if flag(frame, "@requireAll") {
+            properties.into_iter().all(|matching| matching)
+        } else {
+            properties.into_iter().any(|matching| matching)
+        }
+    }
+
+    fn value_matches(&self, value: &Value, pattern: &Value) -> bool {
+        let pattern = match pattern.as_object() {
+            Some(pattern) => pattern,
+            None => return false,
+        };
+        if pattern.contains_key("@value") || pattern.contains_key("@language") {
+            return ["@value", "@type", "@language"]
+                .iter()
+                .all(|key| pattern.get(*key).is_none_or(|expected| value.get(*key) == Some(expected)));
+        }
+        match value.get("@id").and_then(Value::as_str).and_then(|id| self.nodes.get(id)) {
+            Some(node) => self.matches(node, pattern),
+            None => pattern.keys().all(|key| key.starts_with('@')),
+        }
+    }
+
+    fn embed(&self, id: &str, frame: &Map<String, Value>, stack: &mut Vec<String>, embedded: &mut HashSet<String>) -> Value {
+        let node = &self.nodes[id];
+        stack.push(id.to_owned());
+        embedded.insert(id.to_owned());
+        let explicit = flag(frame, "@explicit");
+        let mut output = Map::new();
+        for (property, node_values) in node {
+            if property.starts_with('@') {
+                output.insert(property.clone(), node_values.clone());
+                continue;
+            }
+            if explicit && !frame.contains_key(property) {
+                continue;
+            }
+            let subframe = values(frame.get(property)).first().and_then(Value::as_object).cloned().unwrap_or_default();
+            let embed = embed_flag(&subframe);
+            let framed = node_values.as_array().unwrap().iter().map(|value| {
+                let reference = value.get("@id").and_then(Value::as_str).filter(|id| self.nodes.contains_key(*id));
+                match reference {
+                    Some(reference)
+                        if embed != Embed::Never
+                            && !stack.iter().any(|id| id == reference)
+                            && (embed == Embed::Always || !embedded.contains(reference)) =>
+                    {
+                        self.embed(reference, &subframe, stack, embedded)
+                    }
+                    _ => value.clone(),
+                }
+            });
+            output.insert(property.clone(), Value::Array(framed.collect()));
+        }
+        for (property, patterns) in property_frames(frame) {
+            let pattern = match patterns.first().and_then(Value::as_object) {
+                Some(pattern) if !output.contains_key(property) && !flag(pattern, "@omitDefault") => pattern,
+                _ => continue,
+            };
+            let default = match pattern.get("@default") {
+                Some(Value::Object(default)) => Value::Object(default.clone()),
+                Some(default) => json!({ "@value": default }),
+                None => json!({ "@value": null }),
+            };
+            output.insert(property.clone(), Value::Array(vec![default]));
+        }
+        stack.pop();
+        Value::Object(output)
+    }
+}
+
+// Counts the uses of each blank node identifier
+fn count_blank_nodes(value: &Value, counts: &mut HashMap<String, usize>) {
+    match value {
+        Value::Array(items) => items.iter().for_each(|item| count_blank_nodes(item, counts)),
+        Value::Object(object) => {
+            if let Some(id) = object.get("@id").and_then(Value::as_str).filter(|id| id.starts_with("_:")) {
+                *counts.entry(id.to_owned()).or_default() += 1;
+            }
+            object.values().for_each(|value| count_blank_nodes(value, counts));
+        }
+        _ => {}
+    }
+}
+
+// Removes the identifiers of blank nodes used only once, which are not needed to link nodes
+fn prune_blank_nodes(value: &mut Value, counts: &HashMap<String, usize>) {
+    match value {
+        Value::Array(items) => items.iter_mut().for_each(|item| prune_blank_nodes(item, counts)),
+        Value::Object(object) => {
+            let single = object.get("@id").and_then(Value::as_str).is_some_and(|id| counts.get(id) == Some(&1));
+            if single && object.len() > 1 {
+                object.remove("@id");
+            }
+            object.values_mut().for_each(|value| prune_blank_nodes(value, counts));
+        }
+        _ => {}
+    }
+}
+
+/// Frames `quads` with `frame`, and compacts the result with the frame's `@context`.
+pub fn frame<Q>(quads: Q, frame: &Value, registry: &ContextRegistry) -> Result<Value, ParseError>
+where
+    Q: IntoIterator<Item = Quad>,
+{
+    let expanded = expand_frame(frame, registry)?;
+    let quads: Vec<Quad> = quads.into_iter().collect();
+    let nodes = expand_graph(&quads)
+        .into_iter()
+        .map(|node| match node {
+            Value::Object(node) => (node["@id"].as_str().unwrap().to_owned(), node),
+            _ => unreachable!(),
+        })
+        .collect();
+    let framer = Framer { nodes };
+    let mut results = Value::Array(
+        framer
+            .nodes
+            .iter()
+            .filter(|(_, node)| framer.matches(node, &expanded))
+            .map(|(id, _)| framer.embed(id, &expanded, &mut vec![], &mut HashSet::new()))
+            .collect(),
+    );
+    let mut counts = HashMap::new();
+    count_blank_nodes(&results, &mut counts);
+    prune_blank_nodes(&mut results, &counts);
+    let context = frame.get("@context").cloned().unwrap_or_else(|| json!({}));
+    Ok(compact_nodes(results.as_array().unwrap(), &context, &registry.resolve(&context)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::jsonld_deserialize::ContextRegistry;
+    use crate::jsonld_frame::frame;
+    use crate::namespace::{FOAF, RDF};
+    use crate::quad::Quad;
+    use crate::term::{BlankNode, Identifier, Literal, IRI};
+    use serde_json::json;
+
+    fn quads() -> Vec<Quad> {
+        let iddan = IRI::new("http://example.com#iddan");
+        let tamir = IRI::new("http://example.com#tamir");
+        let address = BlankNode::from_value("address");
+        let graph = Some(Identifier::IRI(IRI::new("http://example.com#people")));
+        vec![
+            Quad::new(&iddan, RDF.iri("type"), FOAF.iri("Person"), None),
+            Quad::new(&iddan, FOAF.iri("name"), Literal::new("Iddan", None, None), None),
+            Quad::new(&iddan, FOAF.iri("knows"), &tamir, graph.clone()),
+            Quad::new(&iddan, IRI::new("http://example.com#address"), &address, None),
+            Quad::new(&address, IRI::new("http://example.com#city"), Literal::new("Tel Aviv", None, None), None),
+            Quad::new(&tamir, RDF.iri("type"), FOAF.iri("Person"), graph),
+            Quad::new(&tamir, FOAF.iri("knows"), &iddan, None),
+        ]
+    }
+
+    fn context() -> serde_json::Value {
+        json!({
+            "foaf": "http://xmlns.com/foaf/0.1/",
+            "ex": "http://example.com#",
+            "Person": "foaf:Person",
+            "knows": { "@id": "foaf:knows", "@type": "@id" },
+        })
+    }
+
+    #[test]
+    fn test_frame_embedding() {
+        let document = json!({ "@context": context(), "@id": "ex:iddan" });
+        let framed = frame(quads(), &document, &ContextRegistry::new()).unwrap();
+        assert_eq!(
+            framed,
+            json!({
+                "@context": context(),
+                "@id": "ex:iddan",
+                "@type": "Person",
+                "foaf:name": "Iddan",
+                "ex:address": { "ex:city": "Tel Aviv" },
+                "knows": { "@id": "ex:tamir", "@type": "Person", "knows": "ex:iddan" },
+            })
+        );
+        // The address is embedded in both people, so it keeps its identifier
+        let framed = frame(quads(), &json!({ "@context": context(), "@type": "Person" }), &ContextRegistry::new()).unwrap();
+        assert_eq!(framed["@graph"][0]["ex:address"]["@id"], json!("_:address"));
+        assert_eq!(framed["@graph"][1]["knows"]["ex:address"]["@id"], json!("_:address"));
+    }
+
+    #[test]
+    fn test_frame_matching() {
+        let mut registry = ContextRegistry::new();
+        registry.register("http://example.com/context.jsonld", context());
+        let document = json!({
+            "@context": "http://example.com/context.jsonld",
+            "@explicit": true,
+            "@requireAll": true,
+            "foaf:name": {},
+            "knows": { "@embed": "@never" },
+            "foaf:age": { "@default": 20 },
+        });
+        let framed = frame(quads(), &document, &registry).unwrap();
+        assert_eq!(
+            framed,
+            json!({
+                "@context": "http://example.com/context.jsonld",
+                "@id": "ex:iddan",
+                "@type": "Person",
+                "foaf:name": "Iddan",
+                "knows": "ex:tamir",
+                "foaf:age": 20,
+            })
+        );
+        let document = json!({ "@context": context(), "@type": "Person", "foaf:name": [] });
+        assert_eq!(frame(quads(), &document, &registry).unwrap()["@id"], json!("ex:tamir"));
+        let document = json!({ "@context": context(), "ex:city": { "@value": "Haifa" } });
+        assert_eq!(frame(quads(), &document, &registry).unwrap()["@graph"], json!([]));
+        assert!(frame(quads(), &json!([{}, {}]), &registry).is_err());
+    }
+}
@@ -0,0 +1,410 @@
+use crate::namespace::{PrefixMap, RDF, XSD};
+use crate::quad::{Context, Quad};
+use crate::term::{Identifier, Literal, Node};
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+
+// JSON-LD output (https://www.w3.org/TR/json-ld11/), following the "Serialize RDF as JSON-LD"
+// algorithm without native types or list conversion. Named graphs become node objects with `@graph`.
+
+fn identifier_id(identifier: &Identifier) -> String {
+    match identifier {
+        Identifier::IRI(iri) => iri.value.clone(),
+        Identifier::BlankNode(blank_node) => format!("_:{}", blank_node.value),
+    }
+}
+
+fn expand_node(node: &Node) -> Value {
+    match node {
+        Node::IRI(iri) => json!({ "@id": iri.value }),
+        Node::BlankNode(blank_node) => json!({ "@id": format!("_:{}", blank_node.value) }),
+        Node::Literal(literal) => expand_literal(literal),
+    }
+}
+
+fn expand_literal(literal: &Literal) -> Value {
+    let mut value = Map::new();
+    value.insert("@value".to_owned(), Value::from(literal.value.clone()));
+    match &literal.language {
+        Some(language) => {
+            value.insert("@language".to_owned(), Value::from(language.clone()));
+        }
+        None if literal.datatype != XSD.iri("string") => {
+            value.insert("@type".to_owned(), Value::from(literal.datatype.value.clone()));
+        }
+        None => {}
+    }
+    Value::Object(value)
+}
+
+// Node objects of one graph, by subject
+pub(crate) fn expand_graph(quads: &[Quad]) -> Vec<Value> {
+    let mut nodes: BTreeMap<String, Map<String, Value>> = BTreeMap::new();
+    for quad in quads {
+        let id = identifier_id(&quad.subject);
+        let node = nodes.entry(id.clone()).or_insert_with(|| {
+            let mut node = Map::new();
+            node.insert("@id".to_owned(), Value::from(id));
+            node
+        });
+        let (key, value) = match &quad.object {
+            Node::IRI(iri) if quad.predicate == RDF.iri("type") => ("@type".to_owned(), Value::from(iri.value.clone())),
+            object => (quad.predicate.value.clone(), expand_node(object)),
+        };
+        let values = node.entry(key).or_insert_with(|| Value::Array(vec![]));
+        let values = values.as_array_mut().unwrap();
+        if !values.contains(&value) {
+            values.push(value);
+        }
+    }
+    nodes.into_values().map(Value::Object).collect()
+}
+
+/// Expanded JSON-LD of `quads`.
+pub fn expand<Q>(quads: Q) -> Value
+where
+    Q: IntoIterator<Item = Quad>,
+{
+    let mut graphs: BTreeMap<Context, Vec<Quad>> = BTreeMap::new();
+    for quad in quads {
+        graphs.entry(quad.context.clone()).or_default().push(quad);
+    }
+    let mut default_graph = graphs.remove(&None).map(|quads| expand_graph(&quads)).unwrap_or_default();
+    for (graph, quads) in graphs {
+        let id = identifier_id(&graph.unwrap());
+        let graph = json!({ "@graph": expand_graph(&quads) });
+        // The graph is added to the node with its name, if there is one
+        let node = default_graph.iter_mut().find(|node| node["@id"] == id.as_str());
+        match node {
+            Some(node) => {
+                node.as_object_mut().unwrap().insert("@graph".to_owned(), graph["@graph"].clone());
+            }
+            None => {
+                let mut node = graph;
+                node.as_object_mut().unwrap().insert("@id".to_owned(), Value::from(id));
+                default_graph.push(node);
+            }
+        }
+    }
+    default_graph.sort_by(|a, b| a["@id"].as_str().cmp(&b["@id"].as_str()));
+    Value::Array(default_graph)
+}
+
+/// A `@context` mapping every prefix of `prefixes` to its namespace.
+pub fn context_from_prefixes(prefixes: &PrefixMap) -> Value {
+    let mut context = Map::new();
+    for (prefix, address) in prefixes.iter() {
+        context.insert(prefix.to_owned(), Value::from(address));
+    }
+    Value::Object(context)
+}
+
+struct TermDefinition {
+    iri: String,
+    datatype: Option<String>,
+    language: Option<Option<String>>,
+}
+
+/// The term definitions of a local `@context`, used to compact IRIs and values.
+struct ActiveContext {
+    terms: BTreeMap<String, TermDefinition>,
+    vocab: Option<String>,
+}
+
+impl ActiveContext {
+    fn new(context: &Value) -> ActiveContext {
+        let mut active = ActiveContext {
+            terms: BTreeMap::new(),
+            vocab: None,
+        };
+        let contexts = match context {
+            Value::Array(contexts) => contexts.iter().collect(),
+            context => vec![context],
+        };
+        for context in contexts {
+            let definitions = match context.as_object() {
+                Some(definitions) => definitions,
+                None => continue,
+            };
+            if let Some(vocab) = definitions.get("@vocab").and_then(Value::as_str) {
+                active.vocab = Some(vocab.to_owned());
+            }
+            for (term, definition) in definitions.iter().filter(|(term, _)| !term.starts_with('@')) {
+                let definition = match definition {
+                    Value::String(iri) => TermDefinition {
+                        iri: iri.clone(),
+                        datatype: None,
+                        language: None,
+                    },
+                    Value::Object(definition) => TermDefinition {
+                        iri: match definition.get("@id").and_then(Value::as_str) {
+                            Some(iri) => iri.to_owned(),
+                            None => term.clone(),
+                        },
+                        datatype: definition.get("@type").and_then(Value::as_str).map(str::to_owned),
+                        language: definition
+                            .get("@language")
+                            .map(|language| language.as_str().map(str::to_owned)),
+                    },
+                    _ => continue,
+                };
+                active.terms.insert(term.clone(), definition);
+            }
+        }
+        // Definitions may themselves use compact IRIs
+        let expanded: Vec<(String, String, Option<String>)> = active
+            .terms
+            .iter()
+            .map(|(term, definition)| {
+                let datatype = definition.datatype.as_ref().map(|datatype| active.expand_iri(datatype));
+                (term.clone(), active.expand_iri(&definition.iri), datatype)
+            })
+            .collect();
+        for (term, iri, datatype) in expanded {
+            let definition = active.terms.get_mut(&term).unwrap();
+            definition.iri = iri;
+            definition.datatype = datatype;
+        }
+        active
+    }
+
+    fn expand_iri(&self, value: &str) -> String {
+        if value.starts_with('@') {
+            return value.to_owned();
+        }
+        if let Some(index) = value.find(':') {
+            let (prefix, suffix) = (&value[..index], &value[index + 1..]);
+            if !suffix.starts_with("//") {
+                if let Some(definition) = self.terms.get(prefix) {
+                    return format!("{}{}", definition.iri, suffix);
+                }
+            }
+            return value.to_owned();
+        }
+        match self.terms.get(value) {
+            Some(definition) => definition.iri.clone(),
+            None => value.to_owned(),
+        }
+    }
+
+    // A term with a plain definition for `iri`, for properties and types
+    fn term(&self, iri: &str) -> Option<&str> {
+        self.terms
+            .iter()
+            .find(|(_, definition)| definition.iri == iri && definition.datatype.is_none() && definition.language.is_none())
+            .map(|(term, _)| term.as_str())
+    }
+
+    fn compact_iri(&self, iri: &str, vocab: bool) -> String {
+        if vocab {
+            if let Some(term) = self.term(iri) {
+                return term.to_owned();
+            }
+            // Unless the suffix is a term already, which would then stand for two IRIs
+            if let Some(suffix) = self.vocab.as_ref().and_then(|vocab| iri.strip_prefix(vocab.as_str())) {
+                if !suffix.is_empty() && !suffix.contains(':') && !self.terms.contains_key(suffix) {
+                    return suffix.to_owned();
+                }
+            }
+        }
+        let curie = self
+            .terms
+            .iter()
+            .filter(|(term, definition)| {
+                !term.contains(':')
+                    && definition.iri.ends_with(['/', '#', ':'])
+                    && iri.len() > definition.iri.len()
+                    && iri.starts_with(definition.iri.as_str())
+            })
+            .max_by_key(|(_, definition)| definition.iri.len())
+            .map(|(term, definition)| format!("{}:{}", term, &iri[definition.iri.len()..]));
+        curie.unwrap_or_else(|| iri.to_owned())
+    }
+
+    // The term for a property, preferring one whose definition matches all `values`
+    fn compact_property(&self, iri: &str, values: &[Value]) -> (String, Option<&TermDefinition>) {
+        let matching = self.terms.iter().find(|(_, definition)| {
+            definition.iri == iri
+                && (definition.datatype.is_some() || definition.language.is_some())
+                && values.iter().all(|value| self.matches(definition, value))
+        });
+        match matching {
+            Some((term, definition)) => (term.clone(), Some(definition)),
+            None => (self.compact_iri(iri, true), None),
+        }
+    }
+
+    fn matches(&self, definition: &TermDefinition, value: &Value) -> bool {
+        match (&definition.datatype, &definition.language) {
+            (Some(datatype), _) if datatype == "@id" => value.get("@id").is_some(),
+            (Some(datatype), _) => value.get("@type").and_then(Value::as_str) == Some(datatype),
+            (None, Some(language)) => {
+                value.get("@value").is_some()
+                    && value.get("@type").is_none()
+                    && value.get("@language").and_then(Value::as_str) == language.as_deref()
+            }
+            (None, None) => true,
+        }
+    }
+
+    fn compact_value(&self, value: &Value, definition: Option<&TermDefinition>) -> Value {
+        if definition.is_some() {
+            // The value is implied by the term definition
+            return match value.get("@id") {
+                Some(id) if value.as_object().unwrap().len() == 1 => Value::from(self.compact_iri(id.as_str().unwrap(), false)),
+                Some(_) => self.compact_node(value),
+                None => value["@value"].clone(),
+            };
+        }
+        let object = value.as_object().unwrap();
+        if !object.contains_key("@value") {
+            // A node reference, or a node embedded by framing
+            return self.compact_node(value);
+        }
+        if object.len() == 1 {
+            return object["@value"].clone();
+        }
+        let mut compacted = object.clone();
+        if let Some(datatype) = object.get("@type") {
+            compacted.insert("@type".to_owned(), Value::from(self.compact_iri(datatype.as_str().unwrap(), true)));
+        }
+        Value::Object(compacted)
+    }
+
+    fn compact_node(&self, node: &Value) -> Value {
+        let mut compacted = Map::new();
+        for (key, values) in node.as_object().unwrap() {
+            let values = values.as_array();
+            let (key, value) = match key.as_str() {
+                "@id" => (key.clone(), Value::from(self.compact_iri(node["@id"].as_str().unwrap(), false))),
+                "@type" => {
+                    let types = values.unwrap().iter().map(|t| Value::from(self.compact_iri(t.as_str().unwrap(), true)));
+                    (key.clone(), Value::Array(types.collect()))
+                }
+                "@graph" => (key.clone(), Value::Array(values.unwrap().iter().map(|node| self.compact_node(node)).collect())),
+                property => {
+                    let values = values.unwrap();
+                    let (term, definition) = self.compact_property(property, values);
+                    (term, Value::Array(values.iter().map(|value| self.compact_value(value, definition)).collect()))
+                }
+            };
+            let value = match value {
+                Value::Array(mut values) if values.len() == 1 && key != "@graph" => values.remove(0),
+                value => value,
+            };
+            compacted.insert(key, value);
+        }
+        Value::Object(compacted)
+    }
+}
+
+/// Compacted JSON-LD of `quads` against `context`, with a top level `@graph` unless there is a single node.
+pub fn compact<Q>(quads: Q, context: &Value) -> Value
+where
+    Q: IntoIterator<Item = Quad>,
+{
+    match expand(quads) {
+        Value::Array(nodes) => compact_nodes(&nodes, context, context),
+        _ => unreachable!(),
+    }
+}
+
+/// Compacted JSON-LD of expanded `nodes`, using the term definitions of `definitions`, which is
+/// `context` with any context IRIs replaced by the contexts they refer to.
+pub(crate) fn compact_nodes(nodes: &[Value], context: &Value, definitions: &Value) -> Value {
+    let active = ActiveContext::new(definitions);
+    let nodes: Vec<Value> = nodes.iter().map(|node| active.compact_node(node)).collect();
+    let mut document = Map::new();
+    document.insert("@context".to_owned(), context.clone());
+    match nodes.len() {
+        1 => document.extend(nodes.into_iter().next().unwrap().as_object().unwrap().clone()),
+        _ => {
+            document.insert("@graph".to_owned(), Value::Array(nodes));
+        }
+    }
+    Value::Object(document)
+}
+
+/// Compacted JSON-LD of `quads` with a context made of `prefixes`.
+pub fn compact_with_prefixes<Q>(quads: Q, prefixes: &PrefixMap) -> Value
+where
+    Q: IntoIterator<Item = Quad>,
+{
+    compact(quads, &context_from_prefixes(prefixes))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::jsonld_serialize::{compact, compact_with_prefixes, expand};
+    use crate::namespace::{PrefixMap, FOAF, RDF, XSD};
+    use crate::quad::Quad;
+    use crate::term::{BlankNode, Identifier, Literal, IRI};
+    use serde_json::json;
+
+    fn quads() -> Vec<Quad> {
+        let iddan = IRI::new("http://example.com#iddan");
+        let graph = Some(Identifier::IRI(IRI::new("http://example.com#people")));
+        vec![
+            Quad::new(&iddan, RDF.iri("type"), FOAF.iri("Person"), None),
+            Quad::new(&iddan, FOAF.iri("name"), Literal::new("Iddan", None, Some("en".to_owned())), None),
+            Quad::new(&iddan, FOAF.iri("age"), Literal::new("20", XSD.iri("integer"), None), graph.clone()),
+            Quad::new(&iddan, FOAF.iri("knows"), BlankNode::from_value("b0"), graph),
+        ]
+    }
+
+    #[test]
+    fn test_expand() {
+        assert_eq!(
+            expand(quads()),
+            json!([{
+                "@id": "http://example.com#iddan",
+                "@type": ["http://xmlns.com/foaf/0.1/Person"],
+                "http://xmlns.com/foaf/0.1/name": [{ "@value": "Iddan", "@language": "en" }],
+            }, {
+                "@id": "http://example.com#people",
+                "@graph": [{
+                    "@id": "http://example.com#iddan",
+                    "http://xmlns.com/foaf/0.1/age": [{
+                        "@value": "20",
+                        "@type": "http://www.w3.org/2001/XMLSchema#integer"
+                    }],
+                    "http://xmlns.com/foaf/0.1/knows": [{ "@id": "_:b0" }],
+                }]
+            }])
+        );
+    }
+
+    #[test]
+    fn test_compact() {
+        let mut prefixes = PrefixMap::new();
+        prefixes.insert("foaf", FOAF.iri("").value);
+        prefixes.insert("ex", "http://example.com#");
+        let compacted = compact_with_prefixes(quads(), &prefixes);
+        assert_eq!(compacted["@context"]["foaf"], json!("http://xmlns.com/foaf/0.1/"));
+        assert_eq!(compacted["@graph"][0]["@type"], json!("foaf:Person"));
+        assert_eq!(compacted["@graph"][1]["@id"], json!("ex:people"));
+        let context = json!({
+            "foaf": "http://xmlns.com/foaf/0.1/",
+            "xsd": "http://www.w3.org/2001/XMLSchema#",
+            "Person": "foaf:Person",
+            "age": { "@id": "foaf:age", "@type": "xsd:integer" },
+            "knows": { "@id": "foaf:knows", "@type": "@id" },
+        });
+        let people: Vec<Quad> = quads().into_iter().filter(|quad| quad.context.is_some()).collect();
+        let compacted = compact(people, &context);
+        assert_eq!(compacted["@id"], json!("http://example.com#people"));
+        assert_eq!(compacted["@graph"][0]["age"], json!("20"));
+        assert_eq!(compacted["@graph"][0]["knows"], json!("_:b0"));
+        assert_eq!(compact(vec![], &context), json!({ "@context": context, "@graph": [] }));
+        // A vocabulary relative IRI that is also a term is written in full
+        let context = json!({ "@vocab": "http://schema.org/", "name": "http://xmlns.com/foaf/0.1/name" });
+        let iddan = IRI::new("http://example.com#iddan");
+        let names = vec![
+            Quad::new(&iddan, IRI::new("http://schema.org/name"), Literal::new("Iddan", None, None), None),
+            Quad::new(&iddan, FOAF.iri("name"), Literal::new("Iddan Aaronsohn", None, None), None),
+        ];
+        let compacted = compact(names, &context);
+        assert_eq!(compacted["name"], json!("Iddan Aaronsohn"));
+        assert_eq!(compacted["http://schema.org/name"], json!("Iddan"));
+    }
+}
@@ -1,6 +1,8 @@
 #![crate_type = "lib"]
 #![crate_name = "minerva"]
 
+pub mod blank_node_id;
+pub mod canonicalization;
 pub mod dataset;
 pub mod namespace;
 pub mod quad;
@@ -9,6 +11,29 @@ pub mod server_http;
 pub mod term;
 pub mod nquads_serialize;
 pub mod nquads_deserialize;
+pub mod nquads_stream;
+pub mod ntriples_serialize;
+pub mod ntriples_deserialize;
+pub mod turtle_deserialize;
+pub mod turtle_serialize;
+pub mod trig_deserialize;
+pub mod trig_serialize;
+pub mod jsonld_serialize;
+pub mod jsonld_deserialize;
+pub mod jsonld_frame;
+pub mod cbor_serialize;
+pub mod cbor_deserialize;
+pub mod rdfxml_deserialize;
+pub mod rdfxml_serialize;
+pub mod format_registry;
+pub mod compression;
+pub mod rdf_patch;
+pub mod csvw;
+pub mod rml;
+pub mod r2rml;
+pub mod html_deserialize;
+pub mod parse_error;
+pub mod vocabulary;
 mod write_service;
 mod read_service;
 mod test_set;
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use crate::term::*;
 
 pub struct Namespace<A>
@@ -59,3 +60,120 @@ pub static DCTERMS: Namespace<&'static str> = Namespace {
 pub static VOID: Namespace<&'static str> = Namespace {
     address: "http://rdfs.org/ns/void#",
 };
+pub static RR: Namespace<&'static str> = Namespace {
+    address: "http://www.w3.org/ns/r2rml#",
+};
+pub static RML: Namespace<&'static str> = Namespace {
+    address: "http://semweb.mmlab.be/ns/rml#",
+};
+pub static QL: Namespace<&'static str> = Namespace {
+    address: "http://semweb.mmlab.be/ns/ql#",
+};
+
+/// Registry of namespace prefixes, used to compact IRIs to CURIEs and back.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrefixMap {
+    prefixes: HashMap<String, String>,
+}
+
+impl PrefixMap {
+    pub fn new() -> PrefixMap {
+        PrefixMap {
+            prefixes: HashMap::new(),
+        }
+    }
+
+    pub fn insert<P, A>(&mut self, prefix: P, address: A)
+    where
+        P: Into<String>,
+        A: Into<String>,
+    {
+        self.prefixes.insert(prefix.into(), address.into());
+    }
+
+    pub fn remove(&mut self, prefix: &str) -> Option<String> {
+        self.prefixes.remove(prefix)
+    }
+
+    pub fn get(&self, prefix: &str) -> Option<&str> {
+        self.prefixes.get(prefix).map(|address| address.as_str())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.prefixes
+            .iter()
+            .map(|(prefix, address)| (prefix.as_str(), address.as_str()))
+    }
+
+    /// Compacts `iri` to `prefix:local` using the longest matching namespace.
+    pub fn compact(&self, iri: &IRI) -> Option<String> {
+        self.prefixes
+            .iter()
+            .filter(|(_, address)| iri.value.starts_with(address.as_str()))
+            .max_by(|(a_prefix, a), (b_prefix, b)| a.len().cmp(&b.len()).then(b_prefix.cmp(a_prefix)))
+            .map(|(prefix, address)| format!("{}:{}", prefix, &iri.value[address.len()..]))
+    }
+
+    /// Expands a `prefix:local` CURIE to an IRI.
+    pub fn expand(&self, curie: &str) -> Result<IRI, String> {
+        match curie.find(':') {
+            Some(index) => {
+                let (prefix, local) = (&curie[..index], &curie[index + 1..]);
+                match self.prefixes.get(prefix) {
+                    Some(address) => Ok(IRI::new(format!("{}{}", address, local))),
+                    None => Err(format!("Unknown prefix {}", prefix)),
+                }
+            }
+            None => Err(format!("Not a CURIE: {}", curie)),
+        }
+    }
+
+    /// Expands `value` if it is a CURIE with a known prefix, otherwise takes it as an IRI.
+    pub fn resolve(&self, value: &str) -> IRI {
+        if value.starts_with('<') && value.ends_with('>') {
+            return IRI::new(&value[1..value.len() - 1]);
+        }
+        self.expand(value).unwrap_or_else(|_| IRI::new(value))
+    }
+}
+
+impl Default for PrefixMap {
+    fn default() -> PrefixMap {
+        let mut prefix_map = PrefixMap::new();
+        for (prefix, namespace) in &[
+            ("rdf", &RDF),
+            ("rdfs", &RDFS),
+            ("owl", &OWL),
+            ("xsd", &XSD),
+            ("skos", &SKOS),
+            ("doap", &DOAP),
+            ("foaf", &FOAF),
+            ("dc", &DC),
+            ("dcterms", &DCTERMS),
+            ("void", &VOID),
+        ] {
+            prefix_map.insert(*prefix, namespace.address);
+        }
+        prefix_map
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::namespace::{PrefixMap, FOAF};
+    use crate::term::IRI;
+
+    #[test]
+    fn test_prefix_map() {
+        let mut prefix_map = PrefixMap::default();
+        assert_eq!(prefix_map.compact(&FOAF.iri("knows")), Some("foaf:knows".to_owned()));
+        assert_eq!(prefix_map.expand("foaf:knows"), Ok(FOAF.iri("knows")));
+        assert!(prefix_map.expand("ex:iddan").is_err());
+        prefix_map.insert("ex", "http://example.com#");
+        let iddan = IRI::new("http://example.com#iddan");
+        assert_eq!(prefix_map.compact(&iddan), Some("ex:iddan".to_owned()));
+        assert_eq!(prefix_map.resolve("ex:iddan"), iddan);
+        assert_eq!(prefix_map.resolve("<http://example.com#iddan>"), iddan);
+        assert_eq!(prefix_map.compact(&IRI::new("http://other.com/")), None);
+    }
+}
@@ -1,142 +1,363 @@
-use crate::quad::{Context, Object, Predicate, Quad, Subject};
+use crate::blank_node_id::{BlankNodeIdGenerator, BlankNodeScope};
+use crate::parse_error::{ParseError, ParseErrorKind};
+use crate::quad::{Context, Quad};
 use crate::term::{BlankNode, Identifier, Literal, Node, IRI};
-use std::iter::Peekable;
+use std::collections::VecDeque;
 use std::str::Chars;
 
-fn deserialize_blank_node(
-    chars: &mut Peekable<impl Iterator<Item = char>>,
-) -> Result<BlankNode, String> {
-    for expected_char in "_:".chars() {
-        match chars.next() {
-            Some(c) if c != expected_char => {
-                return Err(format!("Unexpected character {}", c));
-            }
-            None => {
-                return Err("Unexpected EOF".to_owned());
+// Parser for N-Quads (https://www.w3.org/TR/n-quads/).
+// The term parsers are shared with the other line based and Turtle family formats.
+
+const SNIPPET_LENGTH: usize = 20;
+
+pub struct Input<I: Iterator<Item = char>> {
+    chars: I,
+    lookahead: VecDeque<char>,
+    // The consumed part of the current line, for error snippets
+    line_start: VecDeque<char>,
+    // Position of the last consumed character
+    line: u32,
+    column: u32,
+    offset: usize,
+    last_length: usize,
+    // A consumed line feed only moves the position when the next character is consumed,
+    // so errors about it point at the end of its line
+    after_newline: bool,
+}
+
+impl<I: Iterator<Item = char>> Input<I> {
+    pub fn new(chars: I) -> Input<I> {
+        Input::at(chars, 1, 0)
+    }
+
+    /// Starts reading at `line` and byte `offset` of a larger input.
+    pub fn at(chars: I, line: u32, offset: usize) -> Input<I> {
+        Input {
+            chars,
+            lookahead: VecDeque::new(),
+            line_start: VecDeque::new(),
+            line,
+            column: 0,
+            offset,
+            last_length: 0,
+            after_newline: false,
+        }
+    }
+
+    /// Line of the next character.
+    pub fn line(&self) -> u32 {
+        if self.after_newline {
+            self.line + 1
+        } else {
+            self.line
+        }
+    }
+
+    pub fn peek(&mut self) -> Option<char> {
+        self.peek_nth(0)
+    }
+
+    pub fn peek_nth(&mut self, n: usize) -> Option<char> {
+        while self.lookahead.len() <= n {
+            match self.chars.next() {
+                Some(c) => self.lookahead.push_back(c),
+                None => return None,
             }
-            _ => {}
         }
+        self.lookahead.get(n).cloned()
     }
-    let mut accumulator = String::new();
-    loop {
-        match chars.next() {
-            None if accumulator.is_empty() => {
-                return Err("Unexpected EOF".to_owned());
+
+    /// Builds an error of `kind` at the current position.
+    pub fn error(&mut self, kind: ParseErrorKind) -> ParseError {
+        let mut snippet: String = self.line_start.iter().collect();
+        let following = if self.after_newline { 0 } else { SNIPPET_LENGTH };
+        for n in 0..following {
+            match self.peek_nth(n) {
+                Some('\n') | Some('\r') | None => break,
+                Some(c) => snippet.push(c),
             }
-            Some(' ') | None => {
-                return Ok(BlankNode::from_value(accumulator));
+        }
+        ParseError::new(kind, self.line, self.column, self.offset - self.last_length, snippet)
+    }
+
+    /// Builds an error for a character, or the end of input, that can not appear at this point.
+    pub fn unexpected(&mut self, c: Option<char>) -> ParseError {
+        match c {
+            Some(c) => self.error(ParseErrorKind::UnexpectedCharacter(c)),
+            None => self.error(ParseErrorKind::UnexpectedEOF),
+        }
+    }
+
+    /// Consumes the next character and builds an error for it.
+    pub fn reject(&mut self) -> ParseError {
+        let c = self.next();
+        self.unexpected(c)
+    }
+
+    pub fn expect(&mut self, expected: char) -> Result<(), ParseError> {
+        match self.next() {
+            Some(c) if c == expected => Ok(()),
+            c => Err(self.unexpected(c)),
+        }
+    }
+
+    pub fn skip_whitespace(&mut self) {
+        while let Some(' ') | Some('\t') = self.peek() {
+            self.next();
+        }
+    }
+
+    pub fn skip_comment(&mut self) {
+        if self.peek() == Some('#') {
+            while let Some(c) = self.peek() {
+                if c == '\n' || c == '\r' {
+                    break;
+                }
+                self.next();
             }
+        }
+    }
+}
+
+impl<I: Iterator<Item = char>> Iterator for Input<I> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        let c = match self.lookahead.pop_front() {
+            Some(c) => Some(c),
+            None => self.chars.next(),
+        };
+        if c.is_some() && self.after_newline {
+            self.line += 1;
+            self.column = 0;
+            self.line_start.clear();
+            self.after_newline = false;
+        }
+        match c {
             Some(c) => {
-                accumulator.push(c);
+                self.column += 1;
+                self.offset += c.len_utf8();
+                self.last_length = c.len_utf8();
+                if c == '\n' {
+                    self.after_newline = true;
+                } else {
+                    if self.line_start.len() == SNIPPET_LENGTH {
+                        self.line_start.pop_front();
+                    }
+                    self.line_start.push_back(c);
+                }
             }
+            None => self.last_length = 0,
         }
+        c
     }
 }
 
-pub fn deserialize_iri(chars: &mut Peekable<impl Iterator<Item = char>>) -> Result<IRI, String> {
-    match chars.next() {
-        Some(c) if c != '<' => {
-            return Err(format!("Unexpected character {}", c));
+pub(crate) fn is_pn_chars_base(c: char) -> bool {
+    matches!(
+        c,
+        'A'..='Z'
+            | 'a'..='z'
+            | '\u{00C0}'..='\u{00D6}'
+            | '\u{00D8}'..='\u{00F6}'
+            | '\u{00F8}'..='\u{02FF}'
+            | '\u{0370}'..='\u{037D}'
+            | '\u{037F}'..='\u{1FFF}'
+            | '\u{200C}'..='\u{200D}'
+            | '\u{2070}'..='\u{218F}'
+            | '\u{2C00}'..='\u{2FEF}'
+            | '\u{3001}'..='\u{D7FF}'
+            | '\u{F900}'..='\u{FDCF}'
+            | '\u{FDF0}'..='\u{FFFD}'
+            | '\u{10000}'..='\u{EFFFF}'
+    )
+}
+
+// As in Turtle, N-Triples additionally allows ':' in blank node labels
+pub(crate) fn is_pn_chars_u(c: char) -> bool {
+    is_pn_chars_base(c) || c == '_'
+}
+
+pub(crate) fn is_pn_chars(c: char) -> bool {
+    match c {
+        '-' | '0'..='9' | '\u{00B7}' | '\u{0300}'..='\u{036F}' | '\u{203F}'..='\u{2040}' => true,
+        c => is_pn_chars_u(c),
+    }
+}
+
+fn deserialize_hex(chars: &mut Input<impl Iterator<Item = char>>, length: usize) -> Result<char, ParseError> {
+    let mut code = 0;
+    for _ in 0..length {
+        match chars.next() {
+            Some(c) if c.is_ascii_hexdigit() => code = code * 16 + c.to_digit(16).unwrap(),
+            c => return Err(chars.unexpected(c)),
         }
-        None => {
-            return Err("Unexpected EOF".to_owned());
+    }
+    std::char::from_u32(code).ok_or_else(|| chars.error(ParseErrorKind::InvalidCodePoint(code)))
+}
+
+/// Parses the part of a `UCHAR` following the backslash.
+pub(crate) fn deserialize_uchar(chars: &mut Input<impl Iterator<Item = char>>) -> Result<char, ParseError> {
+    match chars.next() {
+        Some('u') => deserialize_hex(chars, 4),
+        Some('U') => deserialize_hex(chars, 8),
+        c => Err(chars.unexpected(c)),
+    }
+}
+
+/// Parses the part of an `ECHAR` or `UCHAR` following the backslash.
+pub(crate) fn deserialize_escape(chars: &mut Input<impl Iterator<Item = char>>) -> Result<char, ParseError> {
+    match chars.peek() {
+        Some('u') | Some('U') => deserialize_uchar(chars),
+        _ => match chars.next() {
+            Some('t') => Ok('\t'),
+            Some('b') => Ok('\u{8}'),
+            Some('n') => Ok('\n'),
+            Some('r') => Ok('\r'),
+            Some('f') => Ok('\u{c}'),
+            Some('"') => Ok('"'),
+            Some('\'') => Ok('\''),
+            Some('\\') => Ok('\\'),
+            c => Err(chars.unexpected(c)),
+        },
+    }
+}
+
+pub(crate) fn is_absolute_iri(value: &str) -> bool {
+    match value.find(':') {
+        Some(index) if index > 0 => {
+            let scheme = &value[..index];
+            scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '-' || c == '.')
         }
-        _ => {}
+        _ => false,
     }
+}
+
+/// Parses an `IRIREF` without checking that it is absolute.
+pub(crate) fn deserialize_iri_ref(chars: &mut Input<impl Iterator<Item = char>>) -> Result<String, ParseError> {
+    chars.expect('<')?;
     let mut accumulator = String::new();
     loop {
         match chars.next() {
-            Some('>') => {
-                return Ok(IRI::new(accumulator));
-            }
-            Some(c) => {
-                accumulator.push(c);
+            Some('>') => return Ok(accumulator),
+            Some('\\') => {
+                let c = deserialize_uchar(chars)?;
+                match c {
+                    '\u{0}'..=' ' | '<' | '>' | '"' | '{' | '}' | '|' | '^' | '`' | '\\' => {
+                        return Err(chars.error(ParseErrorKind::InvalidIRICharacter(c)));
+                    }
+                    c => accumulator.push(c),
+                }
             }
-            None => {
-                return Err("Unexpected EOF".to_owned());
+            Some(c @ '\u{0}'..=' ') | Some(c @ '<') | Some(c @ '"') | Some(c @ '{') | Some(c @ '}')
+            | Some(c @ '|') | Some(c @ '^') | Some(c @ '`') => {
+                return Err(chars.error(ParseErrorKind::InvalidIRICharacter(c)));
             }
+            Some(c) => accumulator.push(c),
+            None => return Err(chars.unexpected(None)),
         }
     }
 }
 
-fn deserialize_datatype(chars: &mut Peekable<impl Iterator<Item = char>>) -> Result<IRI, String> {
-    for expected_char in "^^".chars() {
-        let c = chars.next();
-        match c {
-            Some(c) if c == expected_char => {}
-            Some(c) => {
-                return Err(format!("Unexpected character {}", c));
+pub fn deserialize_iri(chars: &mut Input<impl Iterator<Item = char>>) -> Result<IRI, ParseError> {
+    let value = deserialize_iri_ref(chars)?;
+    if !is_absolute_iri(&value) {
+        return Err(chars.error(ParseErrorKind::RelativeIRI(value)));
+    }
+    Ok(IRI::new(value))
+}
+
+pub fn deserialize_blank_node(chars: &mut Input<impl Iterator<Item = char>>) -> Result<BlankNode, ParseError> {
+    deserialize_blank_node_label(chars, true)
+}
+
+/// Parses a `BLANK_NODE_LABEL`, with the colons N-Triples allows in it if `colons` is set.
+pub(crate) fn deserialize_blank_node_label(
+    chars: &mut Input<impl Iterator<Item = char>>,
+    colons: bool,
+) -> Result<BlankNode, ParseError> {
+    let is_label_char = |c: char| is_pn_chars(c) || (colons && c == ':');
+    chars.expect('_')?;
+    chars.expect(':')?;
+    let mut accumulator = String::new();
+    match chars.next() {
+        Some(c) if is_pn_chars_u(c) || c.is_ascii_digit() || (colons && c == ':') => accumulator.push(c),
+        c => return Err(chars.unexpected(c)),
+    }
+    loop {
+        match chars.peek() {
+            Some(c) if is_label_char(c) => {
+                accumulator.push(c);
+                chars.next();
             }
-            None => {
-                return Err("Unexpected EOF".to_owned());
+            Some('.') => {
+                // A label can not end with a dot, so dots only belong to it if followed by more of it
+                let mut dots = 1;
+                while chars.peek_nth(dots) == Some('.') {
+                    dots += 1;
+                }
+                match chars.peek_nth(dots) {
+                    Some(c) if is_label_char(c) => {
+                        for _ in 0..dots {
+                            accumulator.push('.');
+                            chars.next();
+                        }
+                    }
+                    _ => return Ok(BlankNode::from_value(accumulator)),
+                }
             }
+            _ => return Ok(BlankNode::from_value(accumulator)),
         }
     }
-    return deserialize_iri(chars);
 }
 
-fn deserialize_language(
-    chars: &mut Peekable<impl Iterator<Item = char>>,
-) -> Result<String, String> {
-    let c = chars.next();
-    match c {
-        Some('@') => {}
-        Some(c) => {
-            return Err(format!("Unexpected character {}", c));
-        }
-        None => {
-            return Err("Unexpected EOF".to_owned());
-        }
-    };
+pub fn deserialize_datatype(chars: &mut Input<impl Iterator<Item = char>>) -> Result<IRI, ParseError> {
+    chars.expect('^')?;
+    chars.expect('^')?;
+    deserialize_iri(chars)
+}
+
+pub fn deserialize_language(chars: &mut Input<impl Iterator<Item = char>>) -> Result<String, ParseError> {
+    chars.expect('@')?;
     let mut accumulator = String::new();
+    let mut subtag_length = 0;
     loop {
         match chars.peek() {
-            Some(' ') | Some('.') | None => return Ok(accumulator),
-            Some(_) => {
-                accumulator.push(chars.next().unwrap());
+            Some(c) if c.is_ascii_alphabetic() || (c.is_ascii_digit() && accumulator.contains('-')) => {
+                accumulator.push(c);
+                subtag_length += 1;
+                chars.next();
+            }
+            Some('-') if subtag_length > 0 => {
+                accumulator.push('-');
+                subtag_length = 0;
+                chars.next();
             }
+            _ if subtag_length == 0 => return Err(chars.reject()),
+            _ => return Ok(accumulator),
         }
     }
 }
 
-fn deserialize_literal_value(
-    chars: &mut Peekable<impl Iterator<Item = char>>,
-) -> Result<String, String> {
-    match chars.next() {
-        Some('"') => {}
-        Some(c) => {
-            return Err(format!("Unexpected character {}", c));
-        }
-        None => {
-            return Err("Unexpected EOF".to_owned());
-        }
-    };
+/// Parses a `STRING_LITERAL_QUOTE`.
+pub fn deserialize_literal_value(chars: &mut Input<impl Iterator<Item = char>>) -> Result<String, ParseError> {
+    chars.expect('"')?;
     let mut accumulator = String::new();
-    let mut escaped = false;
     loop {
         match chars.next() {
-            Some('\\') => {
-                escaped = !escaped;
-            }
-            Some('"') if !escaped => {
-                return Ok(accumulator);
-            }
-            Some(c) => {
-                accumulator.push(c);
-                if escaped {
-                    escaped = false;
-                }
-            }
-            None => return Err("Unexpected EOF".to_owned()),
+            Some('"') => return Ok(accumulator),
+            Some('\\') => accumulator.push(deserialize_escape(chars)?),
+            c @ Some('\n') | c @ Some('\r') | c @ None => return Err(chars.unexpected(c)),
+            Some(c) => accumulator.push(c),
         }
     }
 }
 
-fn deserialize_literal(
-    chars: &mut Peekable<impl Iterator<Item = char>>,
-) -> Result<Literal, String> {
+pub fn deserialize_literal(chars: &mut Input<impl Iterator<Item = char>>) -> Result<Literal, ParseError> {
     let value = deserialize_literal_value(chars)?;
-    // TODO make functions do this:
     match chars.peek() {
         Some('^') => {
             let datatype = deserialize_datatype(chars)?;
@@ -150,9 +371,7 @@ fn deserialize_literal(
     }
 }
 
-pub fn deserialize_identifier(
-    chars: &mut Peekable<impl Iterator<Item = char>>,
-) -> Result<Identifier, String> {
+pub fn deserialize_identifier(chars: &mut Input<impl Iterator<Item = char>>) -> Result<Identifier, ParseError> {
     match chars.peek() {
         Some('<') => {
             let iri = deserialize_iri(chars)?;
@@ -162,12 +381,11 @@ pub fn deserialize_identifier(
             let blank_node = deserialize_blank_node(chars)?;
             Ok(Identifier::BlankNode(blank_node))
         }
-        Some(c) => Err(format!("Unexpected character {}", c)),
-        None => Err("Unexpected EOF".to_owned()),
+        _ => Err(chars.reject()),
     }
 }
 
-pub fn deserialize_node(chars: &mut Peekable<impl Iterator<Item = char>>) -> Result<Node, String> {
+pub fn deserialize_node(chars: &mut Input<impl Iterator<Item = char>>) -> Result<Node, ParseError> {
     match chars.peek() {
         Some('<') => {
             let iri = deserialize_iri(chars)?;
@@ -181,122 +399,227 @@ pub fn deserialize_node(chars: &mut Peekable<impl Iterator<Item = char>>) -> Res
             let blank_node = deserialize_blank_node(chars)?;
             Ok(Node::BlankNode(blank_node))
         }
-        Some(c) => Err(format!("Unexpected character {}", c)),
-        None => Err("Unexpected EOF".to_owned()),
+        _ => Err(chars.reject()),
     }
 }
 
 pub struct NQuadsDeserializer<I: Iterator<Item = char>> {
-    chars: Peekable<I>,
-    column: u32,
-    line: u32,
+    chars: Input<I>,
+    blank_nodes: BlankNodeScope,
+    // Context of statements without a graph label
+    graph: Context,
+    graph_labels: bool,
 }
 
-impl<'a, I: Iterator<Item = char>> NQuadsDeserializer<I> {
-    fn get_next(&mut self) -> Result<Option<Quad>, String> {
-        let mut subject: Option<Subject> = None;
-        let mut predicate: Option<Predicate> = None;
-        let mut object: Option<Object> = None;
-        let mut context: Option<Context> = None;
-        loop {
-            self.column += 1;
+impl<I: Iterator<Item = char>> NQuadsDeserializer<I> {
+    pub fn new(chars: I, blank_nodes: BlankNodeScope) -> NQuadsDeserializer<I> {
+        NQuadsDeserializer::at(Input::new(chars), blank_nodes)
+    }
 
+    pub fn at(chars: Input<I>, blank_nodes: BlankNodeScope) -> NQuadsDeserializer<I> {
+        NQuadsDeserializer {
+            chars,
+            blank_nodes,
+            graph: None,
+            graph_labels: true,
+        }
+    }
+
+    /// Puts statements without a graph label into `graph`. When `graph_labels` is false
+    /// statements with a graph label are rejected, as in N-Triples.
+    pub fn with_graph(mut self, graph: Context, graph_labels: bool) -> NQuadsDeserializer<I> {
+        self.graph = graph;
+        self.graph_labels = graph_labels;
+        self
+    }
+
+    pub fn into_blank_nodes(self) -> BlankNodeScope {
+        self.blank_nodes
+    }
+
+    fn scope_identifier(&mut self, identifier: Identifier) -> Identifier {
+        match identifier {
+            Identifier::BlankNode(blank_node) => {
+                Identifier::BlankNode(self.blank_nodes.labeled(&blank_node.value))
+            }
+            identifier => identifier,
+        }
+    }
+
+    fn scope_node(&mut self, node: Node) -> Node {
+        match node {
+            Node::BlankNode(blank_node) => Node::BlankNode(self.blank_nodes.labeled(&blank_node.value)),
+            node => node,
+        }
+    }
+
+    // Skips whitespace, comments and line ends up to the next statement
+    fn skip_blank(&mut self) {
+        loop {
+            self.chars.skip_whitespace();
+            self.chars.skip_comment();
             match self.chars.peek() {
-                Some('\n') => {
-                    if subject.is_some() {
-                        return Err("Unexpected character \\n".to_owned());
-                    }
-                    self.line += 1;
-                    self.column = 0;
-                    self.chars.next();
-                    continue;
-                }
-                Some(' ') => {
-                    self.column += 1;
+                Some('\n') | Some('\r') => {
                     self.chars.next();
                 }
-                Some('.') => {
-                    if object.is_some() {
-                        self.chars.next();
-                        if context.is_some() {
-                            return Ok(Some(Quad::new(
-                                subject.unwrap(),
-                                predicate.unwrap(),
-                                object.unwrap(),
-                                context.unwrap(),
-                            )));
-                        }
-                        return Ok(Some(Quad::new(
-                            subject.unwrap(),
-                            predicate.unwrap(),
-                            object.unwrap(),
-                            None,
-                        )));
-                    }
-                    return Err("Unexpected character .".to_owned());
-                }
-                Some(_) => {
-                    if subject.is_none() {
-                        let identifier = deserialize_identifier(&mut self.chars)?;
-                        subject = Some(identifier);
-                    } else if predicate.is_none() {
-                        let iri = deserialize_iri(&mut self.chars)?;
-                        predicate = Some(iri);
-                    } else if object.is_none() {
-                        let node = deserialize_node(&mut self.chars)?;
-                        object = Some(node);
-                    } else {
-                        let identifier = deserialize_identifier(&mut self.chars)?;
-                        context = Some(Some(identifier));
-                    }
-                }
-                None => return Ok(None),
+                _ => return,
             }
         }
     }
+
+    fn get_next(&mut self) -> Result<Option<Quad>, ParseError> {
+        self.skip_blank();
+        if self.chars.peek().is_none() {
+            return Ok(None);
+        }
+        let identifier = deserialize_identifier(&mut self.chars)?;
+        let subject = self.scope_identifier(identifier);
+        self.chars.skip_whitespace();
+        let predicate = deserialize_iri(&mut self.chars)?;
+        self.chars.skip_whitespace();
+        let node = deserialize_node(&mut self.chars)?;
+        let object = self.scope_node(node);
+        self.chars.skip_whitespace();
+        let context: Context = match self.chars.peek() {
+            Some('.') => self.graph.clone(),
+            Some('<') | Some('_') if !self.graph_labels => {
+                self.chars.next();
+                return Err(self.chars.error(ParseErrorKind::UnexpectedGraphLabel));
+            }
+            _ => {
+                let identifier = deserialize_identifier(&mut self.chars)?;
+                self.chars.skip_whitespace();
+                Some(self.scope_identifier(identifier))
+            }
+        };
+        self.chars.expect('.')?;
+        self.chars.skip_whitespace();
+        self.chars.skip_comment();
+        match self.chars.peek() {
+            Some('\n') | Some('\r') | None => Ok(Some(Quad::new(subject, predicate, object, context))),
+            _ => Err(self.chars.reject()),
+        }
+    }
 }
 
 impl<I: Iterator<Item = char>> Iterator for NQuadsDeserializer<I> {
-    type Item = Result<Quad, String>;
+    type Item = Result<Quad, ParseError>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let result = self.get_next();
-
-        if result.is_ok() {
-            let value = result.unwrap();
-            if value.is_none() {
-                None
-            } else {
-                Some(Ok(value.unwrap()))
+        self.skip_blank();
+        let line = self.chars.line();
+        match self.get_next() {
+            Ok(quad) => quad.map(Ok),
+            Err(error) => {
+                // Resynchronise at the line after the invalid statement
+                while self.chars.line() == line && self.chars.next().is_some() {}
+                Some(Err(error))
             }
-        } else {
-            let error = result.unwrap_err();
-            let wrapped_err = format!("At line {} column {}: {}", self.line, self.column, error);
-            Some(Err(wrapped_err))
         }
     }
 }
 
-pub fn deserialize<'a>(nquads: &'a str) -> NQuadsDeserializer<Chars<'a>> {
-    return NQuadsDeserializer {
-        column: 0,
-        line: 1,
-        chars: nquads.chars().peekable(),
-    };
+pub fn deserialize(nquads: &str) -> NQuadsDeserializer<Chars<'_>> {
+    NQuadsDeserializer::new(nquads.chars(), BlankNodeScope::default())
+}
+
+pub fn deserialize_with<G>(nquads: &str, generator: G) -> NQuadsDeserializer<Chars<'_>>
+where
+    G: BlankNodeIdGenerator + 'static,
+{
+    NQuadsDeserializer::new(nquads.chars(), BlankNodeScope::new(generator))
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::nquads_deserialize::deserialize;
+    use crate::blank_node_id::Seeded;
+    use crate::nquads_deserialize::{deserialize, deserialize_with};
+    use crate::parse_error::{ParseError, ParseErrorKind};
     use crate::quad::Quad;
+    use crate::term::{Literal, Node};
     use crate::test_set;
     use std::collections::HashSet;
+    use std::fs;
     #[test]
     fn test_deserialize() {
         let nquads = test_set::get_nquads_string();
         let quads_result: Result<HashSet<Quad>, _> = deserialize(&nquads).collect();
         let quads = quads_result.unwrap();
-        let set = test_set::get_quads();
-        assert_eq!(quads, set);
+        assert!(test_set::is_test_set(&quads));
+    }
+
+    #[test]
+    fn test_deserialize_with_generator() {
+        let nquads = test_set::get_nquads_string();
+        let first: Result<HashSet<Quad>, _> = deserialize_with(&nquads, Seeded::new(7)).collect();
+        let second: Result<HashSet<Quad>, _> = deserialize_with(&nquads, Seeded::new(7)).collect();
+        let first = first.unwrap();
+        assert_eq!(first, second.unwrap());
+        assert_eq!(first.len(), test_set::get_quads().len());
+        assert_ne!(first, test_set::get_quads());
+    }
+
+    #[test]
+    fn test_deserialize_escapes_and_comments() {
+        let nquads = "# comment\n_:b1\t<http://example.com#p>\t\"a\\n\\u00E9\\U0001F600\\\"\"._:b1 .\n";
+        assert!(deserialize(nquads).next().unwrap().is_err());
+        let nquads = "# comment\n_:b1\t<http://example.com#p>\t\"a\\n\\u00E9\\U0001F600\\\"\" _:b1. # done\r\n";
+        let quads: Result<Vec<Quad>, _> = deserialize(nquads).collect();
+        let quad = quads.unwrap().pop().unwrap();
+        assert_eq!(quad.object, Node::from(Literal::new("a\n\u{E9}\u{1F600}\"", None, None)));
+        assert_eq!(quad.context, Some(quad.subject.clone()));
+    }
+
+    #[test]
+    fn test_deserialize_error() {
+        let nquads = "<http://example.com#s> <http://example.com#p> <o> .\n";
+        let mut quads = deserialize(nquads);
+        assert_eq!(
+            quads.next(),
+            Some(Err(ParseError::new(
+                ParseErrorKind::RelativeIRI("o".to_owned()),
+                1,
+                49,
+                48,
+                "//example.com#p> <o> .".to_owned()
+            )))
+        );
+        let nquads = "\n_:a <http://example.com#p> \"é\" $ .";
+        let error = deserialize(nquads).next().unwrap().unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::UnexpectedCharacter('$'));
+        assert_eq!((error.line, error.column, error.offset), (2, 32, 33));
+    }
+
+    #[test]
+    fn test_deserialize_resynchronises() {
+        let nquads = "_:a <http://example.com#p> \"unterminated\n_:a <http://example.com#p> <o> .\n_:a <http://example.com#p> _:b .\n";
+        let results: Vec<_> = deserialize(nquads).collect();
+        assert_eq!(results.len(), 3);
+        let error = results[0].clone().unwrap_err();
+        assert_eq!((error.kind, error.line, error.column), (ParseErrorKind::UnexpectedCharacter('\n'), 1, 41));
+        assert_eq!(results[1].clone().unwrap_err().kind, ParseErrorKind::RelativeIRI("o".to_owned()));
+        assert!(results[2].is_ok());
+    }
+
+    // W3C N-Quads test suite, negative syntax tests are the ones named "*-bad-*"
+    #[test]
+    fn test_w3c_suite() {
+        let mut count = 0;
+        for entry in fs::read_dir("src/w3c/n-quads").unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().and_then(|extension| extension.to_str()) != Some("nq") {
+                continue;
+            }
+            let name = path.file_name().unwrap().to_str().unwrap().to_owned();
+            let nquads = fs::read_to_string(&path).unwrap();
+            let result: Result<Vec<Quad>, _> = deserialize(&nquads).collect();
+            if name.contains("-bad-") {
+                assert!(result.is_err(), "{} should be rejected", name);
+            } else {
+                assert!(result.is_ok(), "{} should be accepted: {:?}", name, result);
+            }
+            count += 1;
+        }
+        assert_eq!(count, 85);
     }
 }
@@ -1,11 +1,47 @@
+use crate::canonicalization::canonicalize;
 use crate::namespace::XSD;
 use crate::quad::Quad;
 use crate::term::{BlankNode, Identifier, Literal, Node, IRI};
 use futures::stream::Stream;
+use std::collections::BTreeSet;
 use std::error::Error;
 
+// Escapes as in canonical N-Triples: ECHARs for the characters that have one
+// and UCHARs for the remaining control characters.
+pub fn escape_string(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            '\u{8}' => escaped.push_str("\\b"),
+            '\u{c}' => escaped.push_str("\\f"),
+            '\u{0}'..='\u{1f}' | '\u{7f}' => escaped.push_str(&format!("\\u{:04X}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// Characters not allowed in an IRIREF are written as UCHARs
+pub fn escape_iri(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\u{0}'..=' ' | '<' | '>' | '"' | '{' | '}' | '|' | '^' | '`' | '\\' => {
+                escaped.push_str(&format!("\\u{:04X}", c as u32))
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
 pub fn serialize_literal(literal: Literal) -> String {
-    let escaped_value = literal.value.replace("\"", "\\\"");
+    let escaped_value = escape_string(&literal.value);
     if literal.language.is_some() {
         return format!("\"{}\"@{}", escaped_value, literal.language.unwrap());
     }
@@ -21,7 +57,7 @@ pub fn serialize_blank_node(blank_node: BlankNode) -> String {
 }
 
 pub fn serialize_iri(iri: IRI) -> String {
-    format!("<{}>", iri.value)
+    format!("<{}>", escape_iri(&iri.value))
 }
 
 pub fn serialize_identifier(identifier: Identifier) -> String {
@@ -60,11 +96,26 @@ pub fn serialize_quad(quad: Quad) -> String {
 pub fn serialize(
     stream: impl Stream<Item = Quad, Error = impl Error>,
 ) -> impl Stream<Item = String, Error = impl Error> {
-    stream.map(|quad| serialize_quad(quad))
+    stream.map(|quad| serialize_quad(quad) + "\n")
+}
+
+// Canonical N-Quads: blank nodes relabelled by RDFC-1.0, canonical escaping, one quad per
+// line, no duplicates, lines sorted by code point so dumps can be diffed and hashed.
+pub fn serialize_canonical(quads: impl IntoIterator<Item = Quad>) -> Result<String, String> {
+    let lines: BTreeSet<String> = canonicalize(quads)?.into_iter().map(serialize_quad).collect();
+    let mut canonical = String::new();
+    for line in lines {
+        canonical.push_str(&line);
+        canonical.push('\n');
+    }
+    Ok(canonical)
 }
 
+#[cfg(test)]
 mod tests {
-    use crate::nquads_serialize::serialize;
+    use crate::nquads_deserialize::deserialize;
+    use crate::nquads_serialize::{serialize, serialize_canonical, serialize_literal};
+    use crate::term::Literal;
     use crate::quad::Quad;
     use crate::test_set;
     use futures::future::Future;
@@ -103,6 +154,29 @@ mod tests {
         let serialized_vec = result.wait().unwrap();
         let mut serialized = HashSet::new();
         serialized.extend(serialized_vec.iter().map(|s| s.to_owned()));
-        println!("{:?}", serialized.difference(&nquads_set));
+        assert_eq!(serialized.difference(&nquads_set).count(), 0);
+    }
+
+    #[test]
+    pub fn test_serialize_escapes() {
+        let literal = Literal::new("a \"quoted\"\nline\\ with \u{7}bell\ttab", None, None);
+        assert_eq!(serialize_literal(literal), "\"a \\\"quoted\\\"\\nline\\\\ with \\u0007bell\\ttab\"");
+    }
+
+    #[test]
+    pub fn test_serialize_canonical() {
+        let set = test_set::get_quads();
+        let canonical = serialize_canonical(set.clone()).unwrap();
+        let mut lines: Vec<&str> = canonical.lines().collect();
+        assert_eq!(lines.len(), set.len());
+        let unsorted = lines.clone();
+        lines.sort();
+        assert_eq!(lines, unsorted);
+        assert_eq!(canonical, serialize_canonical(set.iter().cloned().collect::<Vec<_>>().into_iter().rev()).unwrap());
+        let quads: Result<HashSet<Quad>, _> = deserialize(&canonical).collect();
+        let quads = quads.unwrap();
+        assert!(test_set::is_test_set(&quads));
+        // Reading gives the blank nodes fresh labels, canonicalization puts the same ones back
+        assert_eq!(canonical, serialize_canonical(quads).unwrap());
     }
 }
@@ -0,0 +1,324 @@
+use crate::blank_node_id::{BlankNodeIdGenerator, BlankNodeScope};
+use crate::compression;
+use crate::nquads_deserialize::{Input, NQuadsDeserializer};
+use crate::parse_error::{ParseError, ParseErrorKind};
+use crate::quad::Quad;
+use futures::{try_ready, Async, Poll, Stream};
+use std::collections::VecDeque;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::Path;
+
+// Incremental N-Quads parsing of byte chunks, for inputs too large to hold in memory.
+// Statements never span lines, so only the bytes of the current incomplete line are buffered, up to
+// `MAX_LINE_LENGTH` bytes: a longer line is an error, and is skipped up to its end. A line feed byte is never part of a multi-byte UTF-8 sequence, so complete lines always
+// decode on their own, whatever the chunk boundaries were.
+
+/// The longest incomplete line a `NQuadsPushDeserializer` buffers.
+pub const MAX_LINE_LENGTH: usize = 1 << 20;
+
+pub struct NQuadsPushDeserializer {
+    buffer: Vec<u8>,
+    line: u32,
+    offset: usize,
+    // Whether the rest of the current line is skipped, for being too long
+    skipping: bool,
+    blank_nodes: Option<BlankNodeScope>,
+}
+
+impl NQuadsPushDeserializer {
+    pub fn new(blank_nodes: BlankNodeScope) -> NQuadsPushDeserializer {
+        NQuadsPushDeserializer {
+            buffer: Vec::new(),
+            line: 1,
+            offset: 0,
+            skipping: false,
+            blank_nodes: Some(blank_nodes),
+        }
+    }
+
+    /// Feeds the next chunk of input, returning the statements it completed.
+    pub fn push(&mut self, mut chunk: &[u8]) -> Vec<Result<Quad, ParseError>> {
+        if self.skipping {
+            match chunk.iter().position(|byte| *byte == b'\n') {
+                Some(index) => {
+                    self.skipping = false;
+                    self.line += 1;
+                    self.offset += index + 1;
+                    chunk = &chunk[index + 1..];
+                }
+                None => {
+                    self.offset += chunk.len();
+                    return vec![];
+                }
+            }
+        }
+        let mut results = match chunk.iter().rposition(|byte| *byte == b'\n') {
+            Some(index) => {
+                self.buffer.extend_from_slice(&chunk[..=index]);
+                let lines = std::mem::replace(&mut self.buffer, chunk[index + 1..].to_vec());
+                self.deserialize_lines(lines)
+            }
+            None => {
+                self.buffer.extend_from_slice(chunk);
+                vec![]
+            }
+        };
+        if self.buffer.len() > MAX_LINE_LENGTH {
+            let error = ParseErrorKind::LineTooLong(MAX_LINE_LENGTH);
+            results.push(Err(ParseError::new(error, self.line, 1, self.offset, String::new())));
+            self.offset += self.buffer.len();
+            self.buffer = Vec::new();
+            self.skipping = true;
+        }
+        results
+    }
+
+    /// Ends the input, returning the statement on its last line if any.
+    pub fn finish(&mut self) -> Vec<Result<Quad, ParseError>> {
+        let lines = std::mem::take(&mut self.buffer);
+        self.deserialize_lines(lines)
+    }
+
+    fn deserialize_lines(&mut self, lines: Vec<u8>) -> Vec<Result<Quad, ParseError>> {
+        let length = lines.len();
+        let newlines = lines.iter().filter(|byte| **byte == b'\n').count() as u32;
+        let text = match ParseError::decode_utf8(lines) {
+            Ok(text) => text,
+            Err(mut error) => {
+                error.line += self.line - 1;
+                error.offset += self.offset;
+                self.line += newlines;
+                self.offset += length;
+                return vec![Err(error)];
+            }
+        };
+        let blank_nodes = self.blank_nodes.take().unwrap();
+        let mut deserializer =
+            NQuadsDeserializer::at(Input::at(text.chars(), self.line, self.offset), blank_nodes);
+        let quads = deserializer.by_ref().collect();
+        self.blank_nodes = Some(deserializer.into_blank_nodes());
+        self.line += newlines;
+        self.offset += length;
+        quads
+    }
+}
+
+impl Default for NQuadsPushDeserializer {
+    fn default() -> NQuadsPushDeserializer {
+        NQuadsPushDeserializer::new(BlankNodeScope::default())
+    }
+}
+
+/// Parses N-Quads from a stream of byte chunks, such as a hyper `Body`.
+pub struct NQuadsStreamDeserializer<S> {
+    stream: S,
+    deserializer: NQuadsPushDeserializer,
+    pending: VecDeque<Result<Quad, ParseError>>,
+    done: bool,
+}
+
+impl<S> NQuadsStreamDeserializer<S> {
+    pub fn new(stream: S, blank_nodes: BlankNodeScope) -> NQuadsStreamDeserializer<S> {
+        NQuadsStreamDeserializer {
+            stream,
+            deserializer: NQuadsPushDeserializer::new(blank_nodes),
+            pending: VecDeque::new(),
+            done: false,
+        }
+    }
+}
+
+impl<S> Stream for NQuadsStreamDeserializer<S>
+where
+    S: Stream,
+    S::Item: AsRef<[u8]>,
+{
+    type Item = Result<Quad, ParseError>;
+    type Error = S::Error;
+
+    fn poll(&mut self) -> Poll<Option<Self::Item>, S::Error> {
+        loop {
+            if let Some(result) = self.pending.pop_front() {
+                return Ok(Async::Ready(Some(result)));
+            }
+            if self.done {
+                return Ok(Async::Ready(None));
+            }
+            match try_ready!(self.stream.poll()) {
+                Some(chunk) => self.pending.extend(self.deserializer.push(chunk.as_ref())),
+                None => {
+                    self.pending.extend(self.deserializer.finish());
+                    self.done = true;
+                }
+            }
+        }
+    }
+}
+
+pub fn deserialize_stream<S>(stream: S) -> NQuadsStreamDeserializer<S>
+where
+    S: Stream,
+    S::Item: AsRef<[u8]>,
+{
+    NQuadsStreamDeserializer::new(stream, BlankNodeScope::default())
+}
+
+/// Parses N-Quads from a reader a buffer at a time.
+pub struct NQuadsReadDeserializer<R: Read> {
+    reader: BufReader<R>,
+    deserializer: NQuadsPushDeserializer,
+    pending: VecDeque<Result<Quad, ParseError>>,
+    done: bool,
+}
+
+impl<R: Read> NQuadsReadDeserializer<R> {
+    pub fn new(reader: R, blank_nodes: BlankNodeScope) -> NQuadsReadDeserializer<R> {
+        NQuadsReadDeserializer {
+            reader: BufReader::new(reader),
+            deserializer: NQuadsPushDeserializer::new(blank_nodes),
+            pending: VecDeque::new(),
+            done: false,
+        }
+    }
+}
+
+impl<R: Read> Iterator for NQuadsReadDeserializer<R> {
+    type Item = Result<Quad, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(result) = self.pending.pop_front() {
+                return Some(result);
+            }
+            if self.done {
+                return None;
+            }
+            match self.reader.fill_buf() {
+                Ok([]) => {
+                    self.pending.extend(self.deserializer.finish());
+                    self.done = true;
+                }
+                Ok(buffer) => {
+                    let length = buffer.len();
+                    self.pending.extend(self.deserializer.push(buffer));
+                    self.reader.consume(length);
+                }
+                Err(error) => {
+                    self.done = true;
+                    let error = ParseErrorKind::IO(error.to_string());
+                    let line = self.deserializer.line;
+                    let offset = self.deserializer.offset;
+                    return Some(Err(ParseError::new(error, line, 0, offset, String::new())));
+                }
+            }
+        }
+    }
+}
+
+pub fn deserialize_read<R: Read>(reader: R) -> NQuadsReadDeserializer<R> {
+    NQuadsReadDeserializer::new(reader, BlankNodeScope::default())
+}
+
+pub fn deserialize_read_with<R, G>(reader: R, generator: G) -> NQuadsReadDeserializer<R>
+where
+    R: Read,
+    G: BlankNodeIdGenerator + 'static,
+{
+    NQuadsReadDeserializer::new(reader, BlankNodeScope::new(generator))
+}
+
+/// Parses an N-Quads or N-Triples file, which may be compressed with gzip, zstd or bzip2 (see `compression::open`).
+pub fn deserialize_file<P: AsRef<Path>>(path: P) -> io::Result<NQuadsReadDeserializer<Box<dyn Read + Send>>> {
+    Ok(deserialize_read(compression::open(path)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::compression;
+    use crate::nquads_stream::{deserialize_file, deserialize_read, deserialize_stream, NQuadsPushDeserializer, MAX_LINE_LENGTH};
+    use crate::parse_error::ParseErrorKind;
+    use crate::quad::Quad;
+    use crate::term::{Literal, IRI};
+    use crate::test_set;
+    use futures::{Future, Stream};
+    use std::collections::HashSet;
+    use std::io::Write;
+
+    #[test]
+    fn test_push_split_chunks() {
+        let nquads = test_set::get_nquads_string() + "\n<http://example.com#s> <http://example.com#p> \"שלום\" .";
+        let bytes = nquads.as_bytes();
+        for chunk_size in &[1, 2, 3, 7, 64] {
+            let mut deserializer = NQuadsPushDeserializer::default();
+            let mut quads = HashSet::new();
+            for chunk in bytes.chunks(*chunk_size) {
+                quads.extend(deserializer.push(chunk).into_iter().map(|result| result.unwrap()));
+            }
+            quads.extend(deserializer.finish().into_iter().map(|result| result.unwrap()));
+            let extra = Quad::new(IRI::new("http://example.com#s"), IRI::new("http://example.com#p"), Literal::new("שלום", None, None), None);
+            assert!(quads.remove(&extra));
+            assert!(test_set::is_test_set(&quads));
+        }
+    }
+
+    #[test]
+    fn test_stream_and_read() {
+        let nquads = test_set::get_nquads_string();
+        let chunks: Vec<Vec<u8>> = nquads.as_bytes().chunks(5).map(|chunk| chunk.to_vec()).collect();
+        let stream = futures::stream::iter_ok::<_, ()>(chunks);
+        let quads: Result<HashSet<Quad>, _> = deserialize_stream(stream).collect().wait().unwrap().into_iter().collect();
+        assert!(test_set::is_test_set(&quads.unwrap()));
+        let quads: Result<HashSet<Quad>, _> = deserialize_read(nquads.as_bytes()).collect();
+        assert!(test_set::is_test_set(&quads.unwrap()));
+    }
+
+    #[test]
+    fn test_error_position() {
+        let mut deserializer = NQuadsPushDeserializer::default();
+        assert_eq!(deserializer.push(b"<http://example.com#s> <http://example.com#p> \"o\" .\n<http").len(), 1);
+        let results = deserializer.push(b"://example.com#s> $\n");
+        let error = results[0].clone().unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::UnexpectedCharacter('$'));
+        assert_eq!((error.line, error.column, error.offset), (2, 24, 75));
+        let results = deserializer.push(&[0x22, 0xff, b'\n']);
+        assert_eq!(results[0].clone().unwrap_err().line, 3);
+    }
+
+    #[test]
+    fn test_line_too_long() {
+        let statement = "<http://example.com#s> <http://example.com#p> \"o\" .\n";
+        let long = format!("{}\"{}\"\r{}", &statement[..46], "o".repeat(2 * MAX_LINE_LENGTH), statement);
+        let nquads = format!("{}{}{}", statement, long, statement);
+        let results: Vec<_> = deserialize_read(nquads.as_bytes()).collect();
+        assert_eq!(results.len(), 3);
+        let error = results[1].clone().unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::LineTooLong(MAX_LINE_LENGTH));
+        assert_eq!((error.line, error.offset), (2, statement.len()));
+        assert!(results[2].is_ok());
+        // Nothing of the long line is kept once the limit is passed
+        let mut deserializer = NQuadsPushDeserializer::default();
+        for chunk in long.as_bytes().chunks(4096) {
+            deserializer.push(chunk);
+            assert!(deserializer.buffer.len() <= MAX_LINE_LENGTH);
+        }
+    }
+
+    #[test]
+    fn test_compressed_file() {
+        let directory = std::env::temp_dir().join(format!("minerva-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        // Named by extension, and misnamed so the first bytes decide
+        for name in &["dump.nq.gz", "dump.nt.zst", "dump.nq.bz2", "dump.nq"] {
+            let path = directory.join(name);
+            let mut writer = compression::create(&path).unwrap();
+            writer.write_all(test_set::get_nquads_string().as_bytes()).unwrap();
+            drop(writer);
+            let quads: Result<HashSet<Quad>, _> = deserialize_file(&path).unwrap().collect();
+            assert!(test_set::is_test_set(&quads.unwrap()));
+        }
+        std::fs::rename(directory.join("dump.nq.gz"), directory.join("dump.data")).unwrap();
+        let quads: Result<HashSet<Quad>, _> = deserialize_file(directory.join("dump.data")).unwrap().collect();
+        assert!(test_set::is_test_set(&quads.unwrap()));
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
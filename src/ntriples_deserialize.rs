@@ -0,0 +1,79 @@
+use crate::blank_node_id::{BlankNodeIdGenerator, BlankNodeScope};
+use crate::nquads_deserialize::NQuadsDeserializer;
+use crate::quad::Context;
+use std::str::Chars;
+
+// Parser for N-Triples (https://www.w3.org/TR/n-triples/).
+// N-Triples is N-Quads without graph labels, so it shares the N-Quads statement and term parsers.
+// Triples are loaded into the graph chosen by the caller.
+
+pub type NTriplesDeserializer<I> = NQuadsDeserializer<I>;
+
+/// Strict N-Triples: statements with a graph label are rejected.
+pub fn deserialize<C>(ntriples: &str, graph: C) -> NTriplesDeserializer<Chars<'_>>
+where
+    C: Into<Context>,
+{
+    NQuadsDeserializer::new(ntriples.chars(), BlankNodeScope::default()).with_graph(graph.into(), false)
+}
+
+pub fn deserialize_with<C, G>(ntriples: &str, graph: C, generator: G) -> NTriplesDeserializer<Chars<'_>>
+where
+    C: Into<Context>,
+    G: BlankNodeIdGenerator + 'static,
+{
+    NQuadsDeserializer::new(ntriples.chars(), BlankNodeScope::new(generator)).with_graph(graph.into(), false)
+}
+
+/// Relaxed N-Triples: statements with a graph label keep it, the rest go to `graph`.
+pub fn deserialize_relaxed<C>(ntriples: &str, graph: C) -> NTriplesDeserializer<Chars<'_>>
+where
+    C: Into<Context>,
+{
+    NQuadsDeserializer::new(ntriples.chars(), BlankNodeScope::default()).with_graph(graph.into(), true)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ntriples_deserialize::{deserialize, deserialize_relaxed};
+    use crate::parse_error::ParseErrorKind;
+    use crate::quad::Quad;
+    use crate::term::{Identifier, IRI};
+    use std::fs;
+
+    #[test]
+    fn test_deserialize_into_graph() {
+        let graph = Identifier::from(IRI::new("http://example.com#graph"));
+        let ntriples = "<http://example.com#s> <http://example.com#p> \"o\" .\n";
+        let quads: Vec<Quad> = deserialize(ntriples, graph.clone()).map(|quad| quad.unwrap()).collect();
+        assert_eq!(quads[0].context, Some(graph.clone()));
+
+        let ntriples = "<http://example.com#s> <http://example.com#p> \"o\" <http://example.com#other> .\n";
+        let error = deserialize(ntriples, graph.clone()).next().unwrap().unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::UnexpectedGraphLabel);
+        let quad = deserialize_relaxed(ntriples, graph).next().unwrap().unwrap();
+        assert_eq!(quad.context, Some(IRI::new("http://example.com#other").into()));
+    }
+
+    // W3C N-Triples test suite, negative syntax tests are the ones named "*-bad-*"
+    #[test]
+    fn test_w3c_suite() {
+        let mut count = 0;
+        for entry in fs::read_dir("src/w3c/n-triples").unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().and_then(|extension| extension.to_str()) != Some("nt") {
+                continue;
+            }
+            let name = path.file_name().unwrap().to_str().unwrap().to_owned();
+            let ntriples = fs::read_to_string(&path).unwrap();
+            let result: Result<Vec<Quad>, _> = deserialize(&ntriples, None).collect();
+            if name.contains("-bad-") {
+                assert!(result.is_err(), "{} should be rejected", name);
+            } else {
+                assert!(result.is_ok(), "{} should be accepted: {:?}", name, result);
+            }
+            count += 1;
+        }
+        assert_eq!(count, 68);
+    }
+}
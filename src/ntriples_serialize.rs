@@ -0,0 +1,46 @@
+use crate::nquads_serialize::{self, serialize_identifier, serialize_iri, serialize_node};
+use crate::quad::Quad;
+use futures::stream::Stream;
+use std::error::Error;
+
+// N-Triples has no graph labels, the context of the quads is not written.
+
+pub fn serialize_triple(quad: Quad) -> String {
+    format!(
+        "{} {} {} .",
+        serialize_identifier(quad.subject),
+        serialize_iri(quad.predicate),
+        serialize_node(quad.object),
+    )
+}
+
+pub fn serialize(
+    stream: impl Stream<Item = Quad, Error = impl Error>,
+) -> impl Stream<Item = String, Error = impl Error> {
+    stream.map(|quad| serialize_triple(quad) + "\n")
+}
+
+// Canonical N-Triples is the canonical N-Quads of the quads without their contexts
+pub fn serialize_canonical(quads: impl IntoIterator<Item = Quad>) -> Result<String, String> {
+    nquads_serialize::serialize_canonical(quads.into_iter().map(|quad| Quad { context: None, ..quad }))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ntriples_deserialize::deserialize;
+    use crate::ntriples_serialize::serialize_canonical;
+    use crate::quad::Quad;
+    use crate::term::{Identifier, IRI};
+    use crate::test_set;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_round_trip() {
+        let set = test_set::get_quads();
+        let ntriples = serialize_canonical(set.clone()).unwrap();
+        assert!(!ntriples.contains("ontology"));
+        let graph = Identifier::from(IRI::new("http://example.com#ontology"));
+        let quads: Result<HashSet<Quad>, _> = deserialize(&ntriples, graph).collect();
+        assert!(test_set::is_test_set(&quads.unwrap()));
+    }
+}
@@ -0,0 +1,246 @@
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ParseErrorKind {
+    UnexpectedCharacter(char),
+    UnexpectedEOF,
+    InvalidCodePoint(u32),
+    InvalidIRICharacter(char),
+    RelativeIRI(String),
+    UnexpectedGraphLabel,
+    UndefinedPrefix(String),
+    InvalidUTF8,
+    IO(String),
+    Invalid(String),
+    /// A JSON-LD processing error, with what it was found in when that helps
+    JsonLd(JsonLdErrorCode, String),
+    /// A CBOR-LD term code that is not in the context
+    UnknownTermCode(i64),
+    /// A value of the wrong type in a format decoded to a data model first, such as CBOR
+    UnexpectedValue(String),
+    /// CSVW metadata that does not describe a table
+    InvalidMetadata(String),
+    /// An RML or R2RML mapping that can not be run
+    InvalidMapping(String),
+    /// A URI template or RML template that is not well formed
+    InvalidTemplate(String),
+    /// A column or reference that is not in the source
+    UnknownColumn(String),
+    /// A feature of the format that is not implemented
+    Unsupported(String),
+    /// A CSV cell that is not valid for its column
+    InvalidCell(String),
+    /// A query on a database that failed
+    Query(String),
+    /// A line longer than a line based streaming parser buffers, in bytes
+    LineTooLong(usize),
+}
+
+/// The JSON-LD 1.1 processing error codes (https://www.w3.org/TR/json-ld11-api/#jsonldprocessingerror)
+/// that can be found, and `invalid frame` from JSON-LD 1.1 Framing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JsonLdErrorCode {
+    RecursiveContextInclusion,
+    LoadingRemoteContextFailed,
+    InvalidBaseIri,
+    InvalidVocabMapping,
+    InvalidDefaultLanguage,
+    InvalidLocalContext,
+    CyclicIriMapping,
+    InvalidTermDefinition,
+    InvalidIdValue,
+    InvalidTypeValue,
+    InvalidValueObjectValue,
+    InvalidLanguageTaggedString,
+    InvalidIndexValue,
+    InvalidReverseValue,
+    InvalidLanguageMapValue,
+    InvalidFrame,
+}
+
+impl fmt::Display for JsonLdErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let code = match self {
+            JsonLdErrorCode::RecursiveContextInclusion => "recursive context inclusion",
+            JsonLdErrorCode::LoadingRemoteContextFailed => "loading remote context failed",
+            JsonLdErrorCode::InvalidBaseIri => "invalid base IRI",
+            JsonLdErrorCode::InvalidVocabMapping => "invalid vocab mapping",
+            JsonLdErrorCode::InvalidDefaultLanguage => "invalid default language",
+            JsonLdErrorCode::InvalidLocalContext => "invalid local context",
+            JsonLdErrorCode::CyclicIriMapping => "cyclic IRI mapping",
+            JsonLdErrorCode::InvalidTermDefinition => "invalid term definition",
+            JsonLdErrorCode::InvalidIdValue => "invalid @id value",
+            JsonLdErrorCode::InvalidTypeValue => "invalid type value",
+            JsonLdErrorCode::InvalidValueObjectValue => "invalid value object value",
+            JsonLdErrorCode::InvalidLanguageTaggedString => "invalid language-tagged string",
+            JsonLdErrorCode::InvalidIndexValue => "invalid @index value",
+            JsonLdErrorCode::InvalidReverseValue => "invalid @reverse value",
+            JsonLdErrorCode::InvalidLanguageMapValue => "invalid language map value",
+            JsonLdErrorCode::InvalidFrame => "invalid frame",
+        };
+        write!(f, "{}", code)
+    }
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseErrorKind::UnexpectedCharacter(c) => write!(f, "Unexpected character {}", c.escape_debug()),
+            ParseErrorKind::UnexpectedEOF => write!(f, "Unexpected EOF"),
+            ParseErrorKind::InvalidCodePoint(code) => write!(f, "Invalid code point {:X}", code),
+            ParseErrorKind::InvalidIRICharacter(c) => write!(f, "Invalid character {:?} in IRI", c),
+            ParseErrorKind::RelativeIRI(iri) => write!(f, "Relative IRI {}", iri),
+            ParseErrorKind::UnexpectedGraphLabel => write!(f, "Unexpected graph label"),
+            ParseErrorKind::UndefinedPrefix(prefix) => write!(f, "Undefined prefix {}:", prefix),
+            ParseErrorKind::InvalidUTF8 => write!(f, "Invalid UTF-8"),
+            ParseErrorKind::IO(message) => write!(f, "IO error: {}", message),
+            ParseErrorKind::Invalid(message) => write!(f, "{}", message),
+            ParseErrorKind::JsonLd(code, detail) if detail.is_empty() => write!(f, "JSON-LD error: {}", code),
+            ParseErrorKind::JsonLd(code, detail) => write!(f, "JSON-LD error: {} ({})", code, detail),
+            ParseErrorKind::UnknownTermCode(code) => write!(f, "Unknown term code {}", code),
+            ParseErrorKind::UnexpectedValue(message) => write!(f, "Unexpected value: {}", message),
+            ParseErrorKind::InvalidMetadata(message) => write!(f, "Invalid metadata: {}", message),
+            ParseErrorKind::InvalidMapping(message) => write!(f, "Invalid mapping: {}", message),
+            ParseErrorKind::InvalidTemplate(template) => write!(f, "Invalid template {}", template),
+            ParseErrorKind::UnknownColumn(column) => write!(f, "Unknown column {}", column),
+            ParseErrorKind::Unsupported(feature) => write!(f, "Unsupported: {}", feature),
+            ParseErrorKind::InvalidCell(message) => write!(f, "{}", message),
+            ParseErrorKind::Query(message) => write!(f, "Query failed: {}", message),
+            ParseErrorKind::LineTooLong(limit) => write!(f, "Line longer than {} bytes", limit),
+        }
+    }
+}
+
+/// An error found while parsing, with the position of the character it was found at.
+/// `line` and `column` count from 1, `offset` is in bytes from the start of the input.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    pub line: u32,
+    pub column: u32,
+    pub offset: usize,
+    pub snippet: String,
+}
+
+impl ParseError {
+    pub fn new(kind: ParseErrorKind, line: u32, column: u32, offset: usize, snippet: String) -> ParseError {
+        ParseError {
+            kind,
+            line,
+            column,
+            offset,
+            snippet,
+        }
+    }
+
+    /// Decodes `bytes` as UTF-8, reporting the position of the first invalid sequence.
+    pub fn decode_utf8(bytes: Vec<u8>) -> Result<String, ParseError> {
+        String::from_utf8(bytes).map_err(|error| {
+            let bytes = error.as_bytes();
+            let offset = error.utf8_error().valid_up_to();
+            let valid = String::from_utf8_lossy(&bytes[..offset]);
+            let line_start = valid.rfind('\n').map_or(0, |index| index + 1);
+            let snippet: String = valid[line_start..].chars().rev().take(20).collect();
+            ParseError::new(
+                ParseErrorKind::InvalidUTF8,
+                valid.matches('\n').count() as u32 + 1,
+                valid[line_start..].chars().count() as u32 + 1,
+                offset,
+                snippet.chars().rev().collect(),
+            )
+        })
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "At line {} column {}: {} near {:?}", self.line, self.column, self.kind, self.snippet)
+    }
+}
+
+impl Error for ParseError {}
+
+impl From<ParseError> for String {
+    fn from(error: ParseError) -> String {
+        error.to_string()
+    }
+}
+
+// Errors kept in a report, later ones are only counted
+pub const MAX_REPORTED_ERRORS: usize = 100;
+
+/// Outcome of a lenient parse: invalid statements are skipped and reported instead of aborting.
+/// All rejected statements are counted, and the errors of the first `MAX_REPORTED_ERRORS` kept.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParseReport {
+    pub accepted: usize,
+    pub rejected: usize,
+    pub errors: Vec<ParseError>,
+}
+
+impl ParseReport {
+    pub fn new() -> ParseReport {
+        ParseReport::default()
+    }
+
+    pub fn reject(&mut self, error: ParseError) {
+        self.rejected += 1;
+        if self.errors.len() < MAX_REPORTED_ERRORS {
+            self.errors.push(error);
+        }
+    }
+
+    pub fn add<T>(&mut self, result: Result<T, ParseError>) -> Option<T> {
+        match result {
+            Ok(value) => {
+                self.accepted += 1;
+                Some(value)
+            }
+            Err(error) => {
+                self.reject(error);
+                None
+            }
+        }
+    }
+
+    /// Passes through the successfully parsed items of `results`, recording the rest.
+    pub fn lenient<'a, T, I>(&'a mut self, results: I) -> impl Iterator<Item = T> + 'a
+    where
+        I: IntoIterator<Item = Result<T, ParseError>>,
+        I::IntoIter: 'a,
+    {
+        results.into_iter().filter_map(move |result| self.add(result))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::nquads_deserialize::deserialize;
+    use crate::parse_error::{ParseReport, MAX_REPORTED_ERRORS};
+    use crate::quad::Quad;
+
+    #[test]
+    fn test_lenient() {
+        let nquads = "_:a <http://example.com#p> _:b .\n_:a <p> _:b .\n_:a _:p _:b .\n_:b <http://example.com#p> _:a .";
+        let mut report = ParseReport::new();
+        let quads: Vec<Quad> = report.lenient(deserialize(nquads)).collect();
+        assert_eq!(quads.len(), 2);
+        assert_eq!(report.accepted, 2);
+        assert_eq!(report.rejected, 2);
+        let lines: Vec<u32> = report.errors.iter().map(|error| error.line).collect();
+        assert_eq!(lines, vec![2, 3]);
+    }
+
+    #[test]
+    fn test_lenient_bounded() {
+        let nquads = "_:a <p> _:b .\n".repeat(MAX_REPORTED_ERRORS + 5);
+        let mut report = ParseReport::new();
+        assert_eq!(report.lenient(deserialize(&nquads)).count(), 0);
+        assert_eq!(report.rejected, MAX_REPORTED_ERRORS + 5);
+        assert_eq!(report.errors.len(), MAX_REPORTED_ERRORS);
+    }
+}
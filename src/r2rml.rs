@@ -0,0 +1,200 @@
+use crate::dataset::Dataset;
+use crate::namespace::XSD;
+use crate::parse_error::{ParseError, ParseErrorKind};
+use crate::quad::{Context, Quad};
+use crate::rml::{Datum, LogicalSource, Mapping, Record};
+use crate::term::{Identifier, IRI};
+use rusqlite::types::ValueRef;
+use rusqlite::{Connection, OpenFlags};
+use std::collections::HashMap;
+use std::path::Path;
+
+// R2RML (https://www.w3.org/TR/r2rml/) over SQLite databases. The triples maps of the mapping read
+// the rows of an `rr:tableName`, a regular or delimited SQL identifier, or of an `rr:sqlQuery`, and their term maps refer to columns by
+// `rr:column` and in `rr:template`s. Values have the natural datatypes of their SQLite storage
+// class: xsd:integer, xsd:double, plain strings, and xsd:hexBinary for blobs. NULLs generate nothing.
+
+fn error(kind: ParseErrorKind) -> ParseError {
+    ParseError::new(kind, 0, 0, 0, String::new())
+}
+
+struct Row {
+    columns: Vec<(String, Option<Datum>)>,
+}
+
+impl Record for &Row {
+    // Column names may be delimited with double quotes, and otherwise match case-insensitively
+    fn values(&self, reference: &str) -> Result<Vec<Datum>, ParseError> {
+        let column = match reference.strip_prefix('"').and_then(|name| name.strip_suffix('"')) {
+            Some(name) => self.columns.iter().find(|(column, _)| column == name),
+            None => self.columns.iter().find(|(column, _)| column.eq_ignore_ascii_case(reference)),
+        };
+        match column {
+            Some((_, value)) => Ok(value.iter().cloned().collect()),
+            None => Err(error(ParseErrorKind::UnknownColumn(reference.to_owned()))),
+        }
+    }
+}
+
+fn datum(value: ValueRef) -> Option<Datum> {
+    match value {
+        ValueRef::Null => None,
+        ValueRef::Integer(value) => Some(Datum::new(value.to_string(), Some(XSD.iri("integer")))),
+        ValueRef::Real(value) => Some(Datum::new(value.to_string(), Some(XSD.iri("double")))),
+        ValueRef::Text(text) => Some(Datum::new(String::from_utf8_lossy(text), None)),
+        ValueRef::Blob(blob) => {
+            let hex: String = blob.iter().map(|byte| format!("{:02X}", byte)).collect();
+            Some(Datum::new(hex, Some(XSD.iri("hexBinary"))))
+        }
+    }
+}
+
+fn rows(connection: &Connection, query: &str) -> Result<Vec<Row>, rusqlite::Error> {
+    let mut statement = connection.prepare(query)?;
+    let names: Vec<String> = statement.column_names().iter().map(|name| name.to_string()).collect();
+    let mut rows = statement.query([])?;
+    let mut records = vec![];
+    while let Some(row) = rows.next()? {
+        let mut columns = vec![];
+        for (index, name) in names.iter().enumerate() {
+            columns.push((name.clone(), datum(row.get_ref(index)?)));
+        }
+        records.push(Row { columns });
+    }
+    Ok(records)
+}
+
+/// Materializes the quads of an R2RML `mapping`, written in Turtle, over the SQLite database at
+/// `database`, which is opened read-only. Quads that no graph map places in a named graph go to `graph`.
+pub fn deserialize<P: AsRef<Path>>(mapping: &str, database: P, graph: Context, base: Option<IRI>) -> Result<Vec<Quad>, ParseError> {
+    let mapping = Mapping::from_turtle(mapping, base)?;
+    let connection = Connection::open_with_flags(database, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|error| ParseError::new(ParseErrorKind::IO(error.to_string()), 0, 0, 0, String::new()))?;
+    let mut tables: HashMap<&str, usize> = HashMap::new();
+    let mut results: Vec<Vec<Row>> = vec![];
+    let mut records = vec![];
+    // Each query is run once, however many triples maps read it
+    for logical_source in mapping.logical_sources() {
+        let query = match logical_source {
+            LogicalSource::Table { query } => query.as_str(),
+            LogicalSource::Json { source, .. } => return Err(error(ParseErrorKind::InvalidMapping(format!("{} is not a logical table", source)))),
+        };
+        if !tables.contains_key(query) {
+            let rows = rows(&connection, query).map_err(|sql_error| error(ParseErrorKind::Query(format!("{}: {}", query, sql_error))))?;
+            tables.insert(query, results.len());
+            results.push(rows);
+        }
+        records.push(tables[query]);
+    }
+    let records: Vec<Vec<&Row>> = records.into_iter().map(|index| results[index].iter().collect()).collect();
+    let quads = mapping.generate(&records)?;
+    Ok(quads
+        .into_iter()
+        .map(|quad| match quad.context {
+            None => Quad { context: graph.clone(), ..quad },
+            Some(_) => quad,
+        })
+        .collect())
+}
+
+/// Loads the quads of an R2RML `mapping` over the SQLite database at `database` into the named graph
+/// `graph` of `dataset`, or into the named graphs the graph maps of the mapping give. Nothing is
+/// inserted if the mapping fails. Returns how many quads the mapping generated.
+pub fn load<P: AsRef<Path>>(dataset: &mut Dataset, mapping: &str, database: P, graph: IRI, base: Option<IRI>) -> Result<usize, ParseError> {
+    let quads = deserialize(mapping, database, Some(Identifier::IRI(graph)), base)?;
+    let generated = quads.len();
+    dataset.extend(quads);
+    Ok(generated)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dataset::Dataset;
+    use crate::nquads_deserialize;
+    use crate::parse_error::ParseErrorKind;
+    use crate::quad::Quad;
+    use crate::r2rml::{deserialize, load};
+    use crate::term::{Identifier, IRI};
+    use crate::test_set;
+    use rusqlite::Connection;
+    use std::path::PathBuf;
+
+    const MAPPING: &str = r#"
+        @prefix rr: <http://www.w3.org/ns/r2rml#> .
+        @prefix ex: <http://example.com/ns#> .
+
+        <#TriplesMap1> rr:logicalTable [ rr:tableName "EMP" ] ;
+            rr:subjectMap [ rr:template "http://data.example.com/employee/{EMPNO}" ; rr:class ex:Employee ] ;
+            rr:predicateObjectMap [ rr:predicate ex:name ; rr:objectMap [ rr:column "ENAME" ] ] ,
+                [ rr:predicate ex:salary ; rr:objectMap [ rr:column "SAL" ] ] ,
+                [ rr:predicate ex:department ; rr:objectMap [ rr:parentTriplesMap <#TriplesMap2> ; rr:joinCondition [ rr:child "DEPTNO" ; rr:parent "DEPTNO" ] ] ] .
+
+        <#TriplesMap2> rr:logicalTable [ rr:sqlQuery "SELECT DEPTNO, DNAME, LOC FROM DEPT WHERE DEPTNO < 30" ] ;
+            rr:subjectMap [ rr:template "http://data.example.com/department/{DEPTNO}" ; rr:graph ex:departments ] ;
+            rr:predicateObjectMap [ rr:predicate ex:location ; rr:objectMap [ rr:column "\"LOC\"" ; rr:language "en" ] ] .
+    "#;
+
+    const EXPECTED: &str = r#"
+<http://data.example.com/employee/7369> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://example.com/ns#Employee> <http://example.com/hr> .
+<http://data.example.com/employee/7369> <http://example.com/ns#name> "SMITH" <http://example.com/hr> .
+<http://data.example.com/employee/7369> <http://example.com/ns#salary> "800.5"^^<http://www.w3.org/2001/XMLSchema#double> <http://example.com/hr> .
+<http://data.example.com/employee/7369> <http://example.com/ns#department> <http://data.example.com/department/10> <http://example.com/hr> .
+<http://data.example.com/employee/7400> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://example.com/ns#Employee> <http://example.com/hr> .
+<http://data.example.com/employee/7400> <http://example.com/ns#name> "JONES" <http://example.com/hr> .
+<http://data.example.com/department/10> <http://example.com/ns#location> "NEW YORK"@en <http://example.com/ns#departments> .
+"#;
+
+    fn database(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("minerva-r2rml-{}-{}.sqlite", name, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let connection = Connection::open(&path).unwrap();
+        connection
+            .execute_batch(
+                "CREATE TABLE DEPT (DEPTNO INTEGER PRIMARY KEY, DNAME TEXT, LOC TEXT);
+                 CREATE TABLE EMP (EMPNO INTEGER PRIMARY KEY, ENAME TEXT, SAL REAL, DEPTNO INTEGER REFERENCES DEPT (DEPTNO));
+                 INSERT INTO DEPT VALUES (10, 'APPSERVER', 'NEW YORK'), (30, 'SALES', 'BOSTON');
+                 INSERT INTO EMP VALUES (7369, 'SMITH', 800.5, 10), (7400, 'JONES', NULL, 30);",
+            )
+            .unwrap();
+        path
+    }
+
+    #[test]
+    fn test_deserialize() {
+        let path = database("deserialize");
+        let graph = Some(Identifier::IRI(IRI::new("http://example.com/hr")));
+        let quads = deserialize(MAPPING, &path, graph, Some(IRI::new("http://example.com/mapping"))).unwrap();
+        let expected: Vec<Quad> = nquads_deserialize::deserialize(EXPECTED).map(|quad| quad.unwrap()).collect();
+        assert!(test_set::isomorphic(&quads, &expected), "{:#?}", quads);
+        let mut dataset = Dataset::new();
+        assert_eq!(load(&mut dataset, MAPPING, &path, IRI::new("http://example.com/hr"), Some(IRI::new("http://example.com/mapping"))).unwrap(), 7);
+        assert_eq!(dataset.len(), 7);
+        assert!(load(&mut dataset, "<http://a> <http://b> ", &path, IRI::new("http://example.com/hr"), None).is_err());
+        assert_eq!(dataset.len(), 7);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_errors() {
+        let path = database("errors");
+        let kind = |mapping: &str| deserialize(mapping, &path, None, None).unwrap_err().kind;
+        let prefixes = "@prefix rr: <http://www.w3.org/ns/r2rml#> .";
+        assert_eq!(
+            kind(&format!("{} <http://a> rr:logicalTable [ rr:tableName \"EMP\" ] ; rr:subject <http://b> ; rr:predicateObjectMap [ rr:predicate <http://c> ; rr:objectMap [ rr:column \"SALARY\" ] ] .", prefixes)),
+            ParseErrorKind::UnknownColumn("SALARY".to_owned())
+        );
+        match kind(&format!("{} <http://a> rr:logicalTable [ rr:tableName \"MISSING\" ] ; rr:subject <http://b> .", prefixes)) {
+            ParseErrorKind::Query(message) => assert!(message.starts_with("SELECT * FROM \"MISSING\": no such table")),
+            kind => panic!("Unexpected error {:?}", kind),
+        }
+        // Table names are identifiers, written delimited in the query
+        let table = |name: &str| format!("{} <http://a> rr:logicalTable [ rr:tableName {:?} ] ; rr:subject <http://b> ; rr:predicateObjectMap [ rr:predicate <http://c> ; rr:objectMap [ rr:column \"ENAME\" ] ] .", prefixes, name);
+        assert_eq!(deserialize(&table("\"EMP\""), &path, None, None).unwrap().len(), 2);
+        assert_eq!(deserialize(&table("main.emp"), &path, None, None).unwrap().len(), 2);
+        assert!(matches!(kind(&table("EMP; DROP TABLE EMP")), ParseErrorKind::InvalidMapping(_)));
+        assert!(matches!(kind(&table("\"EMP\"; DROP TABLE EMP")), ParseErrorKind::InvalidMapping(_)));
+        let error = deserialize(MAPPING, path.with_extension("missing"), None, Some(IRI::new("http://example.com/mapping"))).unwrap_err();
+        assert!(matches!(error.kind, ParseErrorKind::IO(_)));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
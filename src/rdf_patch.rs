@@ -0,0 +1,298 @@
+use crate::dataset::Dataset;
+use crate::namespace::PrefixMap;
+use crate::nquads_deserialize::{
+    deserialize_identifier, deserialize_iri, deserialize_literal_value, deserialize_node, Input,
+};
+use crate::nquads_serialize::{escape_string, serialize_node, serialize_quad};
+use crate::parse_error::{ParseError, ParseErrorKind};
+use crate::quad::Quad;
+use crate::term::Node;
+
+// RDF Patch (https://afs.github.io/rdf-patch/), a change set with a row per change:
+//   H id <uuid:0686c69d> .
+//   TX .
+//   PA "foaf" "http://xmlns.com/foaf/0.1/" .
+//   A <http://example.com#s> <http://example.com#p> "o" <http://example.com#g> .
+//   D <http://example.com#s> <http://example.com#p> "old" .
+//   TC .
+// Terms are written as in N-Quads. Blank node labels are the identifiers of the blank nodes in the
+// store, so that patches can delete them. Changes between TX and TA are discarded when parsing.
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    Add(Quad),
+    Delete(Quad),
+    AddPrefix(String, String),
+    DeletePrefix(String),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Patch {
+    pub headers: Vec<(String, Node)>,
+    pub changes: Vec<Change>,
+}
+
+impl Patch {
+    pub fn new() -> Patch {
+        Patch::default()
+    }
+
+    /// Applies the changes in order. Deleting a quad or prefix that is not there is not an error.
+    pub fn apply(&self, dataset: &mut Dataset, prefixes: &mut PrefixMap) {
+        for change in &self.changes {
+            match change {
+                Change::Add(quad) => dataset.insert(quad.clone()),
+                Change::Delete(quad) => {
+                    dataset.remove(quad);
+                }
+                Change::AddPrefix(prefix, address) => prefixes.insert(prefix.as_str(), address.as_str()),
+                Change::DeletePrefix(prefix) => {
+                    prefixes.remove(prefix);
+                }
+            }
+        }
+    }
+}
+
+struct PatchDeserializer<I: Iterator<Item = char>> {
+    chars: Input<I>,
+    patch: Patch,
+    // Number of changes when the open transaction started
+    transaction: Option<usize>,
+}
+
+impl<I: Iterator<Item = char>> PatchDeserializer<I> {
+    fn skip_blank(&mut self) {
+        loop {
+            self.chars.skip_whitespace();
+            self.chars.skip_comment();
+            match self.chars.peek() {
+                Some('\n') | Some('\r') => {
+                    self.chars.next();
+                }
+                _ => return,
+            }
+        }
+    }
+
+    // A row code, or a header name
+    fn word(&mut self) -> Result<String, ParseError> {
+        let mut word = String::new();
+        while let Some(c) = self.chars.peek() {
+            if c.is_whitespace() {
+                break;
+            }
+            word.push(c);
+            self.chars.next();
+        }
+        if word.is_empty() {
+            return Err(self.chars.reject());
+        }
+        Ok(word)
+    }
+
+    // Prefixes are strings, or the Turtle `foaf:` form
+    fn prefix(&mut self) -> Result<String, ParseError> {
+        match self.chars.peek() {
+            Some('"') => deserialize_literal_value(&mut self.chars),
+            _ => {
+                let word = self.word()?;
+                Ok(word.strip_suffix(':').unwrap_or(&word).to_owned())
+            }
+        }
+    }
+
+    fn address(&mut self) -> Result<String, ParseError> {
+        match self.chars.peek() {
+            Some('<') => Ok(deserialize_iri(&mut self.chars)?.value),
+            _ => deserialize_literal_value(&mut self.chars),
+        }
+    }
+
+    fn quad(&mut self) -> Result<Quad, ParseError> {
+        let subject = deserialize_identifier(&mut self.chars)?;
+        self.chars.skip_whitespace();
+        let predicate = deserialize_iri(&mut self.chars)?;
+        self.chars.skip_whitespace();
+        let object = deserialize_node(&mut self.chars)?;
+        self.chars.skip_whitespace();
+        let context = match self.chars.peek() {
+            Some('<') | Some('_') => Some(deserialize_identifier(&mut self.chars)?),
+            _ => None,
+        };
+        Ok(Quad::new(subject, predicate, object, context))
+    }
+
+    // Rows end with an optional dot
+    fn end_row(&mut self) -> Result<(), ParseError> {
+        self.chars.skip_whitespace();
+        if self.chars.peek() == Some('.') {
+            self.chars.next();
+            self.chars.skip_whitespace();
+        }
+        self.chars.skip_comment();
+        match self.chars.peek() {
+            Some('\n') | Some('\r') | None => Ok(()),
+            _ => Err(self.chars.reject()),
+        }
+    }
+
+    fn row(&mut self) -> Result<(), ParseError> {
+        let code = self.word()?;
+        self.chars.skip_whitespace();
+        match code.as_str() {
+            "H" => {
+                let name = self.word()?;
+                self.chars.skip_whitespace();
+                let value = deserialize_node(&mut self.chars)?;
+                self.patch.headers.push((name, value));
+            }
+            "TX" => {
+                if self.transaction.is_some() {
+                    return Err(self.chars.error(ParseErrorKind::Invalid("Nested transaction".to_owned())));
+                }
+                self.transaction = Some(self.patch.changes.len());
+            }
+            "TC" | "TA" => match self.transaction.take() {
+                Some(start) if code == "TA" => self.patch.changes.truncate(start),
+                Some(_) => {}
+                None => return Err(self.chars.error(ParseErrorKind::Invalid(format!("{} outside of a transaction", code)))),
+            },
+            "PA" => {
+                let prefix = self.prefix()?;
+                self.chars.skip_whitespace();
+                let address = self.address()?;
+                self.patch.changes.push(Change::AddPrefix(prefix, address));
+            }
+            "PD" => {
+                let prefix = self.prefix()?;
+                self.patch.changes.push(Change::DeletePrefix(prefix));
+            }
+            "A" => {
+                let quad = self.quad()?;
+                self.patch.changes.push(Change::Add(quad));
+            }
+            "D" => {
+                let quad = self.quad()?;
+                self.patch.changes.push(Change::Delete(quad));
+            }
+            _ => return Err(self.chars.error(ParseErrorKind::Invalid(format!("Unknown row {}", code)))),
+        }
+        self.end_row()
+    }
+
+    fn deserialize(mut self) -> Result<Patch, ParseError> {
+        loop {
+            self.skip_blank();
+            if self.chars.peek().is_none() {
+                break;
+            }
+            self.row()?;
+        }
+        if self.transaction.is_some() {
+            return Err(self.chars.error(ParseErrorKind::Invalid("Transaction is neither committed nor aborted".to_owned())));
+        }
+        Ok(self.patch)
+    }
+}
+
+/// Parses a whole patch, so that it can be applied atomically.
+pub fn deserialize(patch: &str) -> Result<Patch, ParseError> {
+    let deserializer = PatchDeserializer {
+        chars: Input::new(patch.chars()),
+        patch: Patch::new(),
+        transaction: None,
+    };
+    deserializer.deserialize()
+}
+
+/// Writes the changes of `patch` as one transaction.
+pub fn serialize(patch: &Patch) -> String {
+    let mut rows = vec![];
+    for (name, value) in &patch.headers {
+        rows.push(format!("H {} {} .", name, serialize_node(value.clone())));
+    }
+    rows.push("TX .".to_owned());
+    for change in &patch.changes {
+        rows.push(match change {
+            Change::Add(quad) => format!("A {}", serialize_quad(quad.clone())),
+            Change::Delete(quad) => format!("D {}", serialize_quad(quad.clone())),
+            Change::AddPrefix(prefix, address) => format!("PA \"{}\" \"{}\" .", escape_string(prefix), escape_string(address)),
+            Change::DeletePrefix(prefix) => format!("PD \"{}\" .", escape_string(prefix)),
+        });
+    }
+    rows.push("TC .".to_owned());
+    rows.join("\n") + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dataset::Dataset;
+    use crate::namespace::PrefixMap;
+    use crate::parse_error::ParseErrorKind;
+    use crate::quad::Quad;
+    use crate::rdf_patch::{deserialize, serialize, Change};
+    use crate::term::{BlankNode, Identifier, Literal, Node, IRI};
+    use crate::test_set;
+
+    #[test]
+    fn test_deserialize() {
+        let patch = deserialize(
+            r#"H id <uuid:0686c69d> .
+# Kept
+TX .
+PA "foaf" "http://xmlns.com/foaf/0.1/" .
+A <http://example.com#iddan> <http://example.com#likes> _:b0 <http://example.com#ontology> .
+D <http://example.com#iddan> <http://example.com#name> "Iddan"@en
+TC .
+TX .
+A <http://example.com#iddan> <http://example.com#likes> <http://example.com#lior> .
+TA .
+PD foaf: .
+"#,
+        )
+        .unwrap();
+        assert_eq!(patch.headers, vec![("id".to_owned(), Node::IRI(IRI::new("uuid:0686c69d")))]);
+        let iddan = IRI::new("http://example.com#iddan");
+        let ontology = Identifier::IRI(IRI::new("http://example.com#ontology"));
+        assert_eq!(
+            patch.changes,
+            vec![
+                Change::AddPrefix("foaf".to_owned(), "http://xmlns.com/foaf/0.1/".to_owned()),
+                Change::Add(Quad::new(&iddan, IRI::new("http://example.com#likes"), BlankNode::from_value("b0"), ontology)),
+                Change::Delete(Quad::new(&iddan, IRI::new("http://example.com#name"), Literal::new("Iddan", None, Some("en".to_owned())), None)),
+                Change::DeletePrefix("foaf".to_owned()),
+            ]
+        );
+        assert_eq!(deserialize(&serialize(&patch)).unwrap(), patch);
+
+        let invalid = |patch: &str| match deserialize(patch).unwrap_err().kind {
+            ParseErrorKind::Invalid(message) => message,
+            kind => panic!("Unexpected error {:?}", kind),
+        };
+        assert_eq!(invalid("TX .\nTX .\nTC .\n"), "Nested transaction");
+        assert_eq!(invalid("TC .\n"), "TC outside of a transaction");
+        assert_eq!(invalid("TX .\nA <http://a> <http://b> <http://c> .\n"), "Transaction is neither committed nor aborted");
+        assert_eq!(invalid("R <http://a> <http://b> <http://c> .\n"), "Unknown row R");
+        assert_eq!(deserialize("A <http://a> <http://b> <http://c> . .\n").unwrap_err().line, 1);
+    }
+
+    #[test]
+    fn test_diff() {
+        let before: Dataset = Dataset::from(test_set::get_quads().into_iter().collect::<Vec<Quad>>());
+        let mut after = before.clone();
+        let removed = before.match_quads(None, None, None, None).next().unwrap();
+        after.remove(&removed);
+        after.insert(Quad::new(IRI::new("http://example.com#lior"), IRI::new("http://example.com#likes"), Literal::new("cheese", None, None), None));
+        let patch = before.diff(&after);
+        assert_eq!(patch.changes.len(), 2);
+        assert_eq!(patch.changes[0], Change::Delete(removed));
+
+        let mut patched = before.clone();
+        let mut prefixes = PrefixMap::new();
+        deserialize(&serialize(&patch)).unwrap().apply(&mut patched, &mut prefixes);
+        assert_eq!(patched.len(), after.len());
+        assert!(after.match_quads(None, None, None, None).all(|quad| patched.contains(&quad)));
+        assert!(after.diff(&patched).changes.is_empty());
+    }
+}
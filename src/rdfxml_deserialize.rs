@@ -0,0 +1,960 @@
+use crate::blank_node_id::{BlankNodeIdGenerator, BlankNodeScope};
+use crate::namespace::RDF;
+use crate::nquads_deserialize::{is_absolute_iri, Input};
+use crate::parse_error::{ParseError, ParseErrorKind};
+use crate::quad::{Context, Quad};
+use crate::term::{Identifier, Literal, Node, IRI};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::str::Chars;
+
+// Parser for RDF/XML (https://www.w3.org/TR/rdf-syntax-grammar/).
+// The document is read into an element tree by a minimal non-validating XML parser (internal
+// entities of bounded expansion, no external DTD), which is then matched against the RDF/XML grammar.
+// XML literals are written in exclusive XML canonical form.
+
+pub(crate) const RDF_NAMESPACE: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+const XML_NAMESPACE: &str = "http://www.w3.org/XML/1998/namespace";
+
+// Namespace declarations by prefix, `None` for the default namespace
+type Namespaces = BTreeMap<Option<String>, String>;
+
+#[derive(Debug, Clone)]
+struct Name {
+    prefix: Option<String>,
+    local: String,
+    namespace: Option<String>,
+}
+
+impl Name {
+    fn iri(&self) -> String {
+        format!("{}{}", self.namespace.as_deref().unwrap_or(""), self.local)
+    }
+
+    fn is_rdf(&self, local: &str) -> bool {
+        self.namespace.as_deref() == Some(RDF_NAMESPACE) && self.local == local
+    }
+
+    fn qualified(&self) -> String {
+        match &self.prefix {
+            Some(prefix) => format!("{}:{}", prefix, self.local),
+            None => self.local.clone(),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Attribute {
+    name: Name,
+    value: String,
+}
+
+#[derive(Debug)]
+struct Element {
+    name: Name,
+    attributes: Vec<Attribute>,
+    // Namespaces in scope, for XML literals
+    namespaces: Namespaces,
+    children: Vec<Content>,
+    // For errors about the element
+    start: ParseError,
+}
+
+#[derive(Debug)]
+enum Content {
+    Element(Element),
+    Text(String),
+}
+
+impl Element {
+    fn error(&self, message: &str) -> ParseError {
+        ParseError {
+            kind: ParseErrorKind::Invalid(message.to_owned()),
+            ..self.start.clone()
+        }
+    }
+
+    fn attribute(&self, local: &str) -> Option<&str> {
+        self.attributes.iter().find(|attribute| attribute.name.is_rdf(local)).map(|attribute| attribute.value.as_str())
+    }
+
+    fn xml_attribute(&self, local: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|attribute| attribute.name.namespace.as_deref() == Some(XML_NAMESPACE) && attribute.name.local == local)
+            .map(|attribute| attribute.value.as_str())
+    }
+
+    fn elements(&self) -> impl Iterator<Item = &Element> {
+        self.children.iter().filter_map(|content| match content {
+            Content::Element(element) => Some(element),
+            Content::Text(_) => None,
+        })
+    }
+
+    fn text(&self) -> String {
+        self.children
+            .iter()
+            .filter_map(|content| match content {
+                Content::Text(text) => Some(text.as_str()),
+                Content::Element(_) => None,
+            })
+            .collect()
+    }
+}
+
+fn is_xml_whitespace(c: char) -> bool {
+    c == ' ' || c == '\t' || c == '\n' || c == '\r'
+}
+
+pub(crate) fn is_name_start_char(c: char) -> bool {
+    matches!(c,
+        'A'..='Z' | 'a'..='z' | '_' | ':'
+        | '\u{C0}'..='\u{D6}' | '\u{D8}'..='\u{F6}' | '\u{F8}'..='\u{2FF}' | '\u{370}'..='\u{37D}'
+        | '\u{37F}'..='\u{1FFF}' | '\u{200C}'..='\u{200D}' | '\u{2070}'..='\u{218F}' | '\u{2C00}'..='\u{2FEF}'
+        | '\u{3001}'..='\u{D7FF}' | '\u{F900}'..='\u{FDCF}' | '\u{FDF0}'..='\u{FFFD}' | '\u{10000}'..='\u{EFFFF}')
+}
+
+pub(crate) fn is_name_char(c: char) -> bool {
+    is_name_start_char(c) || matches!(c, '0'..='9' | '-' | '.' | '\u{B7}' | '\u{300}'..='\u{36F}' | '\u{203F}'..='\u{2040}')
+}
+
+pub(crate) fn is_ncname(value: &str) -> bool {
+    let mut chars = value.chars();
+    chars.next().is_some_and(|c| is_name_start_char(c) && c != ':') && chars.all(|c| is_name_char(c) && c != ':')
+}
+
+// Bounds on entity expansion, against entities that reference others many times over
+// ("billion laughs"): characters of entity replacements in the whole document, and how many
+// entities deep a replacement may be made of
+const MAX_ENTITY_EXPANSION: usize = 1 << 20;
+const MAX_ENTITY_DEPTH: usize = 8;
+
+struct XmlReader<'a, I: Iterator<Item = char>> {
+    chars: &'a mut Input<I>,
+    // Replacement and depth of the internal entities, which are expanded when they are declared
+    entities: HashMap<String, (String, usize)>,
+    expanded: usize,
+    // Depth of the deepest entity referenced since the start of a declaration
+    deepest: usize,
+}
+
+impl<'a, I: Iterator<Item = char>> XmlReader<'a, I> {
+    fn starts_with(&mut self, value: &str) -> bool {
+        value.chars().enumerate().all(|(n, c)| self.chars.peek_nth(n) == Some(c))
+    }
+
+    fn consume(&mut self, value: &str) -> Result<(), ParseError> {
+        for c in value.chars() {
+            self.chars.expect(c)?;
+        }
+        Ok(())
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.chars.peek().is_some_and(is_xml_whitespace) {
+            self.chars.next();
+        }
+    }
+
+    // Everything up to `end`, which is consumed
+    fn until(&mut self, end: &str) -> Result<String, ParseError> {
+        let mut value = String::new();
+        while !self.starts_with(end) {
+            match self.chars.next() {
+                Some(c) => value.push(c),
+                None => return Err(self.chars.unexpected(None)),
+            }
+        }
+        self.consume(end)?;
+        Ok(value)
+    }
+
+    fn name(&mut self) -> Result<String, ParseError> {
+        let mut name = String::new();
+        match self.chars.peek() {
+            Some(c) if is_name_start_char(c) => {}
+            _ => return Err(self.chars.reject()),
+        }
+        while let Some(c) = self.chars.peek().filter(|c| is_name_char(*c)) {
+            name.push(c);
+            self.chars.next();
+        }
+        Ok(name)
+    }
+
+    fn quoted(&mut self) -> Result<String, ParseError> {
+        let quote = match self.chars.next() {
+            Some(c) if c == '"' || c == '\'' => c,
+            c => return Err(self.chars.unexpected(c)),
+        };
+        let mut value = String::new();
+        loop {
+            match self.chars.next() {
+                Some(c) if c == quote => return Ok(value),
+                Some('&') => value.push_str(&self.reference()?),
+                Some('<') => return Err(self.chars.unexpected(Some('<'))),
+                // Attribute value normalization
+                Some(c) if is_xml_whitespace(c) => {
+                    if c != '\n' || !value.ends_with('\r') {
+                        value.push(' ');
+                    }
+                }
+                Some(c) => value.push(c),
+                None => return Err(self.chars.unexpected(None)),
+            }
+        }
+    }
+
+    // The replacement of a reference, after its `&`
+    fn reference(&mut self) -> Result<String, ParseError> {
+        if self.chars.peek() == Some('#') {
+            self.chars.next();
+            let hexadecimal = self.chars.peek() == Some('x');
+            if hexadecimal {
+                self.chars.next();
+            }
+            let digits = self.until(";")?;
+            let code = u32::from_str_radix(&digits, if hexadecimal { 16 } else { 10 })
+                .map_err(|_| self.chars.error(ParseErrorKind::Invalid(format!("Invalid character reference {}", digits))))?;
+            return match std::char::from_u32(code) {
+                Some(c) => Ok(c.to_string()),
+                None => Err(self.chars.error(ParseErrorKind::InvalidCodePoint(code))),
+            };
+        }
+        let name = self.name()?;
+        self.chars.expect(';')?;
+        match name.as_str() {
+            "lt" => Ok("<".to_owned()),
+            "gt" => Ok(">".to_owned()),
+            "amp" => Ok("&".to_owned()),
+            "apos" => Ok("'".to_owned()),
+            "quot" => Ok("\"".to_owned()),
+            name => match self.entities.get(name) {
+                Some((value, depth)) => {
+                    self.expanded += value.len();
+                    if self.expanded > MAX_ENTITY_EXPANSION {
+                        return Err(self.chars.error(ParseErrorKind::Invalid("Entity expansion limit exceeded".to_owned())));
+                    }
+                    self.deepest = self.deepest.max(*depth);
+                    Ok(value.clone())
+                }
+                None => Err(self.chars.error(ParseErrorKind::Invalid(format!("Undefined entity {}", name)))),
+            },
+        }
+    }
+
+    // Comments, processing instructions, the XML declaration and the document type declaration
+    fn misc(&mut self) -> Result<(), ParseError> {
+        loop {
+            self.skip_whitespace();
+            if self.starts_with("<!--") {
+                self.until("-->")?;
+            } else if self.starts_with("<?") {
+                self.until("?>")?;
+            } else if self.starts_with("<!DOCTYPE") {
+                self.doctype()?;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    // Only internal general entities are kept
+    fn doctype(&mut self) -> Result<(), ParseError> {
+        self.consume("<!DOCTYPE")?;
+        loop {
+            self.skip_whitespace();
+            match self.chars.peek() {
+                Some('>') => {
+                    self.chars.next();
+                    return Ok(());
+                }
+                Some('"') | Some('\'') => {
+                    self.quoted()?;
+                }
+                Some('[') => {
+                    self.chars.next();
+                    self.internal_subset()?;
+                }
+                Some(_) => {
+                    self.chars.next();
+                }
+                None => return Err(self.chars.unexpected(None)),
+            }
+        }
+    }
+
+    fn internal_subset(&mut self) -> Result<(), ParseError> {
+        loop {
+            self.skip_whitespace();
+            if self.starts_with("]") {
+                self.chars.next();
+                return Ok(());
+            } else if self.starts_with("<!--") {
+                self.until("-->")?;
+            } else if self.starts_with("<!ENTITY") {
+                self.consume("<!ENTITY")?;
+                self.skip_whitespace();
+                let parameter = self.chars.peek() == Some('%');
+                if parameter {
+                    self.chars.next();
+                    self.skip_whitespace();
+                }
+                let name = self.name()?;
+                self.skip_whitespace();
+                if let Some('"') | Some('\'') = self.chars.peek() {
+                    self.deepest = 0;
+                    let value = self.quoted()?;
+                    let depth = self.deepest + 1;
+                    if depth > MAX_ENTITY_DEPTH {
+                        return Err(self.chars.error(ParseErrorKind::Invalid(format!("Entity {} is nested too deeply", name))));
+                    }
+                    if !parameter {
+                        self.entities.insert(name, (value, depth));
+                    }
+                }
+                self.until(">")?;
+            } else if self.starts_with("<") {
+                self.until(">")?;
+            } else {
+                return Err(self.chars.reject());
+            }
+        }
+    }
+
+    fn resolve(&mut self, name: &str, namespaces: &Namespaces, attribute: bool) -> Result<Name, ParseError> {
+        let (prefix, local) = match name.find(':') {
+            Some(index) => (Some(name[..index].to_owned()), name[index + 1..].to_owned()),
+            None => (None, name.to_owned()),
+        };
+        let namespace = match &prefix {
+            Some(prefix) if prefix == "xml" => Some(XML_NAMESPACE.to_owned()),
+            Some(prefix) => match namespaces.get(&Some(prefix.clone())) {
+                Some(namespace) => Some(namespace.clone()),
+                None => return Err(self.chars.error(ParseErrorKind::UndefinedPrefix(prefix.clone()))),
+            },
+            // Unprefixed attributes are in no namespace
+            None if attribute => None,
+            None => namespaces.get(&None).cloned(),
+        };
+        Ok(Name { prefix, local, namespace })
+    }
+
+    fn element(&mut self, namespaces: &Namespaces) -> Result<Element, ParseError> {
+        self.chars.expect('<')?;
+        let start = self.chars.error(ParseErrorKind::Invalid(String::new()));
+        let qualified = self.name()?;
+        let mut attributes = vec![];
+        let empty = loop {
+            self.skip_whitespace();
+            match self.chars.peek() {
+                Some('/') => {
+                    self.consume("/>")?;
+                    break true;
+                }
+                Some('>') => {
+                    self.chars.next();
+                    break false;
+                }
+                _ => {
+                    let name = self.name()?;
+                    self.skip_whitespace();
+                    self.chars.expect('=')?;
+                    self.skip_whitespace();
+                    attributes.push((name, self.quoted()?));
+                }
+            }
+        };
+        let mut namespaces = namespaces.clone();
+        for (name, value) in attributes.iter() {
+            let prefix = match name.as_str() {
+                "xmlns" => None,
+                name if name.starts_with("xmlns:") => Some(name[6..].to_owned()),
+                _ => continue,
+            };
+            match (prefix, value.is_empty()) {
+                (None, true) => namespaces.remove(&None),
+                (prefix, _) => namespaces.insert(prefix, value.clone()),
+            };
+        }
+        let name = self.resolve(&qualified, &namespaces, false)?;
+        let attributes = attributes
+            .into_iter()
+            .filter(|(name, _)| name != "xmlns" && !name.starts_with("xmlns:"))
+            .map(|(name, value)| Ok(Attribute { name: self.resolve(&name, &namespaces, true)?, value }))
+            .collect::<Result<Vec<Attribute>, ParseError>>()?;
+        let mut children = vec![];
+        if !empty {
+            loop {
+                if self.starts_with("</") {
+                    self.consume("</")?;
+                    if self.name()? != qualified {
+                        return Err(self.chars.error(ParseErrorKind::Invalid(format!("Expected </{}>", qualified))));
+                    }
+                    self.skip_whitespace();
+                    self.chars.expect('>')?;
+                    break;
+                } else if self.starts_with("<!--") {
+                    self.until("-->")?;
+                } else if self.starts_with("<![CDATA[") {
+                    self.consume("<![CDATA[")?;
+                    let text = self.until("]]>")?;
+                    push_text(&mut children, &text);
+                } else if self.starts_with("<?") {
+                    self.until("?>")?;
+                } else if self.starts_with("<") {
+                    children.push(Content::Element(self.element(&namespaces)?));
+                } else {
+                    let text = self.text()?;
+                    push_text(&mut children, &text);
+                }
+            }
+        }
+        Ok(Element {
+            name,
+            attributes,
+            namespaces,
+            children,
+            start,
+        })
+    }
+
+    fn text(&mut self) -> Result<String, ParseError> {
+        let mut text = String::new();
+        loop {
+            match self.chars.peek() {
+                Some('<') => return Ok(text),
+                Some('&') => {
+                    self.chars.next();
+                    text.push_str(&self.reference()?);
+                }
+                // Line ends are normalized to line feeds
+                Some('\r') => {
+                    self.chars.next();
+                    if self.chars.peek() != Some('\n') {
+                        text.push('\n');
+                    }
+                }
+                Some(c) => {
+                    self.chars.next();
+                    text.push(c);
+                }
+                None => return Err(self.chars.unexpected(None)),
+            }
+        }
+    }
+
+    fn document(&mut self) -> Result<Element, ParseError> {
+        self.misc()?;
+        let root = self.element(&Namespaces::new())?;
+        self.misc()?;
+        match self.chars.peek() {
+            None => Ok(root),
+            Some(_) => Err(self.chars.reject()),
+        }
+    }
+}
+
+fn push_text(children: &mut Vec<Content>, text: &str) {
+    match children.last_mut() {
+        Some(Content::Text(previous)) => previous.push_str(text),
+        _ => children.push(Content::Text(text.to_owned())),
+    }
+}
+
+pub(crate) fn escape_text(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('\r', "&#xD;")
+}
+
+pub(crate) fn escape_attribute(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('"', "&quot;")
+        .replace('\t', "&#x9;")
+        .replace('\n', "&#xA;")
+        .replace('\r', "&#xD;")
+}
+
+// Exclusive XML canonicalization of `content`, where `rendered` are the namespace declarations
+// already written by the enclosing elements
+fn canonicalize(content: &[Content], rendered: &Namespaces, output: &mut String) {
+    for content in content {
+        let element = match content {
+            Content::Text(text) => {
+                output.push_str(&escape_text(text));
+                continue;
+            }
+            Content::Element(element) => element,
+        };
+        let mut rendered = rendered.clone();
+        // Only the namespaces visibly used by the element and its attributes are declared
+        let mut used: Vec<Option<String>> = vec![element.name.prefix.clone()];
+        used.extend(element.attributes.iter().filter(|attribute| attribute.name.prefix.is_some()).map(|attribute| attribute.name.prefix.clone()));
+        used.retain(|prefix| prefix.as_deref() != Some("xml"));
+        used.sort();
+        used.dedup();
+        output.push('<');
+        output.push_str(&element.name.qualified());
+        for prefix in used {
+            let namespace = element.namespaces.get(&prefix).cloned().unwrap_or_default();
+            let current = rendered.get(&prefix).cloned().unwrap_or_default();
+            if namespace != current {
+                match &prefix {
+                    Some(prefix) => output.push_str(&format!(" xmlns:{}=\"{}\"", prefix, escape_attribute(&namespace))),
+                    None => output.push_str(&format!(" xmlns=\"{}\"", escape_attribute(&namespace))),
+                }
+                rendered.insert(prefix, namespace);
+            }
+        }
+        let mut attributes: Vec<&Attribute> = element.attributes.iter().collect();
+        attributes.sort_by(|a, b| (a.name.namespace.as_deref().unwrap_or(""), &a.name.local).cmp(&(b.name.namespace.as_deref().unwrap_or(""), &b.name.local)));
+        for attribute in attributes {
+            output.push_str(&format!(" {}=\"{}\"", attribute.name.qualified(), escape_attribute(&attribute.value)));
+        }
+        output.push('>');
+        canonicalize(&element.children, &rendered, output);
+        output.push_str(&format!("</{}>", element.name.qualified()));
+    }
+}
+
+#[derive(Clone)]
+struct Scope {
+    base: Option<IRI>,
+    language: Option<String>,
+}
+
+// How an attribute of a node or property element is read
+enum AttributeKind {
+    // rdf:ID, rdf:about and the others with a meaning of their own
+    Syntax,
+    Property(IRI),
+    // xml:* and reserved attributes, which carry no statements
+    Ignored,
+}
+
+fn classify(attribute: &Attribute, li_allowed: bool) -> Result<AttributeKind, String> {
+    let name = &attribute.name;
+    match name.namespace.as_deref() {
+        Some(XML_NAMESPACE) => return Ok(AttributeKind::Ignored),
+        None if name.local.to_lowercase().starts_with("xml") => return Ok(AttributeKind::Ignored),
+        None => return Err(format!("Unqualified attribute {}", name.local)),
+        Some(RDF_NAMESPACE) => match name.local.as_str() {
+            "ID" | "about" | "parseType" | "resource" | "nodeID" | "datatype" => return Ok(AttributeKind::Syntax),
+            "li" if li_allowed => {}
+            "RDF" | "Description" | "li" | "aboutEach" | "aboutEachPrefix" | "bagID" => {
+                return Err(format!("rdf:{} can not be used as an attribute", name.local))
+            }
+            _ => {}
+        },
+        Some(_) => {}
+    }
+    Ok(AttributeKind::Property(IRI::new(name.iri())))
+}
+
+pub struct RdfXmlDeserializer<I: Iterator<Item = char>> {
+    chars: Input<I>,
+    blank_nodes: BlankNodeScope,
+    base: Option<IRI>,
+    graph: Context,
+    ids: HashSet<String>,
+    pending: VecDeque<Quad>,
+    error: Option<ParseError>,
+    parsed: bool,
+}
+
+impl<I: Iterator<Item = char>> RdfXmlDeserializer<I> {
+    pub fn new(chars: I, blank_nodes: BlankNodeScope, graph: Context) -> RdfXmlDeserializer<I> {
+        RdfXmlDeserializer {
+            chars: Input::new(chars),
+            blank_nodes,
+            base: None,
+            graph,
+            ids: HashSet::new(),
+            pending: VecDeque::new(),
+            error: None,
+            parsed: false,
+        }
+    }
+
+    /// Sets the IRI relative IRIs are resolved against, until the document sets one with `xml:base`.
+    pub fn with_base(mut self, base: IRI) -> RdfXmlDeserializer<I> {
+        self.base = Some(base);
+        self
+    }
+
+    fn emit(&mut self, subject: &Identifier, predicate: IRI, object: Node) {
+        self.pending.push_back(Quad::new(subject.clone(), predicate, object, self.graph.clone()));
+    }
+
+    fn reify(&mut self, statement: IRI, subject: &Identifier, predicate: &IRI, object: &Node) {
+        let statement = Identifier::IRI(statement);
+        self.emit(&statement, RDF.iri("type"), RDF.iri("Statement").into());
+        self.emit(&statement, RDF.iri("subject"), subject.clone().into());
+        self.emit(&statement, RDF.iri("predicate"), predicate.into());
+        self.emit(&statement, RDF.iri("object"), object.clone());
+    }
+
+    fn resolve(&self, element: &Element, scope: &Scope, reference: &str) -> Result<IRI, ParseError> {
+        match &scope.base {
+            Some(base) => Ok(base.resolve(reference)),
+            None if is_absolute_iri(reference) => Ok(IRI::new(reference)),
+            None => Err(ParseError {
+                kind: ParseErrorKind::RelativeIRI(reference.to_owned()),
+                ..element.start.clone()
+            }),
+        }
+    }
+
+    // The scope of `element`, with its xml:base and xml:lang
+    fn scope(&self, element: &Element, parent: &Scope) -> Result<Scope, ParseError> {
+        let mut scope = parent.clone();
+        if let Some(base) = element.xml_attribute("base") {
+            scope.base = Some(self.resolve(element, parent, base)?);
+        }
+        if let Some(language) = element.xml_attribute("lang") {
+            scope.language = if language.is_empty() { None } else { Some(language.to_lowercase()) };
+        }
+        Ok(scope)
+    }
+
+    // The IRI of an rdf:ID, which must be unique within its base
+    fn id(&mut self, element: &Element, scope: &Scope, id: &str) -> Result<IRI, ParseError> {
+        if !is_ncname(id) {
+            return Err(element.error(&format!("Invalid rdf:ID {}", id)));
+        }
+        let iri = self.resolve(element, scope, &format!("#{}", id))?;
+        if !self.ids.insert(iri.value.clone()) {
+            return Err(element.error(&format!("Duplicate rdf:ID {}", id)));
+        }
+        Ok(iri)
+    }
+
+    fn node_id(&mut self, element: &Element, id: &str) -> Result<Identifier, ParseError> {
+        if !is_ncname(id) {
+            return Err(element.error(&format!("Invalid rdf:nodeID {}", id)));
+        }
+        Ok(Identifier::BlankNode(self.blank_nodes.labeled(id)))
+    }
+
+    fn literal(&self, text: String, scope: &Scope) -> Node {
+        Node::Literal(Literal::new(text, None, scope.language.clone()))
+    }
+
+    fn expect_whitespace(&self, element: &Element) -> Result<(), ParseError> {
+        if element.text().chars().all(is_xml_whitespace) {
+            Ok(())
+        } else {
+            Err(element.error("Unexpected text"))
+        }
+    }
+
+    fn node_element(&mut self, element: &Element, parent: &Scope) -> Result<Identifier, ParseError> {
+        let name = &element.name;
+        let forbidden = ["RDF", "ID", "about", "parseType", "resource", "nodeID", "datatype", "li", "aboutEach", "aboutEachPrefix", "bagID"];
+        if forbidden.iter().any(|local| name.is_rdf(local)) {
+            return Err(element.error(&format!("rdf:{} can not be used as a node element", name.local)));
+        }
+        if name.namespace.is_none() {
+            return Err(element.error(&format!("Unqualified node element {}", name.local)));
+        }
+        let scope = self.scope(element, parent)?;
+        let subject = match (element.attribute("ID"), element.attribute("about"), element.attribute("nodeID")) {
+            (Some(id), None, None) => Identifier::IRI(self.id(element, &scope, id)?),
+            (None, Some(about), None) => Identifier::IRI(self.resolve(element, &scope, about)?),
+            (None, None, Some(id)) => self.node_id(element, id)?,
+            (None, None, None) => Identifier::BlankNode(self.blank_nodes.fresh()),
+            _ => return Err(element.error("Only one of rdf:ID, rdf:about and rdf:nodeID is allowed")),
+        };
+        if !name.is_rdf("Description") {
+            self.emit(&subject, RDF.iri("type"), IRI::new(name.iri()).into());
+        }
+        for attribute in element.attributes.iter() {
+            match classify(attribute, false).map_err(|message| element.error(&message))? {
+                AttributeKind::Syntax if attribute.name.is_rdf("ID") || attribute.name.is_rdf("about") || attribute.name.is_rdf("nodeID") => {}
+                AttributeKind::Syntax => return Err(element.error(&format!("rdf:{} is not allowed on a node element", attribute.name.local))),
+                AttributeKind::Property(predicate) if predicate == RDF.iri("type") => {
+                    let object = self.resolve(element, &scope, &attribute.value)?;
+                    self.emit(&subject, predicate, object.into());
+                }
+                AttributeKind::Property(predicate) => {
+                    let object = self.literal(attribute.value.clone(), &scope);
+                    self.emit(&subject, predicate, object);
+                }
+                AttributeKind::Ignored => {}
+            }
+        }
+        self.expect_whitespace(element)?;
+        let mut li = 0;
+        for child in element.elements() {
+            self.property_element(child, &subject, &scope, &mut li)?;
+        }
+        Ok(subject)
+    }
+
+    fn property_element(&mut self, element: &Element, subject: &Identifier, parent: &Scope, li: &mut usize) -> Result<(), ParseError> {
+        let name = &element.name;
+        let forbidden = ["RDF", "ID", "about", "parseType", "resource", "nodeID", "datatype", "Description", "aboutEach", "aboutEachPrefix", "bagID"];
+        if forbidden.iter().any(|local| name.is_rdf(local)) {
+            return Err(element.error(&format!("rdf:{} can not be used as a property element", name.local)));
+        }
+        if name.namespace.is_none() {
+            return Err(element.error(&format!("Unqualified property element {}", name.local)));
+        }
+        let predicate = if name.is_rdf("li") {
+            *li += 1;
+            RDF.iri(format!("_{}", li))
+        } else {
+            IRI::new(name.iri())
+        };
+        let scope = self.scope(element, parent)?;
+        let statement = match element.attribute("ID") {
+            Some(id) => Some(self.id(element, &scope, id)?),
+            None => None,
+        };
+        let mut properties = vec![];
+        for attribute in element.attributes.iter() {
+            match classify(attribute, false).map_err(|message| element.error(&message))? {
+                AttributeKind::Syntax if attribute.name.is_rdf("about") => {
+                    return Err(element.error("rdf:about is not allowed on a property element"))
+                }
+                AttributeKind::Property(predicate) => properties.push((predicate, attribute.value.clone())),
+                _ => {}
+            }
+        }
+        let parse_type = element.attribute("parseType");
+        let resource = element.attribute("resource");
+        let node_id = element.attribute("nodeID");
+        let datatype = element.attribute("datatype");
+        if parse_type.is_some() && (resource.is_some() || node_id.is_some() || datatype.is_some() || !properties.is_empty()) {
+            return Err(element.error("rdf:parseType can not be used with other attributes"));
+        }
+        let children: Vec<&Element> = element.elements().collect();
+        let object = match parse_type {
+            Some("Resource") => {
+                self.expect_whitespace(element)?;
+                let object = Identifier::BlankNode(self.blank_nodes.fresh());
+                self.emit(subject, predicate.clone(), object.clone().into());
+                if let Some(statement) = statement {
+                    self.reify(statement, subject, &predicate, &object.clone().into());
+                }
+                let mut li = 0;
+                for child in children {
+                    self.property_element(child, &object, &scope, &mut li)?;
+                }
+                return Ok(());
+            }
+            Some("Collection") => {
+                self.expect_whitespace(element)?;
+                let mut items = vec![];
+                for child in children {
+                    items.push(self.node_element(child, &scope)?);
+                }
+                let mut list = Node::from(RDF.iri("nil"));
+                let cells: Vec<Identifier> = items.iter().map(|_| Identifier::BlankNode(self.blank_nodes.fresh())).collect();
+                let mut statements = vec![];
+                for (item, cell) in items.into_iter().zip(cells).rev() {
+                    statements.push((cell.clone(), RDF.iri("rest"), list));
+                    statements.push((cell.clone(), RDF.iri("first"), item.into()));
+                    list = cell.into();
+                }
+                self.emit(subject, predicate.clone(), list.clone());
+                for (cell, property, object) in statements.into_iter().rev() {
+                    self.emit(&cell, property, object);
+                }
+                list
+            }
+            // Other parse types are read as literals
+            Some(_) => {
+                let mut xml = String::new();
+                canonicalize(&element.children, &Namespaces::new(), &mut xml);
+                self.emit(subject, predicate.clone(), Literal::new(xml.clone(), RDF.iri("XMLLiteral"), None).into());
+                Literal::new(xml, RDF.iri("XMLLiteral"), None).into()
+            }
+            None if children.len() > 1 => return Err(element.error("A property element can only have one node element")),
+            None if children.len() == 1 => {
+                self.expect_whitespace(element)?;
+                if resource.is_some() || node_id.is_some() || datatype.is_some() || !properties.is_empty() {
+                    return Err(element.error("A property element with a node element can not have other attributes"));
+                }
+                let object: Node = self.node_element(children[0], &scope)?.into();
+                self.emit(subject, predicate.clone(), object.clone());
+                object
+            }
+            None if datatype.is_some() || !element.text().is_empty() => {
+                if resource.is_some() || node_id.is_some() || !properties.is_empty() {
+                    return Err(element.error("A literal property element can only have rdf:ID and rdf:datatype attributes"));
+                }
+                let object: Node = match datatype {
+                    Some(datatype) => Literal::new(element.text(), self.resolve(element, &scope, datatype)?, None).into(),
+                    None => self.literal(element.text(), &scope),
+                };
+                self.emit(subject, predicate.clone(), object.clone());
+                object
+            }
+            None if resource.is_none() && node_id.is_none() && properties.is_empty() => {
+                let object = self.literal(String::new(), &scope);
+                self.emit(subject, predicate.clone(), object.clone());
+                object
+            }
+            None => {
+                let object = match (resource, node_id) {
+                    (Some(resource), None) => Identifier::IRI(self.resolve(element, &scope, resource)?),
+                    (None, Some(id)) => self.node_id(element, id)?,
+                    (None, None) => Identifier::BlankNode(self.blank_nodes.fresh()),
+                    _ => return Err(element.error("Only one of rdf:resource and rdf:nodeID is allowed")),
+                };
+                self.emit(subject, predicate.clone(), object.clone().into());
+                for (property, value) in properties {
+                    let value = if property == RDF.iri("type") {
+                        self.resolve(element, &scope, &value)?.into()
+                    } else {
+                        self.literal(value, &scope)
+                    };
+                    self.emit(&object, property, value);
+                }
+                object.into()
+            }
+        };
+        if let Some(statement) = statement {
+            self.reify(statement, subject, &predicate, &object);
+        }
+        Ok(())
+    }
+
+    fn parse(&mut self) -> Result<(), ParseError> {
+        let root = XmlReader {
+            chars: &mut self.chars,
+            entities: HashMap::new(),
+            expanded: 0,
+            deepest: 0,
+        }
+        .document()?;
+        let scope = Scope {
+            base: self.base.clone(),
+            language: None,
+        };
+        if root.name.is_rdf("RDF") {
+            let scope = self.scope(&root, &scope)?;
+            self.expect_whitespace(&root)?;
+            for child in root.elements() {
+                self.node_element(child, &scope)?;
+            }
+        } else {
+            self.node_element(&root, &scope)?;
+        }
+        Ok(())
+    }
+}
+
+impl<I: Iterator<Item = char>> Iterator for RdfXmlDeserializer<I> {
+    type Item = Result<Quad, ParseError>;
+
+    // The statements before an error are read before it
+    fn next(&mut self) -> Option<Self::Item> {
+        if !self.parsed {
+            self.parsed = true;
+            self.error = self.parse().err();
+        }
+        match self.pending.pop_front() {
+            Some(quad) => Some(Ok(quad)),
+            None => self.error.take().map(Err),
+        }
+    }
+}
+
+/// Parses RDF/XML, with its statements in `graph`.
+pub fn deserialize<C>(xml: &str, graph: C) -> RdfXmlDeserializer<Chars<'_>>
+where
+    C: Into<Context>,
+{
+    RdfXmlDeserializer::new(xml.chars(), BlankNodeScope::default(), graph.into())
+}
+
+pub fn deserialize_with<C, G>(xml: &str, graph: C, generator: G) -> RdfXmlDeserializer<Chars<'_>>
+where
+    C: Into<Context>,
+    G: BlankNodeIdGenerator + 'static,
+{
+    RdfXmlDeserializer::new(xml.chars(), BlankNodeScope::new(generator), graph.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::namespace::{FOAF, RDF};
+    use crate::nquads_deserialize;
+    use crate::quad::Quad;
+    use crate::rdfxml_deserialize::deserialize;
+    use crate::term::{Identifier, Literal, IRI};
+    use crate::test_set;
+    use std::fs;
+
+    #[test]
+    fn test_deserialize() {
+        let xml = r#"<?xml version="1.0"?>
+<!DOCTYPE rdf:RDF [<!ENTITY ex "http://example.com#">]>
+<rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#" xmlns:foaf="http://xmlns.com/foaf/0.1/"
+         xml:base="http://example.com/people/">
+  <foaf:Person rdf:about="&ex;iddan" foaf:nick="id" xml:lang="en">
+    <foaf:name>Iddan</foaf:name>
+    <foaf:knows rdf:resource="tamir"/>
+    <foaf:age rdf:datatype="http://www.w3.org/2001/XMLSchema#integer">20</foaf:age>
+    <foaf:interest rdf:parseType="Collection">
+      <rdf:Description rdf:about="rdf"/>
+    </foaf:interest>
+  </foaf:Person>
+</rdf:RDF>"#;
+        let graph = Some(Identifier::IRI(IRI::new("http://example.com#graph")));
+        let quads: Vec<Quad> = deserialize(xml, graph.clone()).map(|quad| quad.unwrap()).collect();
+        let iddan = IRI::new("http://example.com#iddan");
+        assert_eq!(quads.len(), 8);
+        assert!(quads.iter().all(|quad| quad.context == graph));
+        assert!(quads.contains(&Quad::new(&iddan, RDF.iri("type"), FOAF.iri("Person"), graph.clone())));
+        assert!(quads.contains(&Quad::new(&iddan, FOAF.iri("nick"), Literal::new("id", None, Some("en".to_owned())), graph.clone())));
+        assert!(quads.contains(&Quad::new(&iddan, FOAF.iri("knows"), IRI::new("http://example.com/people/tamir"), graph)));
+        let error = deserialize("<rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">\n  <ex:a/>\n</rdf:RDF>", None)
+            .last()
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(error.line, 2);
+    }
+
+    #[test]
+    fn test_entity_expansion() {
+        let mut entities = "<!ENTITY lol0 \"lol\">".to_owned();
+        for level in 1..10 {
+            let references = format!("&lol{};", level - 1).repeat(10);
+            entities.push_str(&format!("<!ENTITY lol{} \"{}\">", level, references));
+        }
+        let xml = format!("<!DOCTYPE rdf:RDF [{}]>\n<rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\"/>", entities);
+        assert!(deserialize(&xml, None).any(|quad| quad.is_err()));
+        let wide = format!(
+            "<!DOCTYPE rdf:RDF [<!ENTITY lol \"{}\">]>\n<rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\"><rdf:Description rdf:about=\"{}\"/></rdf:RDF>",
+            "lol".repeat(1000),
+            "&lol;".repeat(1000)
+        );
+        assert!(deserialize(&wide, None).any(|quad| quad.is_err()));
+    }
+
+    // W3C RDF/XML test suite, driven by its manifest
+    #[test]
+    fn test_w3c_suite() {
+        let manifest = test_set::Manifest::read("src/w3c/rdf-xml", "http://www.w3.org/2013/RDFXMLTests/");
+        assert_eq!(manifest.entries.len(), 162);
+        for entry in manifest.entries {
+            let xml = fs::read_to_string(&entry.action).unwrap();
+            let base = IRI::new(format!("http://www.w3.org/2013/RDFXMLTests/{}", entry.action_name));
+            let result: Result<Vec<Quad>, _> = deserialize(&xml, None).with_base(base).collect();
+            match entry.kind.as_str() {
+                "TestXMLNegativeSyntax" => assert!(result.is_err(), "{} should be rejected", entry.name),
+                "TestXMLEval" => {
+                    let quads = result.unwrap_or_else(|error| panic!("{} should be accepted: {}", entry.name, error));
+                    let expected = fs::read_to_string(entry.result.unwrap()).unwrap();
+                    let expected: Vec<Quad> = nquads_deserialize::deserialize(&expected).map(|quad| quad.unwrap()).collect();
+                    assert!(test_set::isomorphic(&quads, &expected), "{} gave {:?}", entry.name, quads);
+                }
+                kind => panic!("Unknown test type {}", kind),
+            }
+        }
+    }
+}
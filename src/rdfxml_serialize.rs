@@ -0,0 +1,258 @@
+use crate::namespace::{PrefixMap, RDF, XSD};
+use crate::quad::{Context, Quad};
+use crate::rdfxml_deserialize::{escape_attribute, escape_text, is_name_char, is_name_start_char, is_ncname, RDF_NAMESPACE};
+use crate::term::{BlankNode, Identifier, Literal, Node, IRI};
+use log::warn;
+use std::collections::{BTreeMap, HashMap};
+
+// RDF/XML output, one rdf:Description or typed node element per subject with a property element per
+// statement. Namespaces are declared with the prefixes of the built-in namespaces, and `ns0`, `ns1`...
+// for the others. Blank nodes are written with rdf:nodeID.
+// RDF/XML has no named graphs: `serialize` drops the graphs of the quads, with a warning for each
+// named graph, and `serialize_graphs` writes a document per graph.
+
+const INDENT: &str = "    ";
+
+// Splits `iri` into a namespace and a local name usable as an XML name
+fn split_iri(iri: &IRI) -> Option<(&str, &str)> {
+    let value = iri.value.as_str();
+    let mut start = value.len();
+    for (index, c) in value.char_indices().rev() {
+        if !is_name_char(c) || c == ':' {
+            break;
+        }
+        start = index;
+    }
+    let local = value[start..].trim_start_matches(|c: char| !is_name_start_char(c));
+    if local.is_empty() {
+        return None;
+    }
+    Some((&value[..value.len() - local.len()], local))
+}
+
+// Characters XML 1.0 can not represent, even as references
+fn is_xml_char(c: char) -> bool {
+    !matches!(c, '\u{0}'..='\u{8}' | '\u{B}' | '\u{C}' | '\u{E}'..='\u{1F}' | '\u{FFFE}' | '\u{FFFF}')
+}
+
+fn check_text(value: &str) -> Result<(), String> {
+    match value.chars().find(|c| !is_xml_char(*c)) {
+        Some(c) => Err(format!("Character U+{:04X} can not be written in RDF/XML", c as u32)),
+        None => Ok(()),
+    }
+}
+
+struct RdfXmlWriter {
+    known: PrefixMap,
+    // Declared namespaces by address
+    namespaces: BTreeMap<String, String>,
+    node_ids: HashMap<BlankNode, String>,
+}
+
+impl RdfXmlWriter {
+    fn new() -> RdfXmlWriter {
+        let mut namespaces = BTreeMap::new();
+        namespaces.insert(RDF_NAMESPACE.to_owned(), "rdf".to_owned());
+        RdfXmlWriter {
+            known: PrefixMap::default(),
+            namespaces,
+            node_ids: HashMap::new(),
+        }
+    }
+
+    fn qualified_name(&mut self, iri: &IRI) -> Option<String> {
+        let (namespace, local) = split_iri(iri)?;
+        let generated = self.namespaces.len() - 1;
+        let known = &self.known;
+        let prefix = self.namespaces.entry(namespace.to_owned()).or_insert_with(|| {
+            match known.iter().filter(|(_, address)| *address == namespace).min() {
+                Some((prefix, _)) => prefix.to_owned(),
+                None => format!("ns{}", generated),
+            }
+        });
+        Some(format!("{}:{}", prefix, local))
+    }
+
+    // Labels are kept when they are valid node IDs
+    fn node_id(&mut self, blank_node: &BlankNode) -> String {
+        let count = self.node_ids.len();
+        self.node_ids
+            .entry(blank_node.clone())
+            .or_insert_with(|| match &blank_node.value {
+                label if is_ncname(label) && !label.starts_with("genid") => label.clone(),
+                _ => format!("genid{}", count),
+            })
+            .clone()
+    }
+
+    fn identifier_attribute(&mut self, identifier: &Identifier, iri_attribute: &str) -> Result<String, String> {
+        match identifier {
+            Identifier::IRI(iri) => {
+                check_text(&iri.value)?;
+                Ok(format!("rdf:{}=\"{}\"", iri_attribute, escape_attribute(&iri.value)))
+            }
+            Identifier::BlankNode(blank_node) => Ok(format!("rdf:nodeID=\"{}\"", self.node_id(blank_node))),
+        }
+    }
+
+    fn property(&mut self, predicate: &IRI, object: &Node) -> Result<String, String> {
+        let name = self
+            .qualified_name(predicate)
+            .ok_or_else(|| format!("Predicate {} can not be written in RDF/XML", predicate.value))?;
+        match object {
+            Node::IRI(iri) => Ok(format!("<{} {}/>", name, self.identifier_attribute(&Identifier::IRI(iri.clone()), "resource")?)),
+            Node::BlankNode(blank_node) => Ok(format!("<{} rdf:nodeID=\"{}\"/>", name, self.node_id(blank_node))),
+            Node::Literal(literal) => self.literal(&name, literal),
+        }
+    }
+
+    fn literal(&mut self, name: &str, literal: &Literal) -> Result<String, String> {
+        check_text(&literal.value)?;
+        let attributes = match &literal.language {
+            Some(language) => format!(" xml:lang=\"{}\"", escape_attribute(language)),
+            None if literal.datatype == XSD.iri("string") => String::new(),
+            None => format!(" rdf:datatype=\"{}\"", escape_attribute(&literal.datatype.value)),
+        };
+        Ok(format!("<{}{}>{}</{}>", name, attributes, escape_text(&literal.value), name))
+    }
+
+    // A typed node element for the first rdf:type that has a qualified name
+    fn node_element(&mut self, subject: &Identifier, predicates: &BTreeMap<IRI, Vec<Node>>) -> Result<String, String> {
+        let mut type_name = None;
+        let mut properties = vec![];
+        for (predicate, objects) in predicates {
+            for object in objects {
+                if let (None, Node::IRI(iri)) = (&type_name, object) {
+                    if *predicate == RDF.iri("type") {
+                        type_name = self.qualified_name(iri);
+                        if type_name.is_some() {
+                            continue;
+                        }
+                    }
+                }
+                properties.push(format!("{}{}{}\n", INDENT, INDENT, self.property(predicate, object)?));
+            }
+        }
+        let name = type_name.unwrap_or_else(|| "rdf:Description".to_owned());
+        let subject = self.identifier_attribute(subject, "about")?;
+        if properties.is_empty() {
+            return Ok(format!("{}<{} {}/>\n", INDENT, name, subject));
+        }
+        Ok(format!("{}<{} {}>\n{}{}</{}>\n", INDENT, name, subject, properties.concat(), INDENT, name))
+    }
+
+    fn document<Q>(mut self, quads: Q) -> Result<String, String>
+    where
+        Q: IntoIterator<Item = Quad>,
+    {
+        let mut statements: BTreeMap<Identifier, BTreeMap<IRI, Vec<Node>>> = BTreeMap::new();
+        for quad in quads {
+            let objects = statements.entry(quad.subject).or_default().entry(quad.predicate).or_default();
+            if !objects.contains(&quad.object) {
+                objects.push(quad.object);
+            }
+        }
+        let mut elements = vec![];
+        for (subject, mut predicates) in statements {
+            predicates.values_mut().for_each(|objects| objects.sort());
+            elements.push(self.node_element(&subject, &predicates)?);
+        }
+        let mut namespaces: Vec<(&String, &String)> = self.namespaces.iter().map(|(address, prefix)| (prefix, address)).collect();
+        namespaces.sort_by_key(|(prefix, _)| (prefix.as_str() != "rdf", prefix.as_str()));
+        let declarations: Vec<String> = namespaces
+            .into_iter()
+            .map(|(prefix, address)| format!("xmlns:{}=\"{}\"", prefix, escape_attribute(address)))
+            .collect();
+        Ok(format!(
+            "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<rdf:RDF {}>\n{}</rdf:RDF>\n",
+            declarations.join(&format!("\n{}", " ".repeat("<rdf:RDF ".len()))),
+            elements.concat()
+        ))
+    }
+}
+
+/// Serializes the triples of `quads` as one RDF/XML document. The graphs of the quads are dropped,
+/// and a warning is logged for each named graph.
+/// Fails on predicates that can not be written as XML names and on characters XML can not represent.
+pub fn serialize<Q>(quads: Q) -> Result<String, String>
+where
+    Q: IntoIterator<Item = Quad>,
+{
+    let quads: Vec<Quad> = quads.into_iter().collect();
+    let mut graphs: Vec<&Identifier> = quads.iter().filter_map(|quad| quad.context.as_ref()).collect();
+    graphs.sort();
+    graphs.dedup();
+    for graph in graphs {
+        warn!("Dropped named graph {:?}, RDF/XML has no named graphs", graph);
+    }
+    RdfXmlWriter::new().document(quads)
+}
+
+/// Serializes each graph of `quads` as its own RDF/XML document.
+pub fn serialize_graphs<Q>(quads: Q) -> Result<BTreeMap<Context, String>, String>
+where
+    Q: IntoIterator<Item = Quad>,
+{
+    let mut graphs: BTreeMap<Context, Vec<Quad>> = BTreeMap::new();
+    for quad in quads {
+        graphs.entry(quad.context.clone()).or_default().push(quad);
+    }
+    graphs
+        .into_iter()
+        .map(|(graph, quads)| Ok((graph, RdfXmlWriter::new().document(quads)?)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::namespace::{FOAF, RDF};
+    use crate::quad::Quad;
+    use crate::rdfxml_deserialize::deserialize;
+    use crate::rdfxml_serialize::{serialize, serialize_graphs};
+    use crate::term::{BlankNode, Identifier, Literal, IRI};
+    use crate::test_set;
+
+    #[test]
+    fn test_serialize() {
+        let quads: Vec<Quad> = test_set::get_quads().into_iter().collect();
+        let xml = serialize(quads.clone()).unwrap();
+        let triples: Vec<Quad> = quads.iter().cloned().map(|quad| Quad { context: None, ..quad }).collect();
+        let parsed: Vec<Quad> = deserialize(&xml, None).map(|quad| quad.unwrap()).collect();
+        assert!(test_set::isomorphic(&parsed, &triples));
+
+        let iddan = IRI::new("http://example.com/people#iddan");
+        let quads = vec![
+            Quad::new(&iddan, RDF.iri("type"), FOAF.iri("Person"), None),
+            Quad::new(&iddan, FOAF.iri("name"), Literal::new("Iddan & <co>", None, Some("en".to_owned())), None),
+            Quad::new(&iddan, FOAF.iri("knows"), BlankNode::from_value("tamir"), None),
+        ];
+        assert_eq!(
+            serialize(quads).unwrap(),
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#"
+         xmlns:foaf="http://xmlns.com/foaf/0.1/">
+    <foaf:Person rdf:about="http://example.com/people#iddan">
+        <foaf:knows rdf:nodeID="tamir"/>
+        <foaf:name xml:lang="en">Iddan &amp; &lt;co&gt;</foaf:name>
+    </foaf:Person>
+</rdf:RDF>
+"#
+        );
+        assert!(serialize(vec![Quad::new(&iddan, IRI::new("http://example.com/1"), &iddan, None)]).is_err());
+    }
+
+    #[test]
+    fn test_serialize_graphs() {
+        let mut quads: Vec<Quad> = test_set::get_quads().into_iter().collect();
+        quads.push(Quad::new(IRI::new("http://example.com#iddan"), FOAF.iri("nick"), Literal::new("id", None, None), None));
+        let documents = serialize_graphs(quads.clone()).unwrap();
+        let graphs: Vec<_> = documents.keys().cloned().collect();
+        assert!(graphs.contains(&None));
+        assert!(graphs.contains(&Some(Identifier::IRI(IRI::new("http://example.com#ontology")))));
+        for (graph, xml) in documents {
+            let parsed: Vec<Quad> = deserialize(&xml, graph.clone()).map(|quad| quad.unwrap()).collect();
+            let expected: Vec<Quad> = quads.iter().filter(|quad| quad.context == graph).cloned().collect();
+            assert!(test_set::isomorphic(&parsed, &expected));
+        }
+    }
+}
@@ -0,0 +1,920 @@
+use crate::blank_node_id::{BlankNodeScope, Counter};
+use crate::compression;
+use crate::dataset::Dataset;
+use crate::jsonld_deserialize::parse_json;
+use crate::namespace::{QL, RDF, RML, RR, XSD};
+use crate::nquads_deserialize::is_absolute_iri;
+use crate::parse_error::{ParseError, ParseErrorKind};
+use crate::quad::{Context, Quad};
+use crate::term::{Identifier, Literal, Node, IRI};
+use crate::turtle_deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::Path;
+
+// RML (https://rml.io/specs/rml/) mappings over JSON sources. A mapping is a Turtle document of
+// triples maps, each iterating over the records its `rml:iterator` JSONPath selects in its
+// `rml:source`, and generating terms from `rr:constant`s, `rml:reference` JSONPaths relative to the
+// record and `rr:template`s. Referencing object maps join on `rr:joinCondition`s, or without any
+// use the same record when the parent iterates over the same records and else all of its records.
+// JSONPath is limited to names, indices, `*` and `..`, with no filters or slices.
+// R2RML (https://www.w3.org/TR/r2rml/) triples maps, with an `rr:logicalTable` instead, are read
+// too and run over database rows by `r2rml`.
+
+// Mappings are read from their triples, so their errors have no position
+fn error(kind: ParseErrorKind) -> ParseError {
+    ParseError::new(kind, 0, 0, 0, String::new())
+}
+
+fn invalid(message: String) -> ParseError {
+    error(ParseErrorKind::InvalidMapping(message))
+}
+
+/// A value a reference evaluates to, with its natural datatype, such as xsd:integer for JSON integers.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Datum {
+    pub value: String,
+    pub datatype: Option<IRI>,
+}
+
+impl Datum {
+    pub fn new<V: Into<String>>(value: V, datatype: Option<IRI>) -> Datum {
+        Datum { value: value.into(), datatype }
+    }
+}
+
+/// A record of a logical source, on which the references of term maps are evaluated.
+pub(crate) trait Record {
+    fn values(&self, reference: &str) -> Result<Vec<Datum>, ParseError>;
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Step {
+    Child(String),
+    Index(i64),
+    Wildcard,
+    // `..name`, or `..*` without a name
+    Descendants(Option<String>),
+}
+
+// Paths not starting with `$` are relative to the record, as references are
+fn parse_path(path: &str) -> Result<Vec<Step>, ParseError> {
+    let error = || error(ParseErrorKind::Unsupported(format!("JSONPath {}", path)));
+    let chars: Vec<char> = path.trim().chars().collect();
+    let mut index = 0;
+    let name = |index: &mut usize| -> String {
+        let start = *index;
+        while *index < chars.len() && chars[*index] != '.' && chars[*index] != '[' {
+            *index += 1;
+        }
+        chars[start..*index].iter().collect()
+    };
+    let mut steps = vec![];
+    match chars.first() {
+        Some('$') => index = 1,
+        Some('.') | Some('[') | None => {}
+        Some(_) => steps.push(Step::Child(name(&mut index))),
+    }
+    while index < chars.len() {
+        match chars[index] {
+            '.' if chars.get(index + 1) == Some(&'.') => {
+                index += 2;
+                match name(&mut index).as_str() {
+                    "" => return Err(error()),
+                    "*" => steps.push(Step::Descendants(None)),
+                    child => steps.push(Step::Descendants(Some(child.to_owned()))),
+                }
+            }
+            '.' => {
+                index += 1;
+                match name(&mut index).as_str() {
+                    "" => return Err(error()),
+                    "*" => steps.push(Step::Wildcard),
+                    child => steps.push(Step::Child(child.to_owned())),
+                }
+            }
+            '[' => {
+                let end = index + chars[index..].iter().position(|c| *c == ']').ok_or_else(error)?;
+                let selector: String = chars[index + 1..end].iter().collect();
+                let selector = selector.trim();
+                let quoted = selector.len() >= 2
+                    && (selector.starts_with('\'') && selector.ends_with('\'') || selector.starts_with('"') && selector.ends_with('"'));
+                if selector == "*" {
+                    steps.push(Step::Wildcard);
+                } else if quoted {
+                    steps.push(Step::Child(selector[1..selector.len() - 1].to_owned()));
+                } else {
+                    steps.push(Step::Index(selector.parse().map_err(|_| error())?));
+                }
+                index = end + 1;
+            }
+            _ => return Err(error()),
+        }
+    }
+    Ok(steps)
+}
+
+fn descendants<'a>(value: &'a Value, found: &mut Vec<&'a Value>) {
+    found.push(value);
+    match value {
+        Value::Array(values) => values.iter().for_each(|value| descendants(value, found)),
+        Value::Object(object) => object.values().for_each(|value| descendants(value, found)),
+        _ => {}
+    }
+}
+
+fn select<'a>(value: &'a Value, steps: &[Step]) -> Vec<&'a Value> {
+    let mut selected = vec![value];
+    for step in steps {
+        let mut next = vec![];
+        for value in selected {
+            match (step, value) {
+                (Step::Child(name), Value::Object(object)) => next.extend(object.get(name)),
+                (Step::Index(index), Value::Array(values)) => {
+                    let index = if *index < 0 { values.len() as i64 + index } else { *index };
+                    if index >= 0 {
+                        next.extend(values.get(index as usize));
+                    }
+                }
+                (Step::Wildcard, Value::Array(values)) => next.extend(values),
+                (Step::Wildcard, Value::Object(object)) => next.extend(object.values()),
+                (Step::Descendants(name), value) => {
+                    let mut found = vec![];
+                    descendants(value, &mut found);
+                    for value in found {
+                        match (name, value) {
+                            (Some(name), Value::Object(object)) => next.extend(object.get(name)),
+                            (None, Value::Array(values)) => next.extend(values),
+                            (None, Value::Object(object)) => next.extend(object.values()),
+                            _ => {}
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        selected = next;
+    }
+    selected
+}
+
+fn data(value: &Value, found: &mut Vec<Datum>) {
+    match value {
+        Value::Null => {}
+        Value::Bool(value) => found.push(Datum::new(value.to_string(), Some(XSD.iri("boolean")))),
+        Value::Number(number) if number.is_f64() => found.push(Datum::new(number.to_string(), Some(XSD.iri("double")))),
+        Value::Number(number) => found.push(Datum::new(number.to_string(), Some(XSD.iri("integer")))),
+        Value::String(value) => found.push(Datum::new(value.as_str(), None)),
+        Value::Array(values) => values.iter().for_each(|value| data(value, found)),
+        Value::Object(_) => found.push(Datum::new(value.to_string(), None)),
+    }
+}
+
+impl Record for &Value {
+    fn values(&self, reference: &str) -> Result<Vec<Datum>, ParseError> {
+        let mut found = vec![];
+        for value in select(self, &parse_path(reference)?) {
+            data(value, &mut found);
+        }
+        Ok(found)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TemplatePart {
+    Text(String),
+    Reference(String),
+}
+
+fn parse_template(template: &str) -> Result<Vec<TemplatePart>, ParseError> {
+    let mut parts = vec![];
+    let mut text = String::new();
+    let mut reference: Option<String> = None;
+    let mut chars = template.chars();
+    while let Some(c) = chars.next() {
+        match (c, &mut reference) {
+            ('\\', _) => match chars.next() {
+                Some(escaped) => reference.as_mut().unwrap_or(&mut text).push(escaped),
+                None => return Err(error(ParseErrorKind::InvalidTemplate(template.to_owned()))),
+            },
+            ('{', None) => {
+                if !text.is_empty() {
+                    parts.push(TemplatePart::Text(std::mem::take(&mut text)));
+                }
+                reference = Some(String::new());
+            }
+            ('}', Some(name)) => {
+                parts.push(TemplatePart::Reference(std::mem::take(name)));
+                reference = None;
+            }
+            ('{', Some(_)) | ('}', None) => return Err(error(ParseErrorKind::InvalidTemplate(template.to_owned()))),
+            (c, Some(name)) => name.push(c),
+            (c, None) => text.push(c),
+        }
+    }
+    if reference.is_some() {
+        return Err(error(ParseErrorKind::InvalidTemplate(template.to_owned())));
+    }
+    if !text.is_empty() {
+        parts.push(TemplatePart::Text(text));
+    }
+    Ok(parts)
+}
+
+// Percent-encodes the characters that are not iunreserved (RFC 3987)
+fn iri_safe(value: &str) -> String {
+    let mut encoded = String::new();
+    for c in value.chars() {
+        match c {
+            'A'..='Z' | 'a'..='z' | '0'..='9' | '-' | '.' | '_' | '~' => encoded.push(c),
+            c if !c.is_ascii() => encoded.push(c),
+            c => encoded.push_str(&format!("%{:02X}", c as u32)),
+        }
+    }
+    encoded
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[allow(clippy::upper_case_acronyms)]
+enum TermType {
+    IRI,
+    BlankNode,
+    Literal,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TermSource {
+    Constant(Node),
+    Reference(String),
+    Template(Vec<TemplatePart>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct TermMap {
+    source: TermSource,
+    term_type: TermType,
+    datatype: Option<IRI>,
+    language: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Position {
+    Subject,
+    Predicate,
+    Object,
+    Graph,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Join {
+    child: String,
+    parent: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum ObjectMap {
+    Term(TermMap),
+    Parent { triples_map: usize, joins: Vec<Join> },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct PredicateObjectMap {
+    predicates: Vec<TermMap>,
+    objects: Vec<ObjectMap>,
+    graphs: Vec<TermMap>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum LogicalSource {
+    Json { source: String, iterator: String },
+    // The SQL query of an `rr:logicalTable`
+    Table { query: String },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct TriplesMap {
+    name: String,
+    logical_source: LogicalSource,
+    subject: TermMap,
+    classes: Vec<IRI>,
+    graphs: Vec<TermMap>,
+    predicate_objects: Vec<PredicateObjectMap>,
+}
+
+// The statements of a mapping document by subject, in document order
+struct Description {
+    statements: HashMap<Identifier, Vec<(IRI, Node)>>,
+}
+
+fn node_identifier(node: &Node) -> Option<Identifier> {
+    match node {
+        Node::IRI(iri) => Some(Identifier::IRI(iri.clone())),
+        Node::BlankNode(blank_node) => Some(Identifier::BlankNode(blank_node.clone())),
+        Node::Literal(_) => None,
+    }
+}
+
+fn identifier_node(identifier: Identifier) -> Node {
+    match identifier {
+        Identifier::IRI(iri) => Node::IRI(iri),
+        Identifier::BlankNode(blank_node) => Node::BlankNode(blank_node),
+    }
+}
+
+// The query of the rows of the table of a schema-qualified name. Each part of the name is a
+// regular or a delimited identifier, and is written delimited in the query.
+fn table_query(name: &str) -> Option<String> {
+    let mut parts = vec![];
+    let mut chars = name.chars().peekable();
+    loop {
+        let mut part = String::new();
+        if chars.next_if_eq(&'"').is_some() {
+            loop {
+                match chars.next()? {
+                    '"' if chars.next_if_eq(&'"').is_some() => part.push('"'),
+                    '"' => break,
+                    c => part.push(c),
+                }
+            }
+        } else {
+            while let Some(c) = chars.next_if(|&c| c != '.') {
+                part.push(c);
+            }
+            let mut identifier = part.chars();
+            let start = identifier.next().is_some_and(|c| c.is_alphabetic() || c == '_');
+            if !start || !identifier.all(|c| c.is_alphanumeric() || c == '_') {
+                return None;
+            }
+        }
+        if part.is_empty() {
+            return None;
+        }
+        parts.push(format!("\"{}\"", part.replace('"', "\"\"")));
+        match chars.next() {
+            None => return Some(format!("SELECT * FROM {}", parts.join("."))),
+            Some('.') => {}
+            Some(_) => return None,
+        }
+    }
+}
+
+fn node_name(identifier: &Identifier) -> String {
+    match identifier {
+        Identifier::IRI(iri) => format!("<{}>", iri.value),
+        Identifier::BlankNode(blank_node) => format!("_:{}", blank_node.value),
+    }
+}
+
+impl Description {
+    fn objects<'a>(&'a self, subject: &Identifier, predicate: &IRI) -> Vec<&'a Node> {
+        self.statements
+            .get(subject)
+            .map(|statements| statements.iter().filter(|(p, _)| p == predicate).map(|(_, object)| object).collect())
+            .unwrap_or_default()
+    }
+
+    fn object<'a>(&'a self, subject: &Identifier, predicate: &IRI) -> Result<Option<&'a Node>, ParseError> {
+        match self.objects(subject, predicate).as_slice() {
+            [] => Ok(None),
+            [object] => Ok(Some(object)),
+            _ => Err(invalid(format!("{} has more than one {}", node_name(subject), predicate.value))),
+        }
+    }
+
+    fn string(&self, subject: &Identifier, predicate: &IRI) -> Result<Option<String>, ParseError> {
+        match self.object(subject, predicate)? {
+            None => Ok(None),
+            Some(Node::Literal(literal)) => Ok(Some(literal.value.clone())),
+            Some(_) => Err(invalid(format!("The {} of {} must be a literal", predicate.value, node_name(subject)))),
+        }
+    }
+
+    fn iri(&self, subject: &Identifier, predicate: &IRI) -> Result<Option<IRI>, ParseError> {
+        match self.object(subject, predicate)? {
+            None => Ok(None),
+            Some(Node::IRI(iri)) => Ok(Some(iri.clone())),
+            Some(_) => Err(invalid(format!("The {} of {} must be an IRI", predicate.value, node_name(subject)))),
+        }
+    }
+
+    fn term_map(&self, node: &Node, position: Position) -> Result<TermMap, ParseError> {
+        let id = node_identifier(node).ok_or_else(|| invalid("Term maps must be IRIs or blank nodes".to_owned()))?;
+        let constant = self.object(&id, &RR.iri("constant"))?.cloned();
+        let template = self.string(&id, &RR.iri("template"))?;
+        let reference = match self.string(&id, &RML.iri("reference"))? {
+            Some(reference) => Some(reference),
+            None => self.string(&id, &RR.iri("column"))?,
+        };
+        let datatype = self.iri(&id, &RR.iri("datatype"))?;
+        let language = self.string(&id, &RR.iri("language"))?;
+        let source = match (constant, template, reference) {
+            (Some(constant), None, None) => return Ok(constant_map(constant)),
+            (None, Some(template), None) => TermSource::Template(parse_template(&template)?),
+            (None, None, Some(reference)) => TermSource::Reference(reference),
+            _ => return Err(invalid(format!("Term map {} needs one of a constant, a template or a reference", node_name(&id)))),
+        };
+        let term_type = match self.iri(&id, &RR.iri("termType"))? {
+            Some(term_type) if term_type == RR.iri("IRI") => TermType::IRI,
+            Some(term_type) if term_type == RR.iri("BlankNode") => TermType::BlankNode,
+            Some(term_type) if term_type == RR.iri("Literal") => TermType::Literal,
+            Some(term_type) => return Err(invalid(format!("Unknown term type {}", term_type.value))),
+            None => match (&source, position) {
+                (TermSource::Reference(_), Position::Object) => TermType::Literal,
+                (_, Position::Object) if datatype.is_some() || language.is_some() => TermType::Literal,
+                _ => TermType::IRI,
+            },
+        };
+        let allowed = match position {
+            Position::Subject => term_type != TermType::Literal,
+            Position::Predicate => term_type == TermType::IRI,
+            Position::Object => true,
+            Position::Graph => term_type != TermType::Literal,
+        };
+        if !allowed {
+            return Err(invalid(format!("Term map {} can not generate {:?} terms", node_name(&id), term_type)));
+        }
+        Ok(TermMap { source, term_type, datatype, language })
+    }
+
+    // Term maps given by the `map` property, or as constants by the `shortcut` property
+    fn term_maps(&self, id: &Identifier, map: &str, shortcut: &str, position: Position) -> Result<Vec<TermMap>, ParseError> {
+        let mut term_maps = vec![];
+        for node in self.objects(id, &RR.iri(map)) {
+            term_maps.push(self.term_map(node, position)?);
+        }
+        for node in self.objects(id, &RR.iri(shortcut)) {
+            term_maps.push(constant_map(node.clone()));
+        }
+        Ok(term_maps)
+    }
+
+    fn logical_source(&self, id: &Identifier) -> Result<LogicalSource, ParseError> {
+        if let Some(table) = self.object(id, &RR.iri("logicalTable"))?.and_then(node_identifier) {
+            return match (self.string(&table, &RR.iri("tableName"))?, self.string(&table, &RR.iri("sqlQuery"))?) {
+                (Some(name), None) => match table_query(&name) {
+                    Some(query) => Ok(LogicalSource::Table { query }),
+                    None => Err(invalid(format!("Invalid table name {}", name))),
+                },
+                (None, Some(query)) => Ok(LogicalSource::Table { query }),
+                _ => Err(invalid(format!("Logical table {} needs a table name or a SQL query", node_name(&table)))),
+            };
+        }
+        let node = self
+            .object(id, &RML.iri("logicalSource"))?
+            .and_then(node_identifier)
+            .ok_or_else(|| invalid(format!("Triples map {} has no logical source", node_name(id))))?;
+        let source = match self.object(&node, &RML.iri("source"))? {
+            Some(Node::Literal(literal)) => literal.value.clone(),
+            Some(Node::IRI(iri)) => iri.value.clone(),
+            _ => return Err(invalid(format!("Logical source {} has no source", node_name(&node)))),
+        };
+        match self.iri(&node, &RML.iri("referenceFormulation"))? {
+            Some(formulation) if formulation != QL.iri("JSONPath") => {
+                return Err(error(ParseErrorKind::Unsupported(format!("reference formulation {}", formulation.value))))
+            }
+            _ => {}
+        }
+        let iterator = self.string(&node, &RML.iri("iterator"))?.unwrap_or_else(|| "$".to_owned());
+        parse_path(&iterator)?;
+        Ok(LogicalSource::Json { source, iterator })
+    }
+
+    fn triples_map(&self, id: &Identifier, logical_source: LogicalSource, names: &HashMap<Identifier, usize>) -> Result<TriplesMap, ParseError> {
+        let mut subjects = self.term_maps(id, "subjectMap", "subject", Position::Subject)?;
+        if subjects.len() != 1 {
+            return Err(invalid(format!("Triples map {} needs exactly one subject map", node_name(id))));
+        }
+        let subject_node = self.object(id, &RR.iri("subjectMap"))?.and_then(node_identifier);
+        let mut classes = vec![];
+        let mut graphs = vec![];
+        if let Some(subject_node) = &subject_node {
+            for class in self.objects(subject_node, &RR.iri("class")) {
+                match class {
+                    Node::IRI(class) => classes.push(class.clone()),
+                    _ => return Err(invalid(format!("Classes of {} must be IRIs", node_name(subject_node)))),
+                }
+            }
+            graphs = self.term_maps(subject_node, "graphMap", "graph", Position::Graph)?;
+        }
+        let mut predicate_objects = vec![];
+        for node in self.objects(id, &RR.iri("predicateObjectMap")) {
+            let pom = node_identifier(node).ok_or_else(|| invalid("Predicate object maps must be IRIs or blank nodes".to_owned()))?;
+            let predicates = self.term_maps(&pom, "predicateMap", "predicate", Position::Predicate)?;
+            let mut objects = vec![];
+            for object in self.objects(&pom, &RR.iri("objectMap")) {
+                let object_id = node_identifier(object).ok_or_else(|| invalid("Object maps must be IRIs or blank nodes".to_owned()))?;
+                match self.object(&object_id, &RR.iri("parentTriplesMap"))?.and_then(node_identifier) {
+                    Some(parent) => {
+                        let triples_map = *names
+                            .get(&parent)
+                            .ok_or_else(|| invalid(format!("{} is not a triples map", node_name(&parent))))?;
+                        let mut joins = vec![];
+                        for condition in self.objects(&object_id, &RR.iri("joinCondition")) {
+                            let condition = node_identifier(condition).ok_or_else(|| invalid("Join conditions must be IRIs or blank nodes".to_owned()))?;
+                            match (self.string(&condition, &RR.iri("child"))?, self.string(&condition, &RR.iri("parent"))?) {
+                                (Some(child), Some(parent)) => joins.push(Join { child, parent }),
+                                _ => return Err(invalid(format!("Join condition {} needs a child and a parent", node_name(&condition)))),
+                            }
+                        }
+                        objects.push(ObjectMap::Parent { triples_map, joins });
+                    }
+                    None => objects.push(ObjectMap::Term(self.term_map(object, Position::Object)?)),
+                }
+            }
+            for object in self.objects(&pom, &RR.iri("object")) {
+                objects.push(ObjectMap::Term(constant_map(object.clone())));
+            }
+            let graphs = self.term_maps(&pom, "graphMap", "graph", Position::Graph)?;
+            if predicates.is_empty() || objects.is_empty() {
+                return Err(invalid(format!("Predicate object map {} needs a predicate and an object", node_name(&pom))));
+            }
+            predicate_objects.push(PredicateObjectMap { predicates, objects, graphs });
+        }
+        Ok(TriplesMap {
+            name: node_name(id),
+            logical_source,
+            subject: subjects.remove(0),
+            classes,
+            graphs,
+            predicate_objects,
+        })
+    }
+}
+
+fn constant_map(node: Node) -> TermMap {
+    let term_type = match &node {
+        Node::IRI(_) => TermType::IRI,
+        Node::BlankNode(_) => TermType::BlankNode,
+        Node::Literal(_) => TermType::Literal,
+    };
+    TermMap {
+        source: TermSource::Constant(node),
+        term_type,
+        datatype: None,
+        language: None,
+    }
+}
+
+struct Generator {
+    base: Option<IRI>,
+    blank_nodes: BlankNodeScope,
+}
+
+impl Generator {
+    fn template<R: Record>(&self, parts: &[TemplatePart], record: &R, term_type: TermType) -> Result<Vec<String>, ParseError> {
+        let mut values = vec![String::new()];
+        for part in parts {
+            match part {
+                TemplatePart::Text(text) => values.iter_mut().for_each(|value| value.push_str(text)),
+                TemplatePart::Reference(reference) => {
+                    let data = record.values(reference)?;
+                    let mut expanded = vec![];
+                    for value in &values {
+                        for datum in &data {
+                            let datum = if term_type == TermType::IRI { iri_safe(&datum.value) } else { datum.value.clone() };
+                            expanded.push(format!("{}{}", value, datum));
+                        }
+                    }
+                    values = expanded;
+                }
+            }
+        }
+        Ok(values)
+    }
+
+    fn terms<R: Record>(&mut self, map: &TermMap, record: &R) -> Result<Vec<Node>, ParseError> {
+        let data = match &map.source {
+            TermSource::Constant(node) => return Ok(vec![node.clone()]),
+            TermSource::Reference(reference) => record.values(reference)?,
+            TermSource::Template(parts) => self
+                .template(parts, record, map.term_type)?
+                .into_iter()
+                .map(|value| Datum::new(value, None))
+                .collect(),
+        };
+        let mut terms = vec![];
+        for datum in data {
+            terms.push(match map.term_type {
+                TermType::IRI if is_absolute_iri(&datum.value) => Node::IRI(IRI::new(datum.value)),
+                TermType::IRI => match &self.base {
+                    Some(base) => Node::IRI(base.resolve(&datum.value)),
+                    None => return Err(error(ParseErrorKind::RelativeIRI(datum.value))),
+                },
+                TermType::BlankNode => Node::BlankNode(self.blank_nodes.labeled(&datum.value)),
+                TermType::Literal => {
+                    let datatype = if map.language.is_some() { None } else { map.datatype.clone().or(datum.datatype) };
+                    Node::Literal(Literal::new(datum.value, datatype, map.language.clone()))
+                }
+            });
+        }
+        Ok(terms)
+    }
+
+    fn identifiers<R: Record>(&mut self, map: &TermMap, record: &R) -> Result<Vec<Identifier>, ParseError> {
+        Ok(self.terms(map, record)?.iter().filter_map(node_identifier).collect())
+    }
+
+    fn graphs<R: Record>(&mut self, maps: &[TermMap], record: &R) -> Result<Vec<Context>, ParseError> {
+        let mut graphs = vec![];
+        for map in maps {
+            for graph in self.identifiers(map, record)? {
+                graphs.push(match graph {
+                    Identifier::IRI(iri) if iri == RR.iri("defaultGraph") => None,
+                    graph => Some(graph),
+                });
+            }
+        }
+        Ok(graphs)
+    }
+}
+
+/// An RML mapping from JSON sources to quads.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mapping {
+    triples_maps: Vec<TriplesMap>,
+    base: Option<IRI>,
+}
+
+impl Mapping {
+    /// Reads the triples maps of a Turtle mapping document. `base` resolves relative IRIs, in the
+    /// document and in the generated terms.
+    pub fn from_turtle(turtle: &str, base: Option<IRI>) -> Result<Mapping, ParseError> {
+        let mut deserializer = turtle_deserialize::deserialize(turtle, None);
+        if let Some(base) = &base {
+            deserializer = deserializer.with_base(base.clone());
+        }
+        let quads = deserializer.collect::<Result<Vec<Quad>, ParseError>>()?;
+        let mut description = Description { statements: HashMap::new() };
+        let mut ids = vec![];
+        for quad in quads {
+            let logical = quad.predicate == RML.iri("logicalSource") || quad.predicate == RR.iri("logicalTable");
+            if logical && !ids.contains(&quad.subject) {
+                ids.push(quad.subject.clone());
+            }
+            description.statements.entry(quad.subject).or_default().push((quad.predicate, quad.object));
+        }
+        let names: HashMap<Identifier, usize> = ids.iter().cloned().enumerate().map(|(index, id)| (id, index)).collect();
+        let mut triples_maps = vec![];
+        for id in &ids {
+            let logical_source = description.logical_source(id)?;
+            triples_maps.push(description.triples_map(id, logical_source, &names)?);
+        }
+        Ok(Mapping { triples_maps, base })
+    }
+
+    /// Runs the mapping over the JSON files its sources name, relative to `directory`.
+    /// Compressed files are decompressed as by `compression::open`.
+    pub fn run<P: AsRef<Path>>(&self, directory: P) -> Result<Dataset, ParseError> {
+        self.run_with(|source| {
+            let io_error = |error: std::io::Error| {
+                ParseError::new(ParseErrorKind::IO(format!("{}: {}", source, error)), 0, 0, 0, String::new())
+            };
+            let mut json = String::new();
+            compression::open(directory.as_ref().join(source))
+                .and_then(|mut reader| reader.read_to_string(&mut json))
+                .map_err(io_error)?;
+            parse_json(&json)
+        })
+    }
+
+    /// Runs the mapping over the JSON documents `load` returns for its sources.
+    pub fn run_with<F>(&self, mut load: F) -> Result<Dataset, ParseError>
+    where
+        F: FnMut(&str) -> Result<Value, ParseError>,
+    {
+        let mut documents: HashMap<&str, Value> = HashMap::new();
+        for triples_map in &self.triples_maps {
+            match &triples_map.logical_source {
+                LogicalSource::Json { source, .. } if !documents.contains_key(source.as_str()) => {
+                    documents.insert(source, load(source)?);
+                }
+                LogicalSource::Json { .. } => {}
+                LogicalSource::Table { .. } => return Err(invalid(format!("Triples map {} reads a logical table", triples_map.name))),
+            }
+        }
+        let mut records: Vec<Vec<&Value>> = vec![];
+        for triples_map in &self.triples_maps {
+            if let LogicalSource::Json { source, iterator } = &triples_map.logical_source {
+                records.push(select(&documents[source.as_str()], &parse_path(iterator)?));
+            }
+        }
+        Ok(Dataset::from(self.generate(&records)?))
+    }
+
+    // The logical source of each triples map, in the order `generate` takes their records
+    pub(crate) fn logical_sources(&self) -> impl Iterator<Item = &LogicalSource> {
+        self.triples_maps.iter().map(|triples_map| &triples_map.logical_source)
+    }
+
+    pub(crate) fn generate<R: Record>(&self, records: &[Vec<R>]) -> Result<Vec<Quad>, ParseError> {
+        generate(&self.triples_maps, records, self.base.clone())
+    }
+}
+
+fn generate<R: Record>(triples_maps: &[TriplesMap], records: &[Vec<R>], base: Option<IRI>) -> Result<Vec<Quad>, ParseError> {
+    let mut generator = Generator {
+        base,
+        blank_nodes: BlankNodeScope::new(Counter),
+    };
+    let mut subjects: Vec<Vec<Vec<Identifier>>> = vec![];
+    for (triples_map, records) in triples_maps.iter().zip(records) {
+        let mut map_subjects = vec![];
+        for record in records {
+            map_subjects.push(generator.identifiers(&triples_map.subject, record)?);
+        }
+        subjects.push(map_subjects);
+    }
+    let mut quads = vec![];
+    for (index, triples_map) in triples_maps.iter().enumerate() {
+        // Parent records by their values for the first join condition
+        let mut join_indices: HashMap<(usize, usize), HashMap<String, Vec<usize>>> = HashMap::new();
+        for (pom_index, pom) in triples_map.predicate_objects.iter().enumerate() {
+            for (object_index, object) in pom.objects.iter().enumerate() {
+                if let ObjectMap::Parent { triples_map: parent, joins } = object {
+                    if let Some(join) = joins.first() {
+                        let mut join_index: HashMap<String, Vec<usize>> = HashMap::new();
+                        for (record_index, record) in records[*parent].iter().enumerate() {
+                            for datum in record.values(&join.parent)? {
+                                join_index.entry(datum.value).or_default().push(record_index);
+                            }
+                        }
+                        join_indices.insert((pom_index, object_index), join_index);
+                    }
+                }
+            }
+        }
+        for (record_index, record) in records[index].iter().enumerate() {
+            let subject_graphs = generator.graphs(&triples_map.graphs, record)?;
+            for subject in &subjects[index][record_index] {
+                let graphs: Vec<Context> = if subject_graphs.is_empty() { vec![None] } else { subject_graphs.clone() };
+                for class in &triples_map.classes {
+                    for graph in &graphs {
+                        quads.push(Quad::new(subject.clone(), RDF.iri("type"), class, graph.clone()));
+                    }
+                }
+            }
+            for (pom_index, pom) in triples_map.predicate_objects.iter().enumerate() {
+                let mut predicates = vec![];
+                for map in &pom.predicates {
+                    for predicate in generator.terms(map, record)? {
+                        if let Node::IRI(predicate) = predicate {
+                            predicates.push(predicate);
+                        }
+                    }
+                }
+                let mut objects = vec![];
+                for (object_index, object) in pom.objects.iter().enumerate() {
+                    match object {
+                        ObjectMap::Term(map) => objects.extend(generator.terms(map, record)?),
+                        ObjectMap::Parent { triples_map: parent, joins } => {
+                            let parents: Vec<usize> = match joins.first() {
+                                None if triples_map.logical_source == triples_maps[*parent].logical_source => vec![record_index],
+                                None => (0..records[*parent].len()).collect(),
+                                Some(join) => {
+                                    let join_index = &join_indices[&(pom_index, object_index)];
+                                    let mut parents = vec![];
+                                    for datum in record.values(&join.child)? {
+                                        parents.extend(join_index.get(&datum.value).into_iter().flatten());
+                                    }
+                                    parents.sort_unstable();
+                                    parents.dedup();
+                                    parents
+                                }
+                            };
+                            for parent_index in parents {
+                                let mut joined = true;
+                                for join in joins.iter().skip(1) {
+                                    let child: Vec<String> = record.values(&join.child)?.into_iter().map(|datum| datum.value).collect();
+                                    let parent_values = records[*parent][parent_index].values(&join.parent)?;
+                                    joined &= parent_values.iter().any(|datum| child.contains(&datum.value));
+                                }
+                                if joined {
+                                    objects.extend(subjects[*parent][parent_index].iter().cloned().map(identifier_node));
+                                }
+                            }
+                        }
+                    }
+                }
+                let mut graphs = subject_graphs.clone();
+                graphs.extend(generator.graphs(&pom.graphs, record)?);
+                if graphs.is_empty() {
+                    graphs.push(None);
+                }
+                for subject in &subjects[index][record_index] {
+                    for predicate in &predicates {
+                        for object in &objects {
+                            for graph in &graphs {
+                                quads.push(Quad::new(subject.clone(), predicate, object.clone(), graph.clone()));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+    Ok(quads)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::compression;
+    use crate::dataset::Dataset;
+    use crate::nquads_deserialize;
+    use crate::parse_error::ParseErrorKind;
+    use crate::quad::Quad;
+    use crate::rml::{parse_path, select, Mapping, Step};
+    use crate::term::IRI;
+    use crate::test_set;
+    use serde_json::json;
+    use std::io::Write;
+
+    const MAPPING: &str = r#"
+        @prefix rr: <http://www.w3.org/ns/r2rml#> .
+        @prefix rml: <http://semweb.mmlab.be/ns/rml#> .
+        @prefix ql: <http://semweb.mmlab.be/ns/ql#> .
+        @prefix foaf: <http://xmlns.com/foaf/0.1/> .
+        @prefix ex: <http://example.com#> .
+
+        <#People> rml:logicalSource [ rml:source "people.json" ; rml:referenceFormulation ql:JSONPath ; rml:iterator "$.people[*]" ] ;
+            rr:subjectMap [ rr:template "http://example.com/people/{id}" ; rr:class foaf:Person ; rr:graph ex:people ] ;
+            rr:predicateObjectMap [ rr:predicate foaf:name ; rr:objectMap [ rml:reference "name" ; rr:language "en" ] ] ,
+                [ rr:predicate foaf:age ; rr:objectMap [ rml:reference "age" ] ] ,
+                [ rr:predicate foaf:nick ; rr:objectMap [ rml:reference "nicks[*]" ] ] ,
+                [ rr:predicate foaf:knows ; rr:objectMap [ rr:parentTriplesMap <#People> ; rr:joinCondition [ rr:child "knows" ; rr:parent "id" ] ] ] .
+
+        <#Pets> rml:logicalSource [ rml:source "people.json" ; rml:iterator "$..pets[*]" ] ;
+            rr:subjectMap [ rr:template "pet-{name}" ; rr:termType rr:BlankNode ] ;
+            rr:predicateObjectMap [ rr:predicate foaf:name ; rr:objectMap [ rml:reference "name" ; rr:datatype ex:Name ] ] ,
+                [ rr:predicate ex:owner ; rr:objectMap [ rr:template "people/{owner}" ] ] .
+    "#;
+
+    const EXPECTED: &str = r#"
+<http://example.com/people/1> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://xmlns.com/foaf/0.1/Person> <http://example.com#people> .
+<http://example.com/people/1> <http://xmlns.com/foaf/0.1/name> "Iddan"@en <http://example.com#people> .
+<http://example.com/people/1> <http://xmlns.com/foaf/0.1/age> "30"^^<http://www.w3.org/2001/XMLSchema#integer> <http://example.com#people> .
+<http://example.com/people/1> <http://xmlns.com/foaf/0.1/nick> "id" <http://example.com#people> .
+<http://example.com/people/1> <http://xmlns.com/foaf/0.1/nick> "iddo" <http://example.com#people> .
+<http://example.com/people/1> <http://xmlns.com/foaf/0.1/knows> <http://example.com/people/2%20b> <http://example.com#people> .
+<http://example.com/people/2%20b> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://xmlns.com/foaf/0.1/Person> <http://example.com#people> .
+<http://example.com/people/2%20b> <http://xmlns.com/foaf/0.1/name> "Tamir"@en <http://example.com#people> .
+<http://example.com/people/2%20b> <http://xmlns.com/foaf/0.1/knows> <http://example.com/people/1> <http://example.com#people> .
+_:rex <http://xmlns.com/foaf/0.1/name> "Rex"^^<http://example.com#Name> .
+_:rex <http://example.com#owner> <http://example.com/people/1> .
+"#;
+
+    fn people() -> serde_json::Value {
+        json!({"people": [
+            {"id": 1, "name": "Iddan", "age": 30, "nicks": ["id", "iddo"], "knows": "2 b", "pets": [{"name": "Rex", "owner": 1}]},
+            {"id": "2 b", "name": "Tamir", "age": null, "knows": [1, 3]}
+        ]})
+    }
+
+    #[test]
+    fn test_run() {
+        let mapping = Mapping::from_turtle(MAPPING, Some(IRI::new("http://example.com/"))).unwrap();
+        let dataset = mapping
+            .run_with(|source| {
+                assert_eq!(source, "people.json");
+                Ok(people())
+            })
+            .unwrap();
+        let expected: Vec<Quad> = nquads_deserialize::deserialize(EXPECTED).map(|quad| quad.unwrap()).collect();
+        let quads: Vec<Quad> = dataset.into_iter().collect();
+        assert!(test_set::isomorphic(&quads, &expected));
+
+        let directory = std::env::temp_dir().join(format!("minerva-rml-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let mut writer = compression::create(directory.join("people.json.gz")).unwrap();
+        writer.write_all(people().to_string().as_bytes()).unwrap();
+        drop(writer);
+        let mapping = Mapping::from_turtle(&MAPPING.replace("people.json", "people.json.gz"), Some(IRI::new("http://example.com/"))).unwrap();
+        let dataset: Dataset = mapping.run(&directory).unwrap();
+        assert_eq!(dataset.len(), expected.len());
+        let error = Mapping::from_turtle(MAPPING, Some(IRI::new("http://example.com/"))).unwrap().run(&directory).unwrap_err();
+        assert!(matches!(error.kind, ParseErrorKind::IO(_)));
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_json_path() {
+        assert_eq!(parse_path("$.a['b c'][0]").unwrap(), vec![Step::Child("a".to_owned()), Step::Child("b c".to_owned()), Step::Index(0)]);
+        assert_eq!(parse_path("a.b[*]").unwrap(), vec![Step::Child("a".to_owned()), Step::Child("b".to_owned()), Step::Wildcard]);
+        assert!(parse_path("$.a[?(@.b)]").is_err());
+        let document = json!({"a": [{"b": 1}, {"c": {"b": 2}}], "b": 3});
+        let found: Vec<String> = select(&document, &parse_path("$..b").unwrap()).iter().map(|value| value.to_string()).collect();
+        assert_eq!(found.len(), 3);
+        assert_eq!(select(&document, &parse_path("$.a[-1].c.b").unwrap()), vec![&json!(2)]);
+
+        let kind = |mapping: &str| Mapping::from_turtle(mapping, None).unwrap_err().kind;
+        let prefixes = "@prefix rr: <http://www.w3.org/ns/r2rml#> . @prefix rml: <http://semweb.mmlab.be/ns/rml#> .";
+        assert_eq!(
+            kind(&format!("{} <http://a> rml:logicalSource [ rml:source \"a.json\" ] .", prefixes)),
+            ParseErrorKind::InvalidMapping("Triples map <http://a> needs exactly one subject map".to_owned())
+        );
+        assert_eq!(
+            kind(&format!("{} <http://a> rml:logicalSource [ rml:source \"a.json\" ] ; rr:subjectMap [ rr:template \"{{id\" ] .", prefixes)),
+            ParseErrorKind::InvalidTemplate("{id".to_owned())
+        );
+    }
+}
@@ -1,74 +1,475 @@
 use futures::future;
 use std::sync::{Arc,Mutex};
-use futures::stream::Stream;
+use futures::stream::{self, Stream};
+use std::collections::HashMap;
+use std::io;
 use http::method::Method;
 use hyper::{Body, Request, Response, Server};
 use hyper::rt::Future;
 use hyper::service::{make_service_fn, service_fn};
 use log::{info};
+use serde::Deserialize;
 use serde_qs;
 use crate::dataset::Dataset;
-use crate::nquads_serialize;
-use crate::nquads_deserialize;
+use crate::namespace::PrefixMap;
+use crate::parse_error::{ParseError, ParseReport};
+use crate::nquads_stream;
+use crate::compression::{self, Compression, Decompressor};
+use crate::jsonld_deserialize::ContextRegistry;
+use crate::format_registry::{Chunks, FormatRegistry, ParseOptions, SerializeOptions, SerializeError};
+use crate::rdf_patch;
+use crate::csvw;
+use crate::jsonld_deserialize::parse_json;
 use crate::read_service;
 use crate::write_service;
+use crate::term::{Identifier, IRI};
+use crate::quad::{Subject, Object, Context};
 
-impl From<Request<Body>> for read_service::Params {
-    fn from(request: Request<Body>) -> read_service::Params {
-        match request.uri().query() {
-            Some(query) => {
-                serde_qs::from_str(&query).unwrap()
-            },
-            None => {
-                read_service::Params {
-                    subject: None,
-                    predicate: None,
-                    object: None,
-                    context: None
-                }
+// Predicates may be given as a CURIE (?predicate=foaf:knows) or as an IRI
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PredicateParam {
+    Compact(String),
+    Full(IRI),
+}
+
+#[derive(Deserialize)]
+struct QueryParams {
+    subject: Option<Subject>,
+    predicate: Option<PredicateParam>,
+    object: Option<Object>,
+    context: Option<Context>,
+}
+
+fn read_params(request: &Request<Body>, prefixes: &Mutex<PrefixMap>) -> Result<read_service::Params, String> {
+    match request.uri().query() {
+        Some(query) => {
+            let params: QueryParams = serde_qs::from_str(query).map_err(|error| format!("Invalid parameters: {}", error))?;
+            let prefixes = prefixes.lock().unwrap();
+            Ok(read_service::Params {
+                subject: params.subject,
+                predicate: params.predicate.map(|predicate| match predicate {
+                    PredicateParam::Compact(curie) => prefixes.resolve(&curie),
+                    PredicateParam::Full(iri) => iri,
+                }),
+                object: params.object,
+                context: params.context,
+            })
+        },
+        None => {
+            Ok(read_service::Params {
+                subject: None,
+                predicate: None,
+                object: None,
+                context: None
+            })
+        }
+    }
+}
+
+#[derive(Deserialize, Default)]
+struct JsonLdParams {
+    jsonld_context: Option<String>,
+    jsonld_frame: Option<String>,
+}
+
+
+fn jsonld_params(request: &Request<Body>) -> JsonLdParams {
+    request.uri().query()
+        .and_then(|query| serde_qs::from_str(query).ok())
+        .unwrap_or_default()
+}
+
+
+// Header values may be repeated, and are then read as one comma separated list
+fn header_list(request: &Request<Body>, name: &str) -> Option<String> {
+    let values: Vec<&str> = request.headers().get_all(name).iter()
+        .filter_map(|value| value.to_str().ok())
+        .collect();
+    if values.is_empty() {
+        None
+    } else {
+        Some(values.join(","))
+    }
+}
+
+
+fn error_response(status: u16, message: String) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .body(Body::from(message))
+        .unwrap()
+}
+
+
+// The document is serialized and compressed chunk by chunk as the response body is sent
+fn encoded_body(chunks: Chunks, encoding: Compression) -> io::Result<Body> {
+    let chunks = stream::iter_ok::<_, io::Error>(chunks);
+    if encoding == Compression::None {
+        return Ok(Body::wrap_stream(chunks));
+    }
+    Ok(Body::wrap_stream(compression::compress_stream(chunks, encoding)?))
+}
+
+
+// The format is negotiated from the Accept header among the registered serializers, and the
+// compression from the Accept-Encoding header
+fn quads_service_get(request: Request<Body>, dataset_lock: Arc<Mutex<Dataset>>, prefixes: Arc<Mutex<PrefixMap>>, contexts: Arc<ContextRegistry>, formats: Arc<FormatRegistry>) -> Box<dyn Future<Item=Response<Body>, Error=hyper::Error> + Send> {
+    let (media_type, profile) = match formats.negotiate(header_list(&request, "Accept").as_deref()) {
+        Some(negotiated) => negotiated,
+        None => {
+            let available: Vec<&str> = formats.serializer_media_types().collect();
+            return Box::new(future::ok(error_response(406, format!("Available media types: {}", available.join(", ")))));
+        }
+    };
+    let encoding = header_list(&request, "Accept-Encoding")
+        .map_or(Compression::None, |accept_encoding| Compression::negotiate(&accept_encoding));
+    let params = match read_params(&request, &prefixes) {
+        Ok(params) => params,
+        Err(message) => return Box::new(future::ok(error_response(400, message))),
+    };
+    let quads = Box::new(read_service::read(params, &dataset_lock));
+    let jsonld_params = jsonld_params(&request);
+    let prefixes = prefixes.lock().unwrap().clone();
+    let options = SerializeOptions {
+        prefixes: &prefixes,
+        contexts: &contexts,
+        profile,
+        jsonld_context: jsonld_params.jsonld_context,
+        jsonld_frame: jsonld_params.jsonld_frame,
+    };
+    let response = match formats.serializer(media_type).unwrap().serialize_stream(quads, &options) {
+        Ok(serialized) => {
+            let body = match encoded_body(serialized.chunks, encoding) {
+                Ok(body) => body,
+                Err(error) => return Box::new(future::ok(error_response(500, error.to_string()))),
+            };
+            let mut response = Response::builder();
+            response.status(200).header("Content-Type", media_type).header("Vary", "Accept-Encoding");
+            if encoding != Compression::None {
+                response.header("Content-Encoding", encoding.encoding());
+            }
+            if let Some(warning) = serialized.warning {
+                response.header("Warning", format!("299 minerva \"{}\"", warning));
             }
+            response.body(body).unwrap()
+        },
+        Err(SerializeError::InvalidOption(message)) => error_response(400, message),
+        Err(SerializeError::Unrepresentable(message)) => error_response(406, message),
+    };
+    Box::new(future::ok(response))
+}
+
+
+fn parse_error_response(error: ParseError) -> Response<Body> {
+    Response::builder()
+        .status(400)
+        .header("Content-Type", "application/json")
+        .body(Body::from(serde_json::to_string(&error).unwrap()))
+        .unwrap()
+}
+
+
+enum PostError {
+    Transport(hyper::Error),
+    Decode(io::Error),
+    Parse(ParseError),
+}
+
+
+impl From<io::Error> for PostError {
+    fn from(error: io::Error) -> PostError {
+        PostError::Decode(error)
+    }
+}
+
+
+#[derive(Deserialize, Default)]
+struct WriteParams {
+    lenient: Option<bool>,
+    graph: Option<String>,
+    base: Option<String>,
+}
+
+
+fn write_params(request: &Request<Body>) -> Result<WriteParams, String> {
+    match request.uri().query() {
+        Some(query) => serde_qs::from_str(query).map_err(|error| format!("Invalid parameters: {}", error)),
+        None => Ok(WriteParams::default()),
+    }
+}
+
+
+fn media_type(request: &Request<Body>) -> Option<String> {
+    request.headers().get("Content-Type")
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.split(';').next().unwrap().trim().to_lowercase())
+}
+
+
+// Codings are listed in the order they were applied. Only one compression is supported.
+fn content_encoding(request: &Request<Body>) -> Result<Compression, String> {
+    let mut encoding = Compression::None;
+    for coding in header_list(request, "Content-Encoding").unwrap_or_default().split(',') {
+        if coding.trim().is_empty() {
+            continue;
+        }
+        match Compression::from_encoding(coding) {
+            None => return Err(format!("Unsupported content coding {}, supported: gzip, zstd, bzip2", coding.trim())),
+            Some(Compression::None) => {},
+            Some(_) if encoding != Compression::None => return Err("Only one content coding is supported".to_owned()),
+            Some(compression) => encoding = compression,
         }
     }
+    Ok(encoding)
 }
 
-fn quads_service_get<'a>(request: Request<Body>, dataset_lock: Arc<Mutex<Dataset>>) -> Box<Future<Item=Response<Body>, Error=hyper::Error> + Send> {
-    let params: read_service::Params = request.into();
-    let quads = read_service::read(params, &dataset_lock);
-    // TODO make read service return stream
-    let quads_stream = futures::stream::iter_ok::<_, hyper::Error>(quads);
-    let stream = nquads_serialize::serialize(quads_stream);
-    Box::new(future::ok(Response::builder()
-        .status(200)
-        .header("Content-Type", "x-nquads")
-        .body(Body::wrap_stream(stream))
-        .unwrap()))
+
+fn decode_error_response(error: io::Error) -> Response<Body> {
+    match error.kind() {
+        io::ErrorKind::FileTooLarge => error_response(413, error.to_string()),
+        _ => error_response(400, format!("Invalid compressed body: {}", error)),
+    }
+}
+
+
+fn write_response(report: ParseReport, lenient: bool) -> Response<Body> {
+    if lenient {
+        Response::builder()
+            .status(201)
+            .header("Content-Type", "application/json")
+            .body(Body::from(serde_json::to_string(&report).unwrap()))
+            .unwrap()
+    } else {
+        Response::builder()
+            .status(201)
+            .body(Body::empty())
+            .unwrap()
+    }
+}
+
+
+// The body is parsed as it arrives, a chunk at a time, and its statements are inserted together
+// once it is complete, so nothing is inserted from a body that fails. In lenient mode invalid statements are skipped and reported instead of failing the request.
+// Compressed bodies are decompressed as they arrive too, a chunk to at most `compression::MAX_PUSH_OUTPUT` bytes.
+fn quads_service_post(request: Request<Body>, dataset_lock: Arc<Mutex<Dataset>>, encoding: Compression) -> Box<dyn Future<Item=Response<Body>, Error=hyper::Error> + Send> {
+    let lenient = match write_params(&request) {
+        Ok(params) => params.lenient.unwrap_or(false),
+        Err(message) => return Box::new(future::ok(error_response(400, message))),
+    };
+    let body = match compression::decompress_stream(request.into_body().map_err(PostError::Transport), encoding) {
+        Ok(body) => body,
+        Err(error) => return Box::new(future::ok(decode_error_response(error))),
+    };
+    Box::new(nquads_stream::deserialize_stream(body)
+        .fold((ParseReport::new(), Dataset::new()), move |(mut report, mut quads), result| {
+            match result {
+                Ok(quad) => {
+                    quads.insert(quad);
+                    report.accepted += 1;
+                    Ok((report, quads))
+                },
+                Err(error) if lenient => {
+                    report.reject(error);
+                    Ok((report, quads))
+                },
+                Err(error) => Err(PostError::Parse(error)),
+            }
+        })
+        .then(move |result| match result {
+            Ok((report, quads)) => {
+                write_service::insert_all(quads, &dataset_lock);
+                Ok(write_response(report, lenient))
+            },
+            Err(PostError::Parse(error)) => Ok(parse_error_response(error)),
+            Err(PostError::Decode(error)) => Ok(decode_error_response(error)),
+            Err(PostError::Transport(error)) => Err(error),
+        }))
+}
+
+
+// Bodies parsed whole are limited to this size once decompressed, and are decompressed in chunks
+// of `DECODE_CHUNK_SIZE` bytes
+const MAX_DOCUMENT_SIZE: usize = 256 << 20;
+const DECODE_CHUNK_SIZE: usize = 64 << 10;
+
+
+fn decode_body(body: &[u8], encoding: Compression) -> io::Result<Vec<u8>> {
+    let mut decompressor = Decompressor::new(encoding)?.with_limit(MAX_DOCUMENT_SIZE);
+    let mut decoded = vec![];
+    for chunk in body.chunks(DECODE_CHUNK_SIZE) {
+        decoded.extend(decompressor.push(chunk)?);
+    }
+    decoded.extend(decompressor.finish()?);
+    Ok(decoded)
 }
 
 
-fn quads_service_post(request: Request<Body>, dataset_lock: Arc<Mutex<Dataset>>) -> Box<Future<Item=Response<Body>, Error=hyper::Error> + Send> {
-    Box::new(request.into_body().concat2().and_then(move |body| {
-        // TODO error handle
-        let nquads = String::from_utf8(body.to_vec()).unwrap();
-        let result = write_service::write(nquads, &dataset_lock);
-        match result {
-            Ok(_) => Ok(
-                Response::builder()
-                    .status(201)
-                    .body(Body::empty())
-                    .unwrap()
-            ),
-            Err(_) => Ok(
-                Response::builder()
-                    .status(401)
-                    .body(Body::empty())
-                    .unwrap()
-            )
+// Other formats are parsed once the body is complete, by the parser registered for the media type.
+// Nothing is inserted from an invalid document unless in lenient mode. The prefixes the document
+// declares are added to the shared ones unless already declared, relative IRIs are resolved against
+// the `base` parameter, and triples go to the graph of the `graph` parameter.
+fn document_service_post(request: Request<Body>, dataset_lock: Arc<Mutex<Dataset>>, prefixes: Arc<Mutex<PrefixMap>>, contexts: Arc<ContextRegistry>, formats: Arc<FormatRegistry>, media_type: String, encoding: Compression) -> Box<dyn Future<Item=Response<Body>, Error=hyper::Error> + Send> {
+    let params = match write_params(&request) {
+        Ok(params) => params,
+        Err(message) => return Box::new(future::ok(error_response(400, message))),
+    };
+    let lenient = params.lenient.unwrap_or(false);
+    let graph = params.graph.map(|graph| Identifier::IRI(IRI::new(graph)));
+    let base = params.base.map(IRI::new);
+    Box::new(request.into_body().concat2().map(move |body| {
+        let body = match decode_body(&body, encoding) {
+            Ok(body) => body,
+            Err(error) => return decode_error_response(error),
+        };
+        let parser = formats.parser(&media_type).unwrap();
+        let mut options = ParseOptions {
+            contexts: &contexts,
+            graph,
+            base,
+            prefixes: PrefixMap::new(),
+        };
+        let mut report = ParseReport::new();
+        let parsed = if lenient {
+            parser.parse(&body, &mut options, Some(&mut report))
+        } else {
+            parser.parse(&body, &mut options, None)
+        };
+        let quads = match parsed {
+            Ok(quads) => quads,
+            Err(error) => return parse_error_response(error),
+        };
+        report.accepted = quads.len();
+        let mut prefixes = prefixes.lock().unwrap();
+        for (prefix, address) in options.prefixes.iter() {
+            if prefixes.get(prefix).is_none() {
+                prefixes.insert(prefix, address);
+            }
         }
+        for quad in quads {
+            write_service::insert(quad, &dataset_lock);
+        }
+        write_response(report, lenient)
+    }))
+}
+
+
+// The patch is parsed whole before any of it is applied, and applied under the store locks,
+// so a patch that fails to parse changes nothing and readers never see part of one
+fn patch_service_post(request: Request<Body>, dataset_lock: Arc<Mutex<Dataset>>, prefixes: Arc<Mutex<PrefixMap>>, encoding: Compression) -> Box<dyn Future<Item=Response<Body>, Error=hyper::Error> + Send> {
+    Box::new(request.into_body().concat2().map(move |body| {
+        let body = match decode_body(&body, encoding) {
+            Ok(body) => body,
+            Err(error) => return decode_error_response(error),
+        };
+        let patch = match ParseError::decode_utf8(body).and_then(|patch| rdf_patch::deserialize(&patch)) {
+            Ok(patch) => patch,
+            Err(error) => return parse_error_response(error),
+        };
+        write_service::apply(&patch, &dataset_lock, &prefixes);
+        Response::builder()
+            .status(204)
+            .body(Body::empty())
+            .unwrap()
     }))
 }
 
 
-fn quad_service_unknown_method() -> Box<Future<Item=Response<Body>, Error=hyper::Error> + Send> {
+fn boundary(request: &Request<Body>) -> Option<String> {
+    let content_type = request.headers().get("Content-Type")?.to_str().ok()?;
+    content_type.split(';')
+        .filter_map(|parameter| parameter.trim().split_once('='))
+        .find(|(name, _)| name.eq_ignore_ascii_case("boundary"))
+        .map(|(_, boundary)| boundary.trim_matches('"').to_owned())
+}
+
+
+fn find(haystack: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    haystack[from..].windows(needle.len()).position(|window| window == needle).map(|index| from + index)
+}
+
+
+// The parts of a multipart/form-data body (RFC 7578) by field name
+fn form_data(body: &[u8], boundary: &str) -> HashMap<String, Vec<u8>> {
+    let delimiter = format!("--{}", boundary).into_bytes();
+    let mut parts = HashMap::new();
+    let mut start = match find(body, &delimiter, 0) {
+        Some(index) => index + delimiter.len(),
+        None => return parts,
+    };
+    while let Some(end) = find(body, &delimiter, start) {
+        let part = &body[start..end];
+        let part = part.strip_prefix(b"\r\n").unwrap_or(part);
+        let part = part.strip_suffix(b"\r\n").unwrap_or(part);
+        if let Some(separator) = find(part, b"\r\n\r\n", 0) {
+            let headers = String::from_utf8_lossy(&part[..separator]);
+            let name = headers.split("\r\n")
+                .filter(|header| header.to_lowercase().starts_with("content-disposition:"))
+                .flat_map(|header| header.split(';').skip(1))
+                .filter_map(|parameter| parameter.trim().split_once('='))
+                .find(|(name, _)| *name == "name")
+                .map(|(_, name)| name.trim_matches('"').to_owned());
+            if let Some(name) = name {
+                parts.insert(name, part[separator + 4..].to_vec());
+            }
+        }
+        start = end + delimiter.len();
+    }
+    parts
+}
+
+
+// The table and its metadata are the `csv` and `metadata` fields of a form. Rows go to the graph of
+// the `graph` parameter, by default the graph named by the table's URL, and nothing is inserted
+// from a table with an invalid row.
+fn csvw_service_post(request: Request<Body>, dataset_lock: Arc<Mutex<Dataset>>, encoding: Compression) -> Box<dyn Future<Item=Response<Body>, Error=hyper::Error> + Send> {
+    let WriteParams { graph, base, .. } = match write_params(&request) {
+        Ok(params) => params,
+        Err(message) => return Box::new(future::ok(error_response(400, message))),
+    };
+    let boundary = match boundary(&request) {
+        Some(boundary) => boundary,
+        None => return Box::new(future::ok(error_response(400, "Missing multipart boundary".to_owned()))),
+    };
+    Box::new(request.into_body().concat2().map(move |body| {
+        let body = match decode_body(&body, encoding) {
+            Ok(body) => body,
+            Err(error) => return decode_error_response(error),
+        };
+        let mut parts = form_data(&body, &boundary);
+        let (csv, metadata) = match (parts.remove("csv"), parts.remove("metadata")) {
+            (Some(csv), Some(metadata)) => (csv, metadata),
+            _ => return error_response(400, "Expected the csv and metadata fields".to_owned()),
+        };
+        let base = base.map(IRI::new);
+        let quads = ParseError::decode_utf8(metadata)
+            .and_then(|metadata| parse_json(&metadata))
+            .and_then(|metadata| csvw::Table::from_metadata(&metadata, base))
+            .and_then(|table| {
+                let csv = ParseError::decode_utf8(csv)?;
+                let graph = graph.map_or_else(|| table.url().clone(), IRI::new);
+                table.deserialize(&csv, Some(Identifier::IRI(graph)))
+            });
+        let quads = match quads {
+            Ok(quads) => quads,
+            Err(error) => return parse_error_response(error),
+        };
+        let report = ParseReport { accepted: quads.len(), ..ParseReport::new() };
+        for quad in quads {
+            write_service::insert(quad, &dataset_lock);
+        }
+        write_response(report, false)
+    }))
+}
+
+
+fn quad_service_unsupported_media_type(formats: &FormatRegistry) -> Box<dyn Future<Item=Response<Body>, Error=hyper::Error> + Send> {
+    let available: Vec<&str> = formats.parser_media_types().collect();
+    Box::new(future::ok(error_response(415, format!("Supported media types: {}", available.join(", ")))))
+}
+
+
+fn quad_service_unknown_method() -> Box<dyn Future<Item=Response<Body>, Error=hyper::Error> + Send> {
     Box::new(future::ok(Response::builder()
         .status(405)
         .body(Body::empty())
@@ -76,7 +477,7 @@ fn quad_service_unknown_method() -> Box<Future<Item=Response<Body>, Error=hyper:
 }
 
 
-fn quad_service_unknown_path() -> Box<Future<Item=Response<Body>, Error=hyper::Error> + Send> {
+fn quad_service_unknown_path() -> Box<dyn Future<Item=Response<Body>, Error=hyper::Error> + Send> {
     Box::new(future::ok(Response::builder()
                 .status(404)
                 .body(Body::empty())
@@ -85,20 +486,64 @@ fn quad_service_unknown_path() -> Box<Future<Item=Response<Body>, Error=hyper::E
 
 
 pub fn serve(dataset: Dataset, address: &str) -> impl Future<Item=(), Error=hyper::Error> {
+    serve_with_contexts(dataset, address, ContextRegistry::default())
+}
+
+
+/// Like `serve`, with JSON-LD contexts that posted documents can refer to by IRI.
+pub fn serve_with_contexts(dataset: Dataset, address: &str, contexts: ContextRegistry) -> impl Future<Item=(), Error=hyper::Error> {
+    serve_with_formats(dataset, address, contexts, FormatRegistry::default())
+}
+
+
+/// Like `serve_with_contexts`, reading and writing the formats of `formats`. N-Quads bodies are always
+/// parsed as they arrive, and bodies without a Content-Type are read as N-Quads.
+pub fn serve_with_formats(dataset: Dataset, address: &str, contexts: ContextRegistry, formats: FormatRegistry) -> impl Future<Item=(), Error=hyper::Error> {
     let socket_address = address.parse().unwrap();
     let shared_dataset = Arc::new(Mutex::new(dataset));
+    let shared_prefixes = Arc::new(Mutex::new(PrefixMap::default()));
+    let shared_contexts = Arc::new(contexts);
+    let shared_formats = Arc::new(formats);
     let make_service = make_service_fn(move |_| {
         let cloned_dataset = Arc::clone(&shared_dataset);
+        let cloned_prefixes = Arc::clone(&shared_prefixes);
+        let cloned_contexts = Arc::clone(&shared_contexts);
+        let cloned_formats = Arc::clone(&shared_formats);
         service_fn(move |request| {
             let cloned_dataset = Arc::clone(&cloned_dataset);
+            let cloned_prefixes = Arc::clone(&cloned_prefixes);
+            let cloned_contexts = Arc::clone(&cloned_contexts);
+            let cloned_formats = Arc::clone(&cloned_formats);
             let method = request.method();
             let uri = request.uri();
             info!("{} {}", method, uri.to_string());
             let path = uri.path();
             match (method, path) {
-                (&Method::GET, "/") => quads_service_get(request, cloned_dataset),
-                (&Method::POST, "/") => quads_service_post(request, cloned_dataset),
+                (&Method::GET, "/") => quads_service_get(request, cloned_dataset, cloned_prefixes, cloned_contexts, cloned_formats),
+                (&Method::POST, "/") => match (media_type(&request), content_encoding(&request)) {
+                    (_, Err(message)) => Box::new(future::ok(error_response(415, message))),
+                    (None, Ok(encoding)) => quads_service_post(request, cloned_dataset, encoding),
+                    (Some(ref media_type), Ok(encoding)) if media_type == "application/n-quads" => quads_service_post(request, cloned_dataset, encoding),
+                    (Some(media_type), Ok(encoding)) => match cloned_formats.parser(&media_type) {
+                        Some(_) => document_service_post(request, cloned_dataset, cloned_prefixes, cloned_contexts, cloned_formats, media_type, encoding),
+                        None => quad_service_unsupported_media_type(&cloned_formats),
+                    },
+                },
                 (_, "/") => quad_service_unknown_method(),
+                (&Method::POST, "/patch") => match (media_type(&request), content_encoding(&request)) {
+                    (_, Err(message)) => Box::new(future::ok(error_response(415, message))),
+                    (Some(ref media_type), _) if media_type != "application/rdf-patch" => {
+                        Box::new(future::ok(error_response(415, "Supported media types: application/rdf-patch".to_owned())))
+                    },
+                    (_, Ok(encoding)) => patch_service_post(request, cloned_dataset, cloned_prefixes, encoding),
+                },
+                (_, "/patch") => quad_service_unknown_method(),
+                (&Method::POST, "/csvw") => match (media_type(&request), content_encoding(&request)) {
+                    (_, Err(message)) => Box::new(future::ok(error_response(415, message))),
+                    (Some(ref media_type), Ok(encoding)) if media_type == "multipart/form-data" => csvw_service_post(request, cloned_dataset, encoding),
+                    _ => Box::new(future::ok(error_response(415, "Supported media types: multipart/form-data".to_owned()))),
+                },
+                (_, "/csvw") => quad_service_unknown_method(),
                 _ => quad_service_unknown_path()
             }
         })
@@ -106,4 +551,33 @@ pub fn serve(dataset: Dataset, address: &str) -> impl Future<Item=(), Error=hype
 
     Server::bind(&socket_address)
         .serve(make_service)
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+    use hyper::{Body, Request};
+    use crate::namespace::{PrefixMap, FOAF};
+    use crate::server_http::{read_params, write_params};
+    use crate::term::IRI;
+
+    #[test]
+    fn test_read_params_predicate() {
+        let prefixes = Mutex::new(PrefixMap::default());
+        let request = Request::get("/?predicate=foaf:knows").body(Body::empty()).unwrap();
+        assert_eq!(read_params(&request, &prefixes).unwrap().predicate, Some(FOAF.iri("knows")));
+        let request = Request::get("/?predicate[value]=http://example.com%23likes").body(Body::empty()).unwrap();
+        assert_eq!(read_params(&request, &prefixes).unwrap().predicate, Some(IRI::new("http://example.com#likes")));
+        let request = Request::get("/?subject=foo").body(Body::empty()).unwrap();
+        assert!(read_params(&request, &prefixes).is_err());
+    }
+
+    #[test]
+    fn test_write_params() {
+        let request = Request::post("/?lenient=true&graph=http://example.com/g").body(Body::empty()).unwrap();
+        let params = write_params(&request).ok().unwrap();
+        assert_eq!(params.lenient, Some(true));
+        assert_eq!(params.graph.as_deref(), Some("http://example.com/g"));
+        let request = Request::post("/?lenient=maybe").body(Body::empty()).unwrap();
+        assert!(write_params(&request).is_err());
+    }
+}
@@ -1,9 +1,9 @@
-use uuid::Uuid;
 use serde::{Deserialize, Serialize};
+use crate::blank_node_id;
 use crate::namespace::XSD;
 
 
-#[derive(Debug, Hash, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[derive(Debug, Hash, PartialEq, Eq, PartialOrd, Ord, Clone, Serialize, Deserialize)]
 pub struct IRI {
     pub value: String,
 }
@@ -19,25 +19,184 @@ impl IRI {
     }
 }
 
+struct IRIReference<'a> {
+    scheme: Option<&'a str>,
+    authority: Option<&'a str>,
+    path: &'a str,
+    query: Option<&'a str>,
+    fragment: Option<&'a str>,
+}
+
+impl<'a> IRIReference<'a> {
+    fn parse(value: &'a str) -> IRIReference<'a> {
+        let (rest, fragment) = match value.find('#') {
+            Some(index) => (&value[..index], Some(&value[index + 1..])),
+            None => (value, None),
+        };
+        let (rest, query) = match rest.find('?') {
+            Some(index) => (&rest[..index], Some(&rest[index + 1..])),
+            None => (rest, None),
+        };
+        let (scheme, rest) = match rest.find([':', '/']) {
+            Some(index)
+                if index > 0
+                    && rest[index..].starts_with(':')
+                    && rest.starts_with(|c: char| c.is_ascii_alphabetic())
+                    && rest[..index]
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '-' || c == '.') =>
+            {
+                (Some(&rest[..index]), &rest[index + 1..])
+            }
+            _ => (None, rest),
+        };
+        let (authority, path) = if let Some(rest) = rest.strip_prefix("//") {
+            match rest.find('/') {
+                Some(index) => (Some(&rest[..index]), &rest[index..]),
+                None => (Some(rest), ""),
+            }
+        } else {
+            (None, rest)
+        };
+        IRIReference {
+            scheme,
+            authority,
+            path,
+            query,
+            fragment,
+        }
+    }
+}
+
+fn remove_dot_segments(path: &str) -> String {
+    let mut input = path;
+    let mut output = String::new();
+    while !input.is_empty() {
+        if let Some(rest) = input.strip_prefix("../") {
+            input = rest;
+        } else if let Some(rest) = input.strip_prefix("./") {
+            input = rest;
+        } else if input.starts_with("/./") {
+            input = &input[2..];
+        } else if input == "/." {
+            input = "/";
+        } else if input.starts_with("/../") || input == "/.." {
+            input = if input == "/.." { "/" } else { &input[3..] };
+            match output.rfind('/') {
+                Some(index) => output.truncate(index),
+                None => output.clear(),
+            }
+        } else if input == "." || input == ".." {
+            input = "";
+        } else {
+            let start = if input.starts_with('/') { 1 } else { 0 };
+            let end = input[start..].find('/').map_or(input.len(), |index| index + start);
+            output.push_str(&input[..end]);
+            input = &input[end..];
+        }
+    }
+    output
+}
+
+impl IRI {
+    /// Resolves `reference` against this IRI as a base (RFC 3986 section 5.2).
+    pub fn resolve(&self, reference: &str) -> IRI {
+        let base = IRIReference::parse(&self.value);
+        let reference = IRIReference::parse(reference);
+        let (scheme, authority, path, query);
+        if reference.scheme.is_some() {
+            scheme = reference.scheme;
+            authority = reference.authority;
+            path = remove_dot_segments(reference.path);
+            query = reference.query;
+        } else {
+            scheme = base.scheme;
+            if reference.authority.is_some() {
+                authority = reference.authority;
+                path = remove_dot_segments(reference.path);
+                query = reference.query;
+            } else {
+                authority = base.authority;
+                if reference.path.is_empty() {
+                    path = base.path.to_owned();
+                    query = reference.query.or(base.query);
+                } else {
+                    if reference.path.starts_with('/') {
+                        path = remove_dot_segments(reference.path);
+                    } else if base.authority.is_some() && base.path.is_empty() {
+                        path = remove_dot_segments(&format!("/{}", reference.path));
+                    } else {
+                        let directory = base.path.rfind('/').map_or("", |index| &base.path[..=index]);
+                        path = remove_dot_segments(&format!("{}{}", directory, reference.path));
+                    }
+                    query = reference.query;
+                }
+            }
+        }
+        let mut value = String::new();
+        if let Some(scheme) = scheme {
+            value.push_str(scheme);
+            value.push(':');
+        }
+        if let Some(authority) = authority {
+            value.push_str("//");
+            value.push_str(authority);
+        }
+        value.push_str(&path);
+        if let Some(query) = query {
+            value.push('?');
+            value.push_str(query);
+        }
+        if let Some(fragment) = reference.fragment {
+            value.push('#');
+            value.push_str(fragment);
+        }
+        IRI::new(value)
+    }
+}
+
 impl From<&IRI> for IRI {
     fn from(iri: &IRI) -> IRI {
         iri.to_owned()
     }
 }
 
-#[derive(Debug, Hash, PartialEq, Eq, Clone, Serialize, Deserialize)]
-pub struct BlankNode {
-    pub value: String
+/// An IRI that can be built in a `const` or `static`, as emitted by `vocabulary`.
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
+pub struct StaticIRI {
+    pub value: &'static str,
+}
+
+impl StaticIRI {
+    pub const fn new(value: &'static str) -> StaticIRI {
+        StaticIRI { value }
+    }
+
+    pub fn iri(&self) -> IRI {
+        IRI::new(self.value)
+    }
+}
+
+impl From<StaticIRI> for IRI {
+    fn from(iri: StaticIRI) -> IRI {
+        iri.iri()
+    }
 }
 
-// TODO: generate real unique
-fn generate_blank_node_id() -> String {
-    Uuid::new_v4().to_string()
+impl From<&StaticIRI> for IRI {
+    fn from(iri: &StaticIRI) -> IRI {
+        iri.iri()
+    }
+}
+
+#[derive(Debug, Hash, PartialEq, Eq, PartialOrd, Ord, Clone, Serialize, Deserialize)]
+pub struct BlankNode {
+    pub value: String
 }
 
 impl BlankNode {
     pub fn new() -> BlankNode {
-        BlankNode { value: generate_blank_node_id() }
+        BlankNode { value: blank_node_id::next_id() }
     }
 
     pub fn from_value<V>(value: V) -> BlankNode
@@ -46,7 +205,13 @@ impl BlankNode {
     }
 }
 
-#[derive(Debug, Hash, PartialEq, Eq, Clone, Serialize, Deserialize)]
+impl Default for BlankNode {
+    fn default() -> BlankNode {
+        BlankNode::new()
+    }
+}
+
+#[derive(Debug, Hash, PartialEq, Eq, PartialOrd, Ord, Clone, Serialize, Deserialize)]
 pub struct Literal {
     pub value: String,
     pub datatype: IRI,
@@ -68,7 +233,7 @@ impl Literal {
     }
 }
 
-#[derive(Debug, Hash, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[derive(Debug, Hash, PartialEq, Eq, PartialOrd, Ord, Clone, Serialize, Deserialize)]
 pub enum Node {
     IRI(IRI),
     BlankNode(BlankNode),
@@ -93,6 +258,18 @@ impl From<Literal> for Node {
     }
 }
 
+impl From<StaticIRI> for Node {
+    fn from(value: StaticIRI) -> Node {
+        Node::IRI(value.iri())
+    }
+}
+
+impl From<&StaticIRI> for Node {
+    fn from(value: &StaticIRI) -> Node {
+        Node::IRI(value.iri())
+    }
+}
+
 impl From<&IRI> for Node {
     fn from(value: &IRI) -> Node {
         Node::IRI(value.to_owned())
@@ -111,7 +288,7 @@ impl From<&Literal> for Node {
     }
 }
 
-#[derive(Debug, Hash, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[derive(Debug, Hash, PartialEq, Eq, PartialOrd, Ord, Clone, Serialize, Deserialize)]
 pub enum Identifier {
     IRI(IRI),
     BlankNode(BlankNode),
@@ -129,6 +306,18 @@ impl From<BlankNode> for Identifier {
     }
 }
 
+impl From<StaticIRI> for Identifier {
+    fn from(value: StaticIRI) -> Identifier {
+        Identifier::IRI(value.iri())
+    }
+}
+
+impl From<&StaticIRI> for Identifier {
+    fn from(value: &StaticIRI) -> Identifier {
+        Identifier::IRI(value.iri())
+    }
+}
+
 impl From<&IRI> for Identifier {
     fn from(value: &IRI) -> Identifier {
         Identifier::IRI(value.to_owned())
@@ -139,4 +328,45 @@ impl From<&BlankNode> for Identifier {
     fn from(value: &BlankNode) -> Identifier {
         Identifier::BlankNode(value.to_owned())
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use crate::term::IRI;
+
+    // Examples from RFC 3986 section 5.4
+    #[test]
+    fn test_resolve() {
+        let base = IRI::new("http://a/b/c/d;p?q");
+        for (reference, expected) in &[
+            ("g:h", "g:h"),
+            ("g", "http://a/b/c/g"),
+            ("./g", "http://a/b/c/g"),
+            ("g/", "http://a/b/c/g/"),
+            ("/g", "http://a/g"),
+            ("//g", "http://g"),
+            ("?y", "http://a/b/c/d;p?y"),
+            ("g?y", "http://a/b/c/g?y"),
+            ("#s", "http://a/b/c/d;p?q#s"),
+            ("g#s", "http://a/b/c/g#s"),
+            (";x", "http://a/b/c/;x"),
+            ("", "http://a/b/c/d;p?q"),
+            (".", "http://a/b/c/"),
+            ("./", "http://a/b/c/"),
+            ("..", "http://a/b/"),
+            ("../g", "http://a/b/g"),
+            ("../..", "http://a/"),
+            ("../../g", "http://a/g"),
+            ("../../../g", "http://a/g"),
+            ("/./g", "http://a/g"),
+            ("/../g", "http://a/g"),
+            ("g.", "http://a/b/c/g."),
+            ("..g", "http://a/b/c/..g"),
+            ("./../g", "http://a/b/g"),
+            ("g/./h", "http://a/b/c/g/h"),
+            ("g/../h", "http://a/b/c/h"),
+            ("g;x=1/../y", "http://a/b/c/y"),
+        ] {
+            assert_eq!(base.resolve(reference), IRI::new(*expected), "{}", reference);
+        }
+    }
+}
@@ -187,3 +187,165 @@ pub fn get_quads() -> HashSet<Quad> {
 pub fn get_nquads_string() -> String {
     String::from_utf8(fs::read("src/test_set.nq").unwrap()).unwrap()
 }
+
+/// An entry of a W3C test suite manifest.
+#[cfg(test)]
+pub struct ManifestEntry {
+    pub name: String,
+    pub kind: String,
+    pub action_name: String,
+    pub action: std::path::PathBuf,
+    pub result: Option<std::path::PathBuf>,
+}
+
+#[cfg(test)]
+pub struct Manifest {
+    pub entries: Vec<ManifestEntry>,
+}
+
+#[cfg(test)]
+impl Manifest {
+    /// Reads `directory`/manifest.ttl, whose test files are relative to `base`.
+    pub fn read(directory: &str, base: &str) -> Manifest {
+        use crate::namespace::{Namespace, RDF};
+        use crate::turtle_deserialize;
+        use std::collections::HashMap;
+        use std::path::Path;
+
+        let manifest = fs::read_to_string(Path::new(directory).join("manifest.ttl")).unwrap();
+        let quads: Vec<Quad> = turtle_deserialize::deserialize(&manifest, None)
+            .with_base(IRI::new(format!("{}manifest.ttl", base)))
+            .map(|quad| quad.unwrap())
+            .collect();
+        let mf = Namespace::new("http://www.w3.org/2001/sw/DataAccess/tests/test-manifest#");
+        let rdft = Namespace::new("http://www.w3.org/ns/rdftest#");
+        let file = |node: &Node| match node {
+            Node::IRI(iri) if iri.value.starts_with(base) => iri.value[base.len()..].to_owned(),
+            node => panic!("Unexpected test file {:?}", node),
+        };
+        let mut entries: HashMap<&Identifier, ManifestEntry> = HashMap::new();
+        for quad in quads.iter() {
+            let kind = match &quad.object {
+                Node::IRI(iri) if quad.predicate == RDF.iri("type") && iri.value.starts_with(&rdft.iri("").value) => iri,
+                _ => continue,
+            };
+            entries.insert(
+                &quad.subject,
+                ManifestEntry {
+                    name: String::new(),
+                    kind: kind.value[rdft.iri("").value.len()..].to_owned(),
+                    action_name: String::new(),
+                    action: Default::default(),
+                    result: None,
+                },
+            );
+        }
+        for quad in quads.iter() {
+            if let Some(entry) = entries.get_mut(&quad.subject) {
+                if quad.predicate == mf.iri("name") {
+                    if let Node::Literal(literal) = &quad.object {
+                        entry.name = literal.value.clone();
+                    }
+                } else if quad.predicate == mf.iri("action") {
+                    entry.action_name = file(&quad.object);
+                    entry.action = Path::new(directory).join(&entry.action_name);
+                } else if quad.predicate == mf.iri("result") {
+                    entry.result = Some(Path::new(directory).join(file(&quad.object)));
+                }
+            }
+        }
+        let mut entries: Vec<ManifestEntry> = entries.into_values().collect();
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+        Manifest { entries }
+    }
+}
+
+/// Whether `quads` are the test set, up to renaming of blank nodes.
+#[cfg(test)]
+pub fn is_test_set<'a, I>(quads: I) -> bool
+where
+    I: IntoIterator<Item = &'a Quad>,
+{
+    let quads: Vec<Quad> = quads.into_iter().cloned().collect();
+    let expected: Vec<Quad> = get_quads().into_iter().collect();
+    isomorphic(&quads, &expected)
+}
+
+/// Whether `a` and `b` are the same quads up to renaming of blank nodes.
+#[cfg(test)]
+pub fn isomorphic(a: &[Quad], b: &[Quad]) -> bool {
+    use std::collections::HashMap;
+
+    fn blank_nodes(quads: &[Quad]) -> Vec<String> {
+        let mut values = vec![];
+        for quad in quads {
+            let identifiers = [Some(&quad.subject), quad.context.as_ref()];
+            for identifier in identifiers.iter().flatten() {
+                if let Identifier::BlankNode(blank_node) = identifier {
+                    values.push(blank_node.value.clone());
+                }
+            }
+            if let Node::BlankNode(blank_node) = &quad.object {
+                values.push(blank_node.value.clone());
+            }
+        }
+        values.sort();
+        values.dedup();
+        values
+    }
+
+    fn rename(quad: &Quad, mapping: &HashMap<String, String>) -> Option<Quad> {
+        let identifier = |identifier: &Identifier| match identifier {
+            Identifier::BlankNode(blank_node) => mapping
+                .get(&blank_node.value)
+                .map(|value| Identifier::BlankNode(BlankNode::from_value(value.clone()))),
+            identifier => Some(identifier.clone()),
+        };
+        let object = match &quad.object {
+            Node::BlankNode(blank_node) => Node::BlankNode(BlankNode::from_value(mapping.get(&blank_node.value)?.clone())),
+            node => node.clone(),
+        };
+        let context = match &quad.context {
+            Some(context) => Some(identifier(context)?),
+            None => None,
+        };
+        Some(Quad::new(identifier(&quad.subject)?, quad.predicate.clone(), object, context))
+    }
+
+    fn search(
+        a: &[Quad],
+        b: &HashSet<Quad>,
+        from: &[String],
+        to: &[String],
+        mapping: &mut HashMap<String, String>,
+    ) -> bool {
+        // Every quad whose blank nodes are all mapped must be in `b`
+        if !a.iter().filter_map(|quad| rename(quad, mapping)).all(|quad| b.contains(&quad)) {
+            return false;
+        }
+        let next = match from.iter().find(|value| !mapping.contains_key(*value)) {
+            Some(next) => next.clone(),
+            None => return true,
+        };
+        for candidate in to {
+            if mapping.values().any(|value| value == candidate) {
+                continue;
+            }
+            mapping.insert(next.clone(), candidate.clone());
+            if search(a, b, from, to, mapping) {
+                return true;
+            }
+            mapping.remove(&next);
+        }
+        false
+    }
+
+    let a_set: HashSet<&Quad> = a.iter().collect();
+    let b_set: HashSet<Quad> = b.iter().cloned().collect();
+    let (from, to) = (blank_nodes(a), blank_nodes(b));
+    if a_set.len() != b_set.len() || from.len() != to.len() {
+        return false;
+    }
+    let a: Vec<Quad> = a_set.into_iter().cloned().collect();
+    search(&a, &b_set, &from, &to, &mut HashMap::new())
+}
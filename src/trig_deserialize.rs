@@ -0,0 +1,94 @@
+use crate::blank_node_id::{BlankNodeIdGenerator, BlankNodeScope};
+use crate::turtle_deserialize::TurtleDeserializer;
+use std::str::Chars;
+
+// Parser for TriG (https://www.w3.org/TR/trig/).
+// TriG is Turtle with graph blocks, so it shares the Turtle statement and term parsers.
+// Statements outside of graph blocks go to the default graph.
+
+pub type TriGDeserializer<I> = TurtleDeserializer<I>;
+
+pub fn deserialize(trig: &str) -> TriGDeserializer<Chars<'_>> {
+    TurtleDeserializer::new(trig.chars(), BlankNodeScope::default(), None).with_graphs()
+}
+
+pub fn deserialize_with<G>(trig: &str, generator: G) -> TriGDeserializer<Chars<'_>>
+where
+    G: BlankNodeIdGenerator + 'static,
+{
+    TurtleDeserializer::new(trig.chars(), BlankNodeScope::new(generator), None).with_graphs()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::nquads_deserialize;
+    use crate::quad::Quad;
+    use crate::term::{Identifier, IRI};
+    use crate::test_set;
+    use crate::trig_deserialize::deserialize;
+    use std::fs;
+
+    #[test]
+    fn test_deserialize() {
+        let trig = r#"
+@prefix ex: <http://example.com#> .
+ex:iddan ex:likes ex:tamir .
+ex:ontology { ex:tamir ex:likes ex:iddan . ex:tamir a ex:Person }
+GRAPH <http://example.com#other> { ex:lior a ex:Person . }
+"#;
+        let quads: Vec<Quad> = deserialize(trig).map(|quad| quad.unwrap()).collect();
+        let contexts: Vec<Option<Identifier>> = quads.into_iter().map(|quad| quad.context).collect();
+        let graph = |name: &str| Some(Identifier::IRI(IRI::new(format!("http://example.com#{}", name))));
+        assert_eq!(contexts, vec![None, graph("ontology"), graph("ontology"), graph("other")]);
+        // An invalid statement in a block is skipped, and the block still ends the graph
+        let trig = "@prefix ex: <http://example.com#> .\nex:ontology { ex:a ex:b ex:c . foo:a ex:b ex:c . ex:d ex:e ex:f }\nex:f ex:g ex:h .";
+        let results: Vec<Result<Quad, _>> = deserialize(trig).collect();
+        assert_eq!(results.iter().filter(|result| result.is_err()).count(), 1);
+        let contexts: Vec<Option<Identifier>> = results.into_iter().filter_map(Result::ok).map(|quad| quad.context).collect();
+        assert_eq!(contexts, vec![graph("ontology"), graph("ontology"), None]);
+    }
+
+    // The vendored suite names the graph of most actions <http://example/model.graph>, but results
+    // shared by several tests, such as IRI_spo.nq, still name it <http://example/graph>
+    fn same_graph(quads: Vec<Quad>) -> Vec<Quad> {
+        let renamed = Some(Identifier::IRI(IRI::new("http://example/model.graph")));
+        quads
+            .into_iter()
+            .map(|quad| match quad.context {
+                ref context if *context == renamed => Quad { context: Some(Identifier::IRI(IRI::new("http://example/graph"))), ..quad },
+                _ => quad,
+            })
+            .collect()
+    }
+
+    // W3C TriG test suite, driven by its manifest
+    #[test]
+    fn test_w3c_suite() {
+        let manifest = test_set::Manifest::read("src/w3c/trig", "http://www.w3.org/2013/TriGTests/");
+        assert_eq!(manifest.entries.len(), 335);
+        for mut entry in manifest.entries {
+            // trig-graph-bad-08 has no mf:action, its input is named after the test
+            if entry.action_name.is_empty() {
+                entry.action_name = format!("{}.trig", entry.name);
+                entry.action = std::path::Path::new("src/w3c/trig").join(&entry.action_name);
+            }
+            let trig = fs::read_to_string(&entry.action).unwrap();
+            let base = IRI::new(format!("http://www.w3.org/2013/TriGTests/{}", entry.action_name));
+            let result: Result<Vec<Quad>, _> = deserialize(&trig).with_base(base).collect();
+            match entry.kind.as_str() {
+                "TestTrigPositiveSyntax" => assert!(result.is_ok(), "{} should be accepted: {:?}", entry.name, result),
+                "TestTrigNegativeSyntax" | "TestTrigNegativeEval" => {
+                    assert!(result.is_err(), "{} should be rejected", entry.name)
+                }
+                "TestTrigEval" => {
+                    let quads = result.unwrap_or_else(|error| panic!("{} should be accepted: {}", entry.name, error));
+                    let expected = fs::read_to_string(entry.result.unwrap()).unwrap();
+                    let expected: Vec<Quad> = nquads_deserialize::deserialize(&expected).map(|quad| quad.unwrap()).collect();
+                    let (quads, expected) = (same_graph(quads), same_graph(expected));
+                    assert!(test_set::isomorphic(&quads, &expected), "{} gave {:?}", entry.name, quads);
+                }
+                kind => panic!("Unknown test type {}", kind),
+            }
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use crate::namespace::PrefixMap;
+use crate::nquads_serialize::serialize_identifier;
+use crate::quad::{Context, Quad};
+use crate::term::{BlankNode, Identifier, Node};
+use crate::turtle_serialize::{write_prefixes, TurtleWriter};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+// Pretty TriG: the default graph is written as Turtle, followed by a `{ ... }` block per named graph.
+// Blank nodes are scoped to the document, so the ones used in more than one graph keep their labels.
+
+fn blank_nodes(quad: &Quad) -> Vec<&BlankNode> {
+    let mut blank_nodes = vec![];
+    if let Identifier::BlankNode(blank_node) = &quad.subject {
+        blank_nodes.push(blank_node);
+    }
+    if let Node::BlankNode(blank_node) = &quad.object {
+        blank_nodes.push(blank_node);
+    }
+    blank_nodes
+}
+
+/// Serializes `quads` as TriG, with prefixes from `prefixes` for the namespaces used.
+pub fn serialize<Q>(quads: Q, prefixes: &PrefixMap) -> String
+where
+    Q: IntoIterator<Item = Quad>,
+{
+    let mut graphs: BTreeMap<Context, Vec<Quad>> = BTreeMap::new();
+    for quad in quads {
+        graphs.entry(quad.context.clone()).or_default().push(quad);
+    }
+    let mut graph_counts: HashMap<&BlankNode, HashSet<&Context>> = HashMap::new();
+    let mut labeled = HashSet::new();
+    for (graph, quads) in graphs.iter() {
+        if let Some(Identifier::BlankNode(blank_node)) = graph {
+            labeled.insert(blank_node.clone());
+        }
+        for blank_node in quads.iter().flat_map(blank_nodes) {
+            graph_counts.entry(blank_node).or_default().insert(graph);
+        }
+    }
+    for (blank_node, graphs) in graph_counts {
+        if graphs.len() > 1 {
+            labeled.insert(blank_node.clone());
+        }
+    }
+
+    let mut used_prefixes = BTreeSet::new();
+    let mut sections = vec![];
+    for (graph, quads) in graphs {
+        let mut writer = TurtleWriter::with_labels(quads, prefixes, &labeled);
+        let section = match graph {
+            None => writer.write(""),
+            Some(label) => {
+                let label = match label {
+                    Identifier::IRI(iri) => writer.write_iri(&iri),
+                    label => serialize_identifier(label),
+                };
+                format!("{} {{\n{}}}\n", label, writer.write("    "))
+            }
+        };
+        used_prefixes.extend(writer.used_prefixes);
+        sections.push(section);
+    }
+    let directives = write_prefixes(prefixes, &used_prefixes);
+    let statements = sections.join("\n");
+    match (directives.is_empty(), statements.is_empty()) {
+        (false, false) => format!("{}\n{}", directives, statements),
+        _ => directives + &statements,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::namespace::{PrefixMap, FOAF};
+    use crate::quad::Quad;
+    use crate::term::{BlankNode, Identifier, IRI};
+    use crate::test_set;
+    use crate::trig_deserialize::deserialize;
+    use crate::trig_serialize::serialize;
+    use std::fs;
+
+    #[test]
+    fn test_serialize() {
+        let quads: Vec<Quad> = test_set::get_quads().into_iter().collect();
+        let mut prefixes = PrefixMap::default();
+        prefixes.insert("ex", "http://example.com#");
+        let trig = serialize(quads.clone(), &prefixes);
+        assert!(trig.starts_with("@prefix ex: <http://example.com#> .\n"));
+        assert!(trig.contains("\nex:ontology {\n    ex:iddan a ex:Person ;\n        ex:likes ex:tamir .\n"));
+        let parsed: Vec<Quad> = deserialize(&trig).map(|quad| quad.unwrap()).collect();
+        assert!(test_set::isomorphic(&parsed, &quads), "{}", trig);
+    }
+
+    #[test]
+    fn test_serialize_blank_node_across_graphs() {
+        let shared = BlankNode::from_value("shared");
+        let graph = |name: &str| Some(Identifier::IRI(IRI::new(format!("http://example.com#{}", name))));
+        let quads = vec![
+            Quad::new(IRI::new("http://example.com#iddan"), FOAF.iri("knows"), shared.clone(), graph("a")),
+            Quad::new(shared.clone(), FOAF.iri("name"), IRI::new("http://example.com#name"), graph("b")),
+            Quad::new(shared, FOAF.iri("name"), IRI::new("http://example.com#name"), None),
+        ];
+        let trig = serialize(quads.clone(), &PrefixMap::default());
+        let parsed: Vec<Quad> = deserialize(&trig).map(|quad| quad.unwrap()).collect();
+        assert!(test_set::isomorphic(&parsed, &quads), "{}", trig);
+    }
+
+    // Every W3C evaluation test result survives a round trip
+    #[test]
+    fn test_round_trip_w3c_suite() {
+        let manifest = test_set::Manifest::read("src/w3c/trig", "http://www.w3.org/2013/TriGTests/");
+        for entry in manifest.entries.into_iter().filter(|entry| entry.kind == "TestTrigEval") {
+            let nquads = fs::read_to_string(entry.result.as_ref().unwrap()).unwrap();
+            let quads: Vec<Quad> = crate::nquads_deserialize::deserialize(&nquads).map(|quad| quad.unwrap()).collect();
+            let trig = serialize(quads.clone(), &PrefixMap::default());
+            let parsed: Result<Vec<Quad>, _> = deserialize(&trig).collect();
+            let parsed = parsed.unwrap_or_else(|error| panic!("{}: {}\n{}", entry.name, error, trig));
+            assert!(test_set::isomorphic(&parsed, &quads), "{}\n{}", entry.name, trig);
+        }
+    }
+}
@@ -0,0 +1,830 @@
+use crate::blank_node_id::{BlankNodeIdGenerator, BlankNodeScope};
+use crate::namespace::{PrefixMap, RDF, XSD};
+use crate::nquads_deserialize::{
+    deserialize_blank_node_label, deserialize_escape, deserialize_iri_ref, deserialize_language, is_absolute_iri, is_pn_chars,
+    is_pn_chars_base, is_pn_chars_u, Input,
+};
+use crate::parse_error::{ParseError, ParseErrorKind};
+use crate::quad::{Context, Object, Predicate, Quad, Subject};
+use crate::term::{BlankNode, Identifier, Literal, Node, IRI};
+use std::collections::VecDeque;
+use std::str::Chars;
+
+// Parser for Turtle 1.1 (https://www.w3.org/TR/turtle/).
+// Statements are parsed one at a time and their triples queued into the target graph. After an
+// invalid statement parsing resumes past the next `.` that ends a statement, so later errors are
+// reported too; an invalid statement in a TriG block is skipped in the same way within the block.
+
+pub struct TurtleDeserializer<I: Iterator<Item = char>> {
+    chars: Input<I>,
+    blank_nodes: BlankNodeScope,
+    prefixes: PrefixMap,
+    base: Option<IRI>,
+    graph: Context,
+    // Whether `GRAPH` and `{ ... }` blocks are allowed, as in TriG
+    graphs: bool,
+    // The graph outside of the TriG block being parsed
+    outer_graph: Option<Context>,
+    pending: VecDeque<Quad>,
+    done: bool,
+}
+
+fn is_whitespace(c: char) -> bool {
+    c == ' ' || c == '\t' || c == '\n' || c == '\r'
+}
+
+// Characters that can follow a keyword such as `a` or `true` without making it a prefixed name
+fn ends_keyword(c: Option<char>) -> bool {
+    match c {
+        Some(c) => !(is_pn_chars(c) || c == ':' || c == '.'),
+        None => true,
+    }
+}
+
+impl<I: Iterator<Item = char>> TurtleDeserializer<I> {
+    pub fn new(chars: I, blank_nodes: BlankNodeScope, graph: Context) -> TurtleDeserializer<I> {
+        TurtleDeserializer {
+            chars: Input::new(chars),
+            blank_nodes,
+            prefixes: PrefixMap::new(),
+            base: None,
+            graph,
+            graphs: false,
+            outer_graph: None,
+            pending: VecDeque::new(),
+            done: false,
+        }
+    }
+
+    /// Resolves relative IRIs against `base` until the document sets its own.
+    pub fn with_base(mut self, base: IRI) -> TurtleDeserializer<I> {
+        self.base = Some(base);
+        self
+    }
+
+    /// Starts with `prefixes` declared, in addition to the ones the document declares.
+    pub fn with_prefixes(mut self, prefixes: PrefixMap) -> TurtleDeserializer<I> {
+        self.prefixes = prefixes;
+        self
+    }
+
+    /// Accepts TriG, where statements in `{ ... }` blocks go to the named graph before them.
+    pub fn with_graphs(mut self) -> TurtleDeserializer<I> {
+        self.graphs = true;
+        self
+    }
+
+    /// The prefixes declared so far.
+    pub fn prefixes(&self) -> &PrefixMap {
+        &self.prefixes
+    }
+
+    fn skip_whitespace(&mut self) {
+        loop {
+            match self.chars.peek() {
+                Some(c) if is_whitespace(c) => {
+                    self.chars.next();
+                }
+                Some('#') => self.chars.skip_comment(),
+                _ => return,
+            }
+        }
+    }
+
+    fn emit(&mut self, subject: Subject, predicate: Predicate, object: Object) {
+        let quad = Quad::new(subject, predicate, object, self.graph.clone());
+        self.pending.push_back(quad);
+    }
+
+    // Matches a case insensitive keyword followed by whitespace, without consuming anything
+    fn peek_keyword(&mut self, keyword: &str) -> bool {
+        for (n, expected) in keyword.chars().enumerate() {
+            match self.chars.peek_nth(n) {
+                Some(c) if c.eq_ignore_ascii_case(&expected) => {}
+                _ => return false,
+            }
+        }
+        match self.chars.peek_nth(keyword.len()) {
+            Some(c) => is_whitespace(c) || c == '<' || c == '#' || c == '[',
+            None => false,
+        }
+    }
+
+    fn consume(&mut self, count: usize) {
+        for _ in 0..count {
+            self.chars.next();
+        }
+    }
+
+    fn expect_word(&mut self, word: &str) -> Result<(), ParseError> {
+        for expected in word.chars() {
+            self.chars.expect(expected)?;
+        }
+        Ok(())
+    }
+
+    fn iri_ref(&mut self) -> Result<IRI, ParseError> {
+        let value = deserialize_iri_ref(&mut self.chars)?;
+        match &self.base {
+            Some(base) => Ok(base.resolve(&value)),
+            None if is_absolute_iri(&value) => Ok(IRI::new(value)),
+            None => Err(self.chars.error(ParseErrorKind::RelativeIRI(value))),
+        }
+    }
+
+    // PNAME_NS without the colon
+    fn prefix_name(&mut self) -> Result<String, ParseError> {
+        let mut prefix = String::new();
+        match self.chars.peek() {
+            Some(':') => {}
+            Some(c) if is_pn_chars_base(c) => {
+                prefix.push(c);
+                self.chars.next();
+                loop {
+                    match self.chars.peek() {
+                        Some(c) if is_pn_chars(c) => {
+                            prefix.push(c);
+                            self.chars.next();
+                        }
+                        Some('.') => {
+                            let mut dots = 1;
+                            while self.chars.peek_nth(dots) == Some('.') {
+                                dots += 1;
+                            }
+                            match self.chars.peek_nth(dots) {
+                                Some(c) if is_pn_chars(c) => {
+                                    for _ in 0..dots {
+                                        prefix.push('.');
+                                        self.chars.next();
+                                    }
+                                }
+                                _ => break,
+                            }
+                        }
+                        _ => break,
+                    }
+                }
+            }
+            _ => return Err(self.chars.reject()),
+        }
+        self.chars.expect(':')?;
+        Ok(prefix)
+    }
+
+    // PLX, appended to `local`, if the next characters are one
+    fn local_escape(&mut self, local: &mut String) -> Result<bool, ParseError> {
+        match self.chars.peek() {
+            Some('%') => {
+                for n in 1..3 {
+                    match self.chars.peek_nth(n) {
+                        Some(c) if c.is_ascii_hexdigit() => {}
+                        _ => {
+                            self.consume(n);
+                            return Err(self.chars.reject());
+                        }
+                    }
+                }
+                for _ in 0..3 {
+                    local.push(self.chars.next().unwrap());
+                }
+                Ok(true)
+            }
+            Some('\\') => {
+                self.chars.next();
+                match self.chars.next() {
+                    Some(
+                        c @ '_' | c @ '~' | c @ '.' | c @ '-' | c @ '!' | c @ '$' | c @ '&' | c @ '\'' | c @ '('
+                        | c @ ')' | c @ '*' | c @ '+' | c @ ',' | c @ ';' | c @ '=' | c @ '/' | c @ '?' | c @ '#'
+                        | c @ '@' | c @ '%',
+                    ) => {
+                        local.push(c);
+                        Ok(true)
+                    }
+                    c => Err(self.chars.unexpected(c)),
+                }
+            }
+            _ => Ok(false),
+        }
+    }
+
+    fn local_name(&mut self) -> Result<String, ParseError> {
+        let mut local = String::new();
+        match self.chars.peek() {
+            Some(c) if is_pn_chars_u(c) || c == ':' || c.is_ascii_digit() => {
+                local.push(c);
+                self.chars.next();
+            }
+            _ => {
+                if !self.local_escape(&mut local)? {
+                    return Ok(local);
+                }
+            }
+        }
+        loop {
+            match self.chars.peek() {
+                Some(c) if is_pn_chars(c) || c == ':' => {
+                    local.push(c);
+                    self.chars.next();
+                }
+                Some('.') => {
+                    let mut dots = 1;
+                    while self.chars.peek_nth(dots) == Some('.') {
+                        dots += 1;
+                    }
+                    match self.chars.peek_nth(dots) {
+                        Some(c) if is_pn_chars(c) || c == ':' || c == '%' || c == '\\' => {
+                            for _ in 0..dots {
+                                local.push('.');
+                                self.chars.next();
+                            }
+                        }
+                        _ => return Ok(local),
+                    }
+                }
+                _ => {
+                    if !self.local_escape(&mut local)? {
+                        return Ok(local);
+                    }
+                }
+            }
+        }
+    }
+
+    fn prefixed_name(&mut self) -> Result<IRI, ParseError> {
+        let prefix = self.prefix_name()?;
+        let local = self.local_name()?;
+        match self.prefixes.get(&prefix) {
+            Some(address) => Ok(IRI::new(format!("{}{}", address, local))),
+            None => Err(self.chars.error(ParseErrorKind::UndefinedPrefix(prefix))),
+        }
+    }
+
+    fn iri(&mut self) -> Result<IRI, ParseError> {
+        match self.chars.peek() {
+            Some('<') => self.iri_ref(),
+            _ => self.prefixed_name(),
+        }
+    }
+
+    fn blank_node(&mut self) -> Result<BlankNode, ParseError> {
+        let blank_node = deserialize_blank_node_label(&mut self.chars, false)?;
+        Ok(self.blank_nodes.labeled(&blank_node.value))
+    }
+
+    // Whether the next characters are `[` and `]` with only whitespace in between
+    fn peek_anon(&mut self) -> bool {
+        if self.chars.peek() != Some('[') {
+            return false;
+        }
+        let mut n = 1;
+        loop {
+            match self.chars.peek_nth(n) {
+                Some(']') => return true,
+                Some(c) if is_whitespace(c) => n += 1,
+                Some('#') => {
+                    while let Some(c) = self.chars.peek_nth(n) {
+                        if c == '\n' || c == '\r' {
+                            break;
+                        }
+                        n += 1;
+                    }
+                }
+                _ => return false,
+            }
+        }
+    }
+
+    fn anon(&mut self) -> Result<BlankNode, ParseError> {
+        self.chars.expect('[')?;
+        self.skip_whitespace();
+        self.chars.expect(']')?;
+        Ok(self.blank_nodes.fresh())
+    }
+
+    fn string(&mut self) -> Result<String, ParseError> {
+        let quote = match self.chars.next() {
+            Some(c @ '"') | Some(c @ '\'') => c,
+            c => return Err(self.chars.unexpected(c)),
+        };
+        let long = self.chars.peek() == Some(quote) && self.chars.peek_nth(1) == Some(quote);
+        if long {
+            self.consume(2);
+        } else if self.chars.peek() == Some(quote) {
+            self.chars.next();
+            return Ok(String::new());
+        }
+        let mut accumulator = String::new();
+        loop {
+            match self.chars.next() {
+                Some('\\') => accumulator.push(deserialize_escape(&mut self.chars)?),
+                Some(c) if c == quote && !long => return Ok(accumulator),
+                Some(c) if c == quote && self.chars.peek() == Some(quote) && self.chars.peek_nth(1) == Some(quote) => {
+                    self.consume(2);
+                    return Ok(accumulator);
+                }
+                c @ Some('\n') | c @ Some('\r') if !long => return Err(self.chars.unexpected(c)),
+                Some(c) => accumulator.push(c),
+                None => return Err(self.chars.unexpected(None)),
+            }
+        }
+    }
+
+    fn rdf_literal(&mut self) -> Result<Literal, ParseError> {
+        let value = self.string()?;
+        match self.chars.peek() {
+            Some('@') => {
+                let language = deserialize_language(&mut self.chars)?;
+                Ok(Literal::new(value, None, language))
+            }
+            Some('^') => {
+                self.chars.next();
+                self.chars.expect('^')?;
+                let datatype = self.iri()?;
+                Ok(Literal::new(value, datatype, None))
+            }
+            _ => Ok(Literal::new(value, None, None)),
+        }
+    }
+
+    fn digits(&mut self, accumulator: &mut String) -> usize {
+        let mut count = 0;
+        while let Some(c) = self.chars.peek() {
+            if !c.is_ascii_digit() {
+                break;
+            }
+            accumulator.push(c);
+            self.chars.next();
+            count += 1;
+        }
+        count
+    }
+
+    fn is_exponent_at(&mut self, n: usize) -> bool {
+        match (self.chars.peek_nth(n), self.chars.peek_nth(n + 1), self.chars.peek_nth(n + 2)) {
+            (Some('e'), Some(c), _) | (Some('E'), Some(c), _) if c.is_ascii_digit() => true,
+            (Some('e'), Some('+'), Some(c))
+            | (Some('e'), Some('-'), Some(c))
+            | (Some('E'), Some('+'), Some(c))
+            | (Some('E'), Some('-'), Some(c)) => c.is_ascii_digit(),
+            _ => false,
+        }
+    }
+
+    fn numeric_literal(&mut self) -> Result<Literal, ParseError> {
+        let mut value = String::new();
+        if let Some(c @ '+') | Some(c @ '-') = self.chars.peek() {
+            value.push(c);
+            self.chars.next();
+        }
+        let integer_digits = self.digits(&mut value);
+        let mut datatype = "integer";
+        if self.chars.peek() == Some('.')
+            && (self.chars.peek_nth(1).is_some_and(|c| c.is_ascii_digit())
+                || (integer_digits > 0 && self.is_exponent_at(1)))
+        {
+            value.push('.');
+            self.chars.next();
+            self.digits(&mut value);
+            datatype = "decimal";
+        } else if integer_digits == 0 {
+            return Err(self.chars.reject());
+        }
+        if self.is_exponent_at(0) {
+            value.push(self.chars.next().unwrap());
+            if let Some(c @ '+') | Some(c @ '-') = self.chars.peek() {
+                value.push(c);
+                self.chars.next();
+            }
+            self.digits(&mut value);
+            datatype = "double";
+        }
+        Ok(Literal::new(value, XSD.iri(datatype), None))
+    }
+
+    fn collection(&mut self) -> Result<Node, ParseError> {
+        self.chars.expect('(')?;
+        let mut items = vec![];
+        loop {
+            self.skip_whitespace();
+            if self.chars.peek() == Some(')') {
+                self.chars.next();
+                break;
+            }
+            items.push(self.object()?);
+        }
+        let mut list = Node::IRI(RDF.iri("nil"));
+        let cells: Vec<BlankNode> = items.iter().map(|_| self.blank_nodes.fresh()).collect();
+        for (item, cell) in items.into_iter().zip(cells).rev() {
+            let subject = Identifier::BlankNode(cell.clone());
+            self.emit(subject.clone(), RDF.iri("first"), item);
+            self.emit(subject, RDF.iri("rest"), list);
+            list = Node::BlankNode(cell);
+        }
+        Ok(list)
+    }
+
+    fn blank_node_property_list(&mut self) -> Result<BlankNode, ParseError> {
+        self.chars.expect('[')?;
+        let blank_node = self.blank_nodes.fresh();
+        self.skip_whitespace();
+        self.predicate_object_list(&Identifier::BlankNode(blank_node.clone()))?;
+        self.skip_whitespace();
+        self.chars.expect(']')?;
+        Ok(blank_node)
+    }
+
+    fn object(&mut self) -> Result<Node, ParseError> {
+        match self.chars.peek() {
+            Some('<') => Ok(Node::IRI(self.iri_ref()?)),
+            Some('_') if self.chars.peek_nth(1) == Some(':') => Ok(Node::BlankNode(self.blank_node()?)),
+            Some('[') if self.peek_anon() => Ok(Node::BlankNode(self.anon()?)),
+            Some('[') => Ok(Node::BlankNode(self.blank_node_property_list()?)),
+            Some('(') => self.collection(),
+            Some('"') | Some('\'') => Ok(Node::Literal(self.rdf_literal()?)),
+            Some(c) if c.is_ascii_digit() || c == '+' || c == '-' || c == '.' => {
+                Ok(Node::Literal(self.numeric_literal()?))
+            }
+            Some('t') if self.peek_word("true") => {
+                self.consume(4);
+                Ok(Node::Literal(Literal::new("true", XSD.iri("boolean"), None)))
+            }
+            Some('f') if self.peek_word("false") => {
+                self.consume(5);
+                Ok(Node::Literal(Literal::new("false", XSD.iri("boolean"), None)))
+            }
+            _ => Ok(Node::IRI(self.prefixed_name()?)),
+        }
+    }
+
+    // Whether the next characters are `word` as a keyword rather than the start of a prefixed name
+    fn peek_word(&mut self, word: &str) -> bool {
+        for (n, expected) in word.chars().enumerate() {
+            if self.chars.peek_nth(n) != Some(expected) {
+                return false;
+            }
+        }
+        let length = word.len();
+        match self.chars.peek_nth(length) {
+            Some('.') => ends_keyword(self.chars.peek_nth(length + 1)),
+            c => ends_keyword(c),
+        }
+    }
+
+    fn verb(&mut self) -> Result<IRI, ParseError> {
+        if self.peek_word("a") {
+            self.chars.next();
+            return Ok(RDF.iri("type"));
+        }
+        self.iri()
+    }
+
+    fn object_list(&mut self, subject: &Subject, predicate: &Predicate) -> Result<(), ParseError> {
+        loop {
+            let object = self.object()?;
+            self.emit(subject.clone(), predicate.clone(), object);
+            self.skip_whitespace();
+            if self.chars.peek() != Some(',') {
+                return Ok(());
+            }
+            self.chars.next();
+            self.skip_whitespace();
+        }
+    }
+
+    fn predicate_object_list(&mut self, subject: &Subject) -> Result<(), ParseError> {
+        loop {
+            let predicate = self.verb()?;
+            self.skip_whitespace();
+            self.object_list(subject, &predicate)?;
+            self.skip_whitespace();
+            if self.chars.peek() != Some(';') {
+                return Ok(());
+            }
+            while self.chars.peek() == Some(';') {
+                self.chars.next();
+                self.skip_whitespace();
+            }
+            match self.chars.peek() {
+                Some('.') | Some(']') | Some('}') | None => return Ok(()),
+                _ => {}
+            }
+        }
+    }
+
+    fn subject(&mut self) -> Result<Subject, ParseError> {
+        match self.chars.peek() {
+            Some('<') => Ok(Identifier::IRI(self.iri_ref()?)),
+            Some('_') if self.chars.peek_nth(1) == Some(':') => Ok(Identifier::BlankNode(self.blank_node()?)),
+            Some('[') if self.peek_anon() => Ok(Identifier::BlankNode(self.anon()?)),
+            Some('(') => match self.collection()? {
+                Node::BlankNode(blank_node) => Ok(Identifier::BlankNode(blank_node)),
+                Node::IRI(iri) => Ok(Identifier::IRI(iri)),
+                Node::Literal(_) => unreachable!(),
+            },
+            _ => Ok(Identifier::IRI(self.prefixed_name()?)),
+        }
+    }
+
+    // triples, without the final '.'
+    fn triples(&mut self) -> Result<(), ParseError> {
+        if self.chars.peek() == Some('[') && !self.peek_anon() {
+            let blank_node = self.blank_node_property_list()?;
+            self.skip_whitespace();
+            match self.chars.peek() {
+                Some('.') | Some('}') | None => return Ok(()),
+                _ => return self.predicate_object_list(&Identifier::BlankNode(blank_node)),
+            }
+        }
+        let subject = self.subject()?;
+        self.skip_whitespace();
+        self.predicate_object_list(&subject)
+    }
+
+    // Parses a directive if one is next, returning whether there was one
+    fn directive(&mut self) -> Result<bool, ParseError> {
+        let (sparql, prefix) = if self.chars.peek() == Some('@') {
+            self.chars.next();
+            match self.chars.peek() {
+                Some('p') => {
+                    self.expect_word("prefix")?;
+                    (false, true)
+                }
+                Some('b') => {
+                    self.expect_word("base")?;
+                    (false, false)
+                }
+                _ => return Err(self.chars.reject()),
+            }
+        } else if self.peek_keyword("prefix") {
+            self.consume(6);
+            (true, true)
+        } else if self.peek_keyword("base") {
+            self.consume(4);
+            (true, false)
+        } else {
+            return Ok(false);
+        };
+        self.skip_whitespace();
+        if prefix {
+            let name = self.prefix_name()?;
+            self.skip_whitespace();
+            let iri = self.iri_ref()?;
+            self.prefixes.insert(name, iri.value);
+        } else {
+            let iri = self.iri_ref()?;
+            self.base = Some(iri);
+        }
+        if !sparql {
+            self.skip_whitespace();
+            self.chars.expect('.')?;
+        }
+        Ok(true)
+    }
+
+    // labelOrSubject of TriG
+    fn label(&mut self) -> Result<Identifier, ParseError> {
+        match self.chars.peek() {
+            Some('_') if self.chars.peek_nth(1) == Some(':') => Ok(Identifier::BlankNode(self.blank_node()?)),
+            Some('[') => Ok(Identifier::BlankNode(self.anon()?)),
+            _ => Ok(Identifier::IRI(self.iri()?)),
+        }
+    }
+
+    // Opens a block, whose statements are then parsed one at a time like top-level ones
+    fn wrapped_graph(&mut self, label: Context) -> Result<(), ParseError> {
+        self.chars.expect('{')?;
+        self.outer_graph = Some(std::mem::replace(&mut self.graph, label));
+        Ok(())
+    }
+
+    // A statement of a block, or its closing brace
+    fn graph_statement(&mut self) -> Result<(), ParseError> {
+        self.skip_whitespace();
+        if self.chars.peek() != Some('}') {
+            self.triples()?;
+            self.skip_whitespace();
+            if self.chars.peek() == Some('.') {
+                self.chars.next();
+                return Ok(());
+            }
+        }
+        self.skip_whitespace();
+        self.chars.expect('}')?;
+        self.graph = self.outer_graph.take().unwrap();
+        Ok(())
+    }
+
+    // A TriG block, other than a directive
+    fn block(&mut self) -> Result<(), ParseError> {
+        if self.peek_keyword("graph") {
+            self.consume(5);
+            self.skip_whitespace();
+            let label = self.label()?;
+            self.skip_whitespace();
+            return self.wrapped_graph(Some(label));
+        }
+        match self.chars.peek() {
+            Some('{') => return self.wrapped_graph(None),
+            Some('(') => self.triples()?,
+            Some('[') if !self.peek_anon() => self.triples()?,
+            _ => {
+                let label = self.label()?;
+                self.skip_whitespace();
+                if self.chars.peek() == Some('{') {
+                    return self.wrapped_graph(Some(label));
+                }
+                self.predicate_object_list(&label)?;
+            }
+        }
+        self.skip_whitespace();
+        self.chars.expect('.')
+    }
+
+    fn statement(&mut self) -> Result<bool, ParseError> {
+        if self.outer_graph.is_some() {
+            self.graph_statement()?;
+            return Ok(true);
+        }
+        self.skip_whitespace();
+        if self.chars.peek().is_none() {
+            return Ok(false);
+        }
+        if self.directive()? {
+            return Ok(true);
+        }
+        if self.graphs {
+            self.block()?;
+        } else {
+            self.triples()?;
+            self.skip_whitespace();
+            self.chars.expect('.')?;
+        }
+        Ok(true)
+    }
+
+    // Skips the rest of an invalid statement, up to a `.` followed by whitespace outside of IRIs,
+    // strings and brackets, or in a block up to its closing brace. Returns whether any input is left.
+    fn recover(&mut self) -> bool {
+        let mut depth = 0usize;
+        let mut quote = None;
+        while let Some(c) = self.chars.peek() {
+            match (quote, c) {
+                (Some('>'), '>') => quote = None,
+                (Some('>'), _) => {}
+                (Some(_), '\\') => {
+                    self.chars.next();
+                }
+                (Some(quote_char), c) if c == quote_char => quote = None,
+                (Some(_), _) => {}
+                (None, '<' | '"' | '\'') => quote = Some(if c == '<' { '>' } else { c }),
+                (None, '#') => {
+                    self.chars.skip_comment();
+                    continue;
+                }
+                (None, '[' | '(' | '{') => depth += 1,
+                (None, '}') if depth == 0 && self.outer_graph.is_some() => return true,
+                (None, ']' | ')' | '}') => depth = depth.saturating_sub(1),
+                (None, '.') if depth == 0 => {
+                    let next = self.chars.peek_nth(1);
+                    if next.is_none_or(|next| is_whitespace(next) || next == '#' || next == '}') {
+                        self.chars.next();
+                        return next.is_some();
+                    }
+                }
+                (None, _) => {}
+            }
+            self.chars.next();
+        }
+        false
+    }
+}
+
+impl<I: Iterator<Item = char>> Iterator for TurtleDeserializer<I> {
+    type Item = Result<Quad, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(quad) = self.pending.pop_front() {
+                return Some(Ok(quad));
+            }
+            if self.done {
+                return None;
+            }
+            match self.statement() {
+                Ok(true) => {}
+                Ok(false) => self.done = true,
+                Err(error) => {
+                    self.pending.clear();
+                    self.done = !self.recover();
+                    return Some(Err(error));
+                }
+            }
+        }
+    }
+}
+
+pub fn deserialize<C>(turtle: &str, graph: C) -> TurtleDeserializer<Chars<'_>>
+where
+    C: Into<Context>,
+{
+    TurtleDeserializer::new(turtle.chars(), BlankNodeScope::default(), graph.into())
+}
+
+pub fn deserialize_with<C, G>(turtle: &str, graph: C, generator: G) -> TurtleDeserializer<Chars<'_>>
+where
+    C: Into<Context>,
+    G: BlankNodeIdGenerator + 'static,
+{
+    TurtleDeserializer::new(turtle.chars(), BlankNodeScope::new(generator), graph.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::namespace::{RDF, XSD};
+    use crate::nquads_deserialize;
+    use crate::quad::Quad;
+    use crate::term::{Identifier, Literal, Node, IRI};
+    use crate::test_set;
+    use crate::turtle_deserialize::deserialize;
+    use std::fs;
+
+    #[test]
+    fn test_deserialize() {
+        let turtle = r#"
+@prefix ex: <http://example.com#> .
+PREFIX foaf: <http://xmlns.com/foaf/0.1/>
+@base <http://example.com/base/> .
+ex:iddan a foaf:Person ;
+    foaf:knows ex:tamir, <lior> ;
+    ex:age 20 ;
+    ex:height 1.8 ;
+    ex:ratio 1e3 ;
+    ex:active true ;
+    ex:nick 'id', """multi
+line"""@en ;
+    ex:list ( 1 [ ex:p ex:o ] ) .
+[] ex:p [ ex:q "v"^^ex:type ] .
+"#;
+        let graph = Identifier::from(IRI::new("http://example.com#graph"));
+        let mut deserializer = deserialize(turtle, graph.clone());
+        let quads: Vec<Quad> = deserializer.by_ref().map(|quad| quad.unwrap()).collect();
+        assert_eq!(deserializer.prefixes().get("foaf"), Some("http://xmlns.com/foaf/0.1/"));
+        assert_eq!(quads.len(), 17);
+        assert!(quads.iter().all(|quad| quad.context == Some(graph.clone())));
+        let objects: Vec<&Node> = quads.iter().map(|quad| &quad.object).collect();
+        assert!(objects.contains(&&Node::from(IRI::new("http://example.com/base/lior"))));
+        assert!(objects.contains(&&Node::from(Literal::new("1.8", XSD.iri("decimal"), None))));
+        assert!(objects.contains(&&Node::from(Literal::new("1e3", XSD.iri("double"), None))));
+        assert!(objects.contains(&&Node::from(Literal::new("true", XSD.iri("boolean"), None))));
+        assert!(objects.contains(&&Node::from(Literal::new("multi\nline", None, Some("en".to_owned())))));
+        assert!(objects.contains(&&Node::from(RDF.iri("nil"))));
+    }
+
+    #[test]
+    fn test_relative_iri() {
+        let error = deserialize("<a> <http://example.com#b> <http://example.com#c> .", None).next().unwrap().unwrap_err();
+        assert_eq!(error.kind, crate::parse_error::ParseErrorKind::RelativeIRI("a".to_owned()));
+        let quad = deserialize("<a> <http://example.com#b> <http://example.com#c> .", None)
+            .with_base(IRI::new("http://example.com/"))
+            .next()
+            .unwrap()
+            .unwrap();
+        assert_eq!(quad.subject, Identifier::from(IRI::new("http://example.com/a")));
+    }
+
+    #[test]
+    fn test_undefined_prefix() {
+        let error = deserialize("ex:a ex:b ex:c .", None).next().unwrap().unwrap_err();
+        assert_eq!(error.kind, crate::parse_error::ParseErrorKind::UndefinedPrefix("ex".to_owned()));
+        // Parsing resumes after the invalid statement
+        let turtle = "@prefix ex: <http://example.com#> .\nex:a ex:b ex:c .\nex:a ex:b [ ex:c \"x . y\" ; ex:d <http://example.com/.> ] 1.5 .\nex:d ex:e ex:f .\nfoo:a ex:b ex:c .\nex:g ex:h ex:i .";
+        let results: Vec<Result<Quad, _>> = deserialize(turtle, None).collect();
+        assert_eq!(results.iter().map(Result::is_ok).collect::<Vec<bool>>(), [true, false, true, false, true]);
+        assert_eq!(results[2].as_ref().unwrap().subject, Identifier::from(IRI::new("http://example.com#d")));
+    }
+
+    // W3C Turtle test suite, driven by its manifest
+    #[test]
+    fn test_w3c_suite() {
+        let manifest = test_set::Manifest::read("src/w3c/turtle", "http://www.w3.org/2013/TurtleTests/");
+        assert_eq!(manifest.entries.len(), 291);
+        for entry in manifest.entries {
+            let turtle = fs::read_to_string(&entry.action).unwrap();
+            let base = IRI::new(format!("http://www.w3.org/2013/TurtleTests/{}", entry.action_name));
+            let result: Result<Vec<Quad>, _> = deserialize(&turtle, None).with_base(base).collect();
+            match entry.kind.as_str() {
+                "TestTurtlePositiveSyntax" => assert!(result.is_ok(), "{} should be accepted: {:?}", entry.name, result),
+                "TestTurtleNegativeSyntax" | "TestTurtleNegativeEval" => {
+                    assert!(result.is_err(), "{} should be rejected", entry.name)
+                }
+                "TestTurtleEval" => {
+                    let quads = result.unwrap_or_else(|error| panic!("{} should be accepted: {}", entry.name, error));
+                    let expected = fs::read_to_string(entry.result.unwrap()).unwrap();
+                    let expected: Vec<Quad> = nquads_deserialize::deserialize(&expected).map(|quad| quad.unwrap()).collect();
+                    assert!(test_set::isomorphic(&quads, &expected), "{} gave {:?}", entry.name, quads);
+                }
+                kind => panic!("Unknown test type {}", kind),
+            }
+        }
+    }
+}
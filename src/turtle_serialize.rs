@@ -0,0 +1,457 @@
+use crate::namespace::{PrefixMap, RDF, XSD};
+use crate::nquads_deserialize::{is_pn_chars, is_pn_chars_base, is_pn_chars_u};
+use crate::nquads_serialize::{escape_iri, escape_string, serialize_identifier};
+use crate::quad::Quad;
+use crate::term::{BlankNode, Identifier, Literal, Node, IRI};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+// Pretty Turtle: statements are grouped by subject, IRIs are compacted with the given prefixes,
+// blank nodes referenced once are nested as `[ ... ]` and well formed lists written as `( ... )`.
+// The context of the quads is not written.
+
+const INDENT: &str = "    ";
+
+// PN_LOCAL without escapes
+fn is_pn_local(local: &str) -> bool {
+    local.is_empty()
+        || (local.starts_with(|c: char| is_pn_chars_u(c) || c.is_ascii_digit() || c == ':')
+            && !local.ends_with('.')
+            && local.chars().all(|c| is_pn_chars(c) || c == '.' || c == ':'))
+}
+
+fn is_pn_prefix(prefix: &str) -> bool {
+    prefix.is_empty()
+        || (prefix.starts_with(is_pn_chars_base)
+            && !prefix.ends_with('.')
+            && prefix.chars().all(|c| is_pn_chars(c) || c == '.'))
+}
+
+// Whether `value` is in the Turtle shorthand syntax of the numeric `datatype`
+fn is_numeric_shorthand(value: &str, datatype: &str) -> bool {
+    let is_digits = |part: &str| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit());
+    let unsigned = value.strip_prefix(|c| c == '+' || c == '-').unwrap_or(value);
+    let (mantissa, exponent) = match unsigned.find(['e', 'E']) {
+        Some(index) => (&unsigned[..index], Some(&unsigned[index + 1..])),
+        None => (unsigned, None),
+    };
+    let (integer, fraction) = match mantissa.find('.') {
+        Some(index) => (&mantissa[..index], Some(&mantissa[index + 1..])),
+        None => (mantissa, None),
+    };
+    match (datatype, fraction, exponent) {
+        ("integer", None, None) => is_digits(integer),
+        ("decimal", Some(fraction), None) => is_digits(fraction) && (integer.is_empty() || is_digits(integer)),
+        ("double", fraction, Some(exponent)) => {
+            let exponent = exponent.strip_prefix(|c| c == '+' || c == '-').unwrap_or(exponent);
+            let mantissa_valid = match fraction {
+                Some(fraction) if integer.is_empty() => is_digits(fraction),
+                Some(fraction) => is_digits(integer) && (fraction.is_empty() || is_digits(fraction)),
+                None => is_digits(integer),
+            };
+            mantissa_valid && is_digits(exponent)
+        }
+        _ => false,
+    }
+}
+
+/// Writes the triples of one graph, recording the prefixes it uses.
+pub(crate) struct TurtleWriter<'a> {
+    prefixes: &'a PrefixMap,
+    pub(crate) used_prefixes: BTreeSet<String>,
+    statements: BTreeMap<Identifier, BTreeMap<IRI, Vec<Node>>>,
+    inline: HashSet<BlankNode>,
+    lists: HashMap<BlankNode, Vec<Node>>,
+    labeled: HashSet<BlankNode>,
+    // Blank nodes that are the object of some statement
+    referenced: HashSet<BlankNode>,
+}
+
+impl<'a> TurtleWriter<'a> {
+    pub(crate) fn new<Q>(quads: Q, prefixes: &'a PrefixMap) -> TurtleWriter<'a>
+    where
+        Q: IntoIterator<Item = Quad>,
+    {
+        TurtleWriter::with_labels(quads, prefixes, &HashSet::new())
+    }
+
+    /// Like `new`, but the blank nodes in `labeled` are never nested, as they are also used elsewhere.
+    pub(crate) fn with_labels<Q>(quads: Q, prefixes: &'a PrefixMap, labeled: &HashSet<BlankNode>) -> TurtleWriter<'a>
+    where
+        Q: IntoIterator<Item = Quad>,
+    {
+        let mut statements: BTreeMap<Identifier, BTreeMap<IRI, Vec<Node>>> = BTreeMap::new();
+        let mut references: HashMap<BlankNode, usize> = HashMap::new();
+        for quad in quads {
+            let objects = statements.entry(quad.subject).or_default().entry(quad.predicate).or_default();
+            if objects.contains(&quad.object) {
+                continue;
+            }
+            if let Node::BlankNode(blank_node) = &quad.object {
+                *references.entry(blank_node.clone()).or_default() += 1;
+            }
+            objects.push(quad.object);
+        }
+        for objects in statements.values_mut().flat_map(|predicates| predicates.values_mut()) {
+            objects.sort();
+        }
+        let mut writer = TurtleWriter {
+            prefixes,
+            used_prefixes: BTreeSet::new(),
+            statements,
+            inline: HashSet::new(),
+            lists: HashMap::new(),
+            labeled: labeled.clone(),
+            referenced: references.keys().cloned().collect(),
+        };
+        writer.inline = references
+            .into_iter()
+            .filter(|(blank_node, count)| *count == 1 && !labeled.contains(blank_node))
+            .map(|(blank_node, _)| blank_node)
+            .collect();
+        writer.find_lists();
+        writer.break_cycles();
+        writer
+    }
+
+    fn predicates(&self, blank_node: &BlankNode) -> Option<&BTreeMap<IRI, Vec<Node>>> {
+        self.statements.get(&Identifier::BlankNode(blank_node.clone()))
+    }
+
+    // A list node has exactly one rdf:first and one rdf:rest and nothing else
+    fn list_cell(&self, blank_node: &BlankNode) -> Option<(Node, Node)> {
+        let predicates = self.predicates(blank_node)?;
+        let first = predicates.get(&RDF.iri("first"))?;
+        let rest = predicates.get(&RDF.iri("rest"))?;
+        if predicates.len() != 2 || first.len() != 1 || rest.len() != 1 {
+            return None;
+        }
+        Some((first[0].clone(), rest[0].clone()))
+    }
+
+    fn find_lists(&mut self) {
+        let mut lists = HashMap::new();
+        for head in self.inline.iter() {
+            let mut items = vec![];
+            let mut cells = HashSet::new();
+            let mut cell = head.clone();
+            let complete = loop {
+                if !self.inline.contains(&cell) || !cells.insert(cell.clone()) {
+                    break false;
+                }
+                match self.list_cell(&cell) {
+                    Some((first, Node::BlankNode(rest))) => {
+                        items.push(first);
+                        cell = rest;
+                    }
+                    Some((first, Node::IRI(ref nil))) if *nil == RDF.iri("nil") => {
+                        items.push(first);
+                        break true;
+                    }
+                    _ => break false,
+                }
+            };
+            if complete {
+                lists.insert(head.clone(), items);
+            }
+        }
+        // Only the heads are written as lists, a tail is written as part of its head
+        let tails: HashSet<BlankNode> = lists
+            .keys()
+            .filter_map(|head| match self.list_cell(head) {
+                Some((_, Node::BlankNode(rest))) => Some(rest),
+                _ => None,
+            })
+            .collect();
+        lists.retain(|head, _| !tails.contains(head));
+        self.lists = lists;
+    }
+
+    fn inlined_objects(&self, predicates: &BTreeMap<IRI, Vec<Node>>, reached: &mut Vec<BlankNode>) {
+        for object in predicates.values().flatten() {
+            if let Node::BlankNode(blank_node) = object {
+                if self.inline.contains(blank_node) {
+                    reached.push(blank_node.clone());
+                }
+            }
+        }
+    }
+
+    // Blank nodes that only reference each other would never be written if all nested,
+    // so some of them are written with labels instead
+    fn break_cycles(&mut self) {
+        loop {
+            let mut reached = HashSet::new();
+            let mut pending = vec![];
+            for (subject, predicates) in self.statements.iter() {
+                let nested = match subject {
+                    Identifier::BlankNode(blank_node) => self.inline.contains(blank_node),
+                    _ => false,
+                };
+                if !nested {
+                    self.inlined_objects(predicates, &mut pending);
+                }
+            }
+            while let Some(blank_node) = pending.pop() {
+                if !reached.insert(blank_node.clone()) {
+                    continue;
+                }
+                match self.lists.get(&blank_node) {
+                    Some(items) => {
+                        let mut cell = blank_node.clone();
+                        for item in items {
+                            if let Node::BlankNode(item) = item {
+                                if self.inline.contains(item) {
+                                    pending.push(item.clone());
+                                }
+                            }
+                            if let Some((_, Node::BlankNode(rest))) = self.list_cell(&cell) {
+                                reached.insert(rest.clone());
+                                cell = rest;
+                            }
+                        }
+                    }
+                    None => {
+                        if let Some(predicates) = self.predicates(&blank_node) {
+                            self.inlined_objects(predicates, &mut pending);
+                        }
+                    }
+                }
+            }
+            let unreached = self
+                .statements
+                .keys()
+                .filter_map(|subject| match subject {
+                    Identifier::BlankNode(blank_node) if self.inline.contains(blank_node) => Some(blank_node),
+                    _ => None,
+                })
+                .find(|blank_node| !reached.contains(*blank_node))
+                .cloned();
+            match unreached {
+                Some(blank_node) => {
+                    self.inline.remove(&blank_node);
+                    self.lists.remove(&blank_node);
+                }
+                None => return,
+            }
+        }
+    }
+
+    pub(crate) fn write_iri(&mut self, iri: &IRI) -> String {
+        if let Some(curie) = self.prefixes.compact(iri) {
+            let index = curie.find(':').unwrap();
+            let (prefix, local) = (&curie[..index], &curie[index + 1..]);
+            if is_pn_prefix(prefix) && is_pn_local(local) {
+                self.used_prefixes.insert(prefix.to_owned());
+                return curie;
+            }
+        }
+        format!("<{}>", escape_iri(&iri.value))
+    }
+
+    fn write_literal(&mut self, literal: &Literal) -> String {
+        if let Some(language) = &literal.language {
+            return format!("\"{}\"@{}", escape_string(&literal.value), language);
+        }
+        let datatype = &literal.datatype.value;
+        if datatype == &XSD.iri("string").value {
+            return format!("\"{}\"", escape_string(&literal.value));
+        }
+        if let Some(local) = datatype.strip_prefix(&XSD.iri("").value) {
+            if is_numeric_shorthand(&literal.value, local)
+                || (local == "boolean" && (literal.value == "true" || literal.value == "false"))
+            {
+                return literal.value.clone();
+            }
+        }
+        let datatype = self.write_iri(&literal.datatype);
+        format!("\"{}\"^^{}", escape_string(&literal.value), datatype)
+    }
+
+    fn write_node(&mut self, node: &Node, depth: usize) -> String {
+        match node {
+            Node::IRI(iri) => self.write_iri(iri),
+            Node::Literal(literal) => self.write_literal(literal),
+            Node::BlankNode(blank_node) if self.lists.contains_key(blank_node) => {
+                let items = self.lists[blank_node].clone();
+                let items: Vec<String> = items.iter().map(|item| self.write_node(item, depth + 1)).collect();
+                format!("( {} )", items.join(" "))
+            }
+            Node::BlankNode(blank_node) if self.inline.contains(blank_node) => {
+                match self.statements.get(&Identifier::BlankNode(blank_node.clone())).cloned() {
+                    Some(predicates) => format!(
+                        "[\n{}{}\n{}]",
+                        INDENT.repeat(depth + 1),
+                        self.write_predicates(&predicates, depth + 1),
+                        INDENT.repeat(depth)
+                    ),
+                    None => "[]".to_owned(),
+                }
+            }
+            Node::BlankNode(blank_node) => serialize_identifier(Identifier::BlankNode(blank_node.clone())),
+        }
+    }
+
+    fn write_predicates(&mut self, predicates: &BTreeMap<IRI, Vec<Node>>, depth: usize) -> String {
+        let mut lines = vec![];
+        // rdf:type is written first, as `a`
+        let predicates = predicates
+            .iter()
+            .filter(|(predicate, _)| **predicate == RDF.iri("type"))
+            .chain(predicates.iter().filter(|(predicate, _)| **predicate != RDF.iri("type")));
+        for (predicate, objects) in predicates {
+            let predicate = match predicate {
+                predicate if *predicate == RDF.iri("type") => "a".to_owned(),
+                predicate => self.write_iri(predicate),
+            };
+            let objects: Vec<String> = objects.iter().map(|object| self.write_node(object, depth)).collect();
+            lines.push(format!("{} {}", predicate, objects.join(", ")));
+        }
+        lines.join(&format!(" ;\n{}", INDENT.repeat(depth)))
+    }
+
+    /// Writes every statement, each line prefixed by `indent`.
+    pub(crate) fn write(&mut self, indent: &str) -> String {
+        let mut statements = vec![];
+        for (subject, predicates) in self.statements.clone() {
+            let subject = match subject {
+                Identifier::BlankNode(ref blank_node) if self.inline.contains(blank_node) => continue,
+                Identifier::BlankNode(ref blank_node) if !self.referenced.contains(blank_node) && !self.labeled.contains(blank_node) => {
+                    format!("[\n{}{}\n]", INDENT, self.write_predicates(&predicates, 1))
+                }
+                Identifier::IRI(ref iri) => format!("{} {}", self.write_iri(iri), self.write_predicates(&predicates, 1)),
+                subject => format!("{} {}", serialize_identifier(subject), self.write_predicates(&predicates, 1)),
+            };
+            statements.push(format!("{} .", subject).replace('\n', &format!("\n{}", indent)));
+        }
+        statements
+            .iter()
+            .map(|statement| format!("{}{}\n", indent, statement))
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+}
+
+/// Writes the `@prefix` directives of the `used` prefixes.
+pub(crate) fn write_prefixes(prefixes: &PrefixMap, used: &BTreeSet<String>) -> String {
+    let mut directives = String::new();
+    for prefix in used.iter() {
+        let address = prefixes.get(prefix).unwrap();
+        directives.push_str(&format!("@prefix {}: <{}> .\n", prefix, escape_iri(address)));
+    }
+    directives
+}
+
+/// Serializes the triples of `quads` as Turtle, with prefixes from `prefixes` for the namespaces used.
+pub fn serialize<Q>(quads: Q, prefixes: &PrefixMap) -> String
+where
+    Q: IntoIterator<Item = Quad>,
+{
+    let mut writer = TurtleWriter::new(quads, prefixes);
+    let statements = writer.write("");
+    let directives = write_prefixes(prefixes, &writer.used_prefixes);
+    match (directives.is_empty(), statements.is_empty()) {
+        (false, false) => format!("{}\n{}", directives, statements),
+        _ => directives + &statements,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::namespace::{PrefixMap, FOAF, RDF};
+    use crate::quad::Quad;
+    use crate::term::{BlankNode, Identifier, Literal, Node, IRI};
+    use crate::test_set;
+    use crate::turtle_deserialize::deserialize;
+    use crate::turtle_serialize::serialize;
+
+    #[test]
+    fn test_serialize() {
+        let iddan = IRI::new("http://example.com#iddan");
+        let address = BlankNode::from_value("address");
+        let list = [BlankNode::from_value("l1"), BlankNode::from_value("l2")];
+        let quads = vec![
+            Quad::new(&iddan, RDF.iri("type"), FOAF.iri("Person"), None),
+            Quad::new(&iddan, FOAF.iri("name"), Literal::new("Iddan", None, None), None),
+            Quad::new(&iddan, FOAF.iri("nick"), Literal::new("iddan", None, Some("en".to_owned())), None),
+            Quad::new(&iddan, FOAF.iri("nick"), Literal::new("idan", None, None), None),
+            Quad::new(&iddan, FOAF.iri("age"), Literal::new("20", crate::namespace::XSD.iri("integer"), None), None),
+            Quad::new(&iddan, IRI::new("http://example.com#address"), address.clone(), None),
+            Quad::new(address.clone(), IRI::new("http://example.com#city"), Literal::new("Tel Aviv", None, None), None),
+            Quad::new(&iddan, IRI::new("http://example.com#likes"), list[0].clone(), None),
+            Quad::new(list[0].clone(), RDF.iri("first"), IRI::new("http://example.com#tamir"), None),
+            Quad::new(list[0].clone(), RDF.iri("rest"), list[1].clone(), None),
+            Quad::new(list[1].clone(), RDF.iri("first"), IRI::new("http://example.com#lior"), None),
+            Quad::new(list[1].clone(), RDF.iri("rest"), RDF.iri("nil"), None),
+        ];
+        let mut prefixes = PrefixMap::default();
+        prefixes.insert("ex", "http://example.com#");
+        let turtle = serialize(quads.clone(), &prefixes);
+        assert_eq!(
+            turtle,
+            r#"@prefix ex: <http://example.com#> .
+@prefix foaf: <http://xmlns.com/foaf/0.1/> .
+
+ex:iddan a foaf:Person ;
+    ex:address [
+        ex:city "Tel Aviv"
+    ] ;
+    ex:likes ( ex:tamir ex:lior ) ;
+    foaf:age 20 ;
+    foaf:name "Iddan" ;
+    foaf:nick "idan", "iddan"@en .
+"#
+        );
+        let parsed: Vec<Quad> = deserialize(&turtle, None).map(|quad| quad.unwrap()).collect();
+        assert!(test_set::isomorphic(&parsed, &quads));
+    }
+
+    #[test]
+    fn test_serialize_shared_and_cyclic_blank_nodes() {
+        let (a, b, shared) = (BlankNode::from_value("a"), BlankNode::from_value("b"), BlankNode::from_value("s"));
+        let knows = FOAF.iri("knows");
+        let quads = vec![
+            Quad::new(a.clone(), &knows, b.clone(), None),
+            Quad::new(b.clone(), &knows, a.clone(), None),
+            Quad::new(IRI::new("http://example.com#x"), &knows, shared.clone(), None),
+            Quad::new(IRI::new("http://example.com#y"), &knows, shared.clone(), None),
+            Quad::new(Identifier::BlankNode(shared), &knows, Node::from(BlankNode::from_value("leaf")), None),
+        ];
+        let turtle = serialize(quads.clone(), &PrefixMap::default());
+        assert!(turtle.contains("_:s foaf:knows []"));
+        let parsed: Vec<Quad> = deserialize(&turtle, None).map(|quad| quad.unwrap()).collect();
+        assert!(test_set::isomorphic(&parsed, &quads), "{}", turtle);
+    }
+
+    #[test]
+    fn test_serialize_local_names() {
+        let mut prefixes = PrefixMap::new();
+        prefixes.insert("ex", "http://example.com#");
+        let names = ["a\u{b7}b", "x\u{b2}", "1:2", "caf\u{e9}"];
+        let quads: Vec<Quad> = names
+            .iter()
+            .map(|name| Quad::new(IRI::new(format!("http://example.com#{}", name)), RDF.iri("type"), RDF.iri("Property"), None))
+            .collect();
+        let turtle = serialize(quads.clone(), &prefixes);
+        assert!(turtle.contains("ex:a\u{b7}b a"));
+        assert!(turtle.contains("<http://example.com#x\u{b2}> a"));
+        assert!(turtle.contains("ex:1:2 a"));
+        assert!(turtle.contains("ex:caf\u{e9} a"));
+        let parsed: Vec<Quad> = deserialize(&turtle, None).map(|quad| quad.unwrap()).collect();
+        assert!(test_set::isomorphic(&parsed, &quads), "{}", turtle);
+    }
+
+    // Every W3C evaluation test result survives a round trip
+    #[test]
+    fn test_round_trip_w3c_suite() {
+        let manifest = test_set::Manifest::read("src/w3c/turtle", "http://www.w3.org/2013/TurtleTests/");
+        for entry in manifest.entries.into_iter().filter(|entry| entry.kind == "TestTurtleEval") {
+            let ntriples = std::fs::read_to_string(entry.result.as_ref().unwrap()).unwrap();
+            let quads: Vec<Quad> = crate::nquads_deserialize::deserialize(&ntriples).map(|quad| quad.unwrap()).collect();
+            let turtle = serialize(quads.clone(), &PrefixMap::default());
+            let parsed: Result<Vec<Quad>, _> = deserialize(&turtle, None).collect();
+            let parsed = parsed.unwrap_or_else(|error| panic!("{}: {}\n{}", entry.name, error, turtle));
+            assert!(test_set::isomorphic(&parsed, &quads), "{}\n{}", entry.name, turtle);
+        }
+    }
+}
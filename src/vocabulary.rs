@@ -0,0 +1,226 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use crate::namespace::{OWL, RDF, RDFS};
+use crate::nquads_deserialize;
+use crate::parse_error::ParseError;
+use crate::quad::Quad;
+use crate::term::{Identifier, Node, IRI};
+use crate::turtle_deserialize;
+
+// Generates Rust modules of typed `StaticIRI` constants from RDFS/OWL vocabularies.
+//
+// Typically called from a build script:
+//
+//     minerva::vocabulary::generate_file("vocab/foaf.nq", "http://xmlns.com/foaf/0.1/",
+//         Path::new(&env::var("OUT_DIR").unwrap()).join("foaf.rs")).unwrap();
+//
+// and included with `pub mod foaf { include!(concat!(env!("OUT_DIR"), "/foaf.rs")); }`.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    NQuads,
+    Turtle,
+}
+
+impl Format {
+    pub fn from_path(path: &Path) -> Result<Format, String> {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("nq") | Some("nt") => Ok(Format::NQuads),
+            Some("ttl") => Ok(Format::Turtle),
+            _ => Err(format!("Unknown vocabulary format for {}", path.display())),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Term {
+    is_term: bool,
+    comment: Option<String>,
+}
+
+fn term_types() -> Vec<IRI> {
+    vec![
+        RDFS.iri("Class"),
+        RDFS.iri("Datatype"),
+        RDF.iri("Property"),
+        OWL.iri("Class"),
+        OWL.iri("ObjectProperty"),
+        OWL.iri("DatatypeProperty"),
+        OWL.iri("AnnotationProperty"),
+        OWL.iri("FunctionalProperty"),
+        OWL.iri("InverseFunctionalProperty"),
+        OWL.iri("TransitiveProperty"),
+        OWL.iri("SymmetricProperty"),
+    ]
+}
+
+const STRICT_KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "dyn", "else", "enum", "extern", "false",
+    "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref",
+    "return", "static", "struct", "trait", "true", "type", "unsafe", "use", "where", "while",
+    "abstract", "become", "box", "do", "final", "macro", "override", "priv", "try", "typeof",
+    "unsized", "virtual", "yield",
+];
+
+fn identifier(local_name: &str) -> String {
+    let mut name: String = local_name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '_' })
+        .collect();
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        name.insert(0, '_');
+    }
+    match name.as_str() {
+        "self" | "super" | "crate" | "Self" | "_" => name + "_",
+        _ if STRICT_KEYWORDS.contains(&name.as_str()) => format!("r#{}", name),
+        _ => name,
+    }
+}
+
+/// Emits a Rust module body with a constant for every class and property of
+/// `namespace` described in `quads`. Fails when two local names map to the same constant.
+pub fn generate<Q>(quads: Q, namespace: &str) -> Result<String, String>
+where
+    Q: IntoIterator<Item = Quad>,
+{
+    let term_types = term_types();
+    let mut terms: BTreeMap<String, Term> = BTreeMap::new();
+    for quad in quads {
+        let local_name = match &quad.subject {
+            Identifier::IRI(iri) if iri.value.starts_with(namespace) && iri.value.len() > namespace.len() => {
+                iri.value[namespace.len()..].to_owned()
+            }
+            _ => continue,
+        };
+        let term = terms.entry(local_name).or_default();
+        match quad.object {
+            Node::IRI(ref object) if quad.predicate == RDF.iri("type") && term_types.contains(object) => {
+                term.is_term = true;
+            }
+            Node::Literal(ref literal) if quad.predicate == RDFS.iri("comment") => {
+                let preferred = match &literal.language {
+                    None => true,
+                    Some(language) => language.starts_with("en"),
+                };
+                if term.comment.is_none() || preferred {
+                    term.comment = Some(literal.value.trim().to_owned());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut identifiers: BTreeMap<String, &str> = BTreeMap::new();
+    for local_name in terms.iter().filter(|(_, term)| term.is_term).map(|(local_name, _)| local_name) {
+        if let Some(other) = identifiers.insert(identifier(local_name), local_name) {
+            return Err(format!("{} and {} both generate the constant {}", other, local_name, identifier(local_name)));
+        }
+    }
+
+    let mut module = String::from("use minerva::term::StaticIRI;\n");
+    for (local_name, term) in terms.iter().filter(|(_, term)| term.is_term) {
+        module.push('\n');
+        if let Some(comment) = &term.comment {
+            for line in comment.lines() {
+                module.push_str(&format!("/// {}\n", line.trim()).replace("/// \n", "///\n"));
+            }
+        }
+        module.push_str("#[allow(non_upper_case_globals)]\n");
+        module.push_str(&format!(
+            "pub const {}: StaticIRI = StaticIRI::new({:?});\n",
+            identifier(local_name),
+            format!("{}{}", namespace, local_name)
+        ));
+    }
+    Ok(module)
+}
+
+pub fn generate_from_str(source: &str, format: Format, namespace: &str) -> Result<String, String> {
+    match format {
+        Format::NQuads => {
+            let quads: Result<Vec<Quad>, ParseError> = nquads_deserialize::deserialize(source).collect();
+            generate(quads?, namespace)
+        }
+        Format::Turtle => {
+            let quads: Result<Vec<Quad>, ParseError> = turtle_deserialize::deserialize(source, None).collect();
+            generate(quads?, namespace)
+        }
+    }
+}
+
+/// Build script helper: reads the vocabulary at `input` and writes the generated module to `output`.
+pub fn generate_file<I, O>(input: I, namespace: &str, output: O) -> Result<(), String>
+where
+    I: AsRef<Path>,
+    O: AsRef<Path>,
+{
+    let format = Format::from_path(input.as_ref())?;
+    let source = fs::read_to_string(input.as_ref()).map_err(|error| error.to_string())?;
+    let module = generate_from_str(&source, format, namespace)?;
+    fs::write(output.as_ref(), module).map_err(|error| error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::vocabulary::{generate_from_str, Format};
+    use std::process::Command;
+
+    // Compiles a generated module against a stand-in for `minerva::term::StaticIRI`
+    fn compiles(name: &str, module: &str) -> bool {
+        let directory = std::env::temp_dir().join(format!("minerva-vocabulary-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let source = format!(
+            "extern crate self as minerva;\n\
+             pub mod term {{ pub struct StaticIRI {{ pub value: &'static str }} impl StaticIRI {{ pub const fn new(value: &'static str) -> StaticIRI {{ StaticIRI {{ value }} }} }} }}\n\
+             pub mod vocabulary {{\n{}\n}}\n",
+            module
+        );
+        std::fs::write(directory.join("lib.rs"), source).unwrap();
+        let status = Command::new(std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_owned()))
+            .args(["--edition", "2018", "--crate-type", "lib", "--emit", "metadata", "--crate-name", "vocabulary", "--out-dir"])
+            .arg(&directory)
+            .arg(directory.join("lib.rs"))
+            .status()
+            .unwrap();
+        std::fs::remove_dir_all(&directory).unwrap();
+        status.success()
+    }
+
+    #[test]
+    fn test_generate() {
+        let vocabulary = r#"
+<http://example.com/v#Person> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://www.w3.org/2000/01/rdf-schema#Class> .
+<http://example.com/v#Person> <http://www.w3.org/2000/01/rdf-schema#comment> "A person." .
+<http://example.com/v#type> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://www.w3.org/1999/02/22-rdf-syntax-ns#Property> .
+<http://example.com/v#iddan> <http://example.com/v#type> <http://example.com/v#Person> .
+"#;
+        let module = generate_from_str(vocabulary, Format::NQuads, "http://example.com/v#").unwrap();
+        assert!(module.contains("/// A person.\n#[allow(non_upper_case_globals)]\npub const Person: StaticIRI = StaticIRI::new(\"http://example.com/v#Person\");\n"));
+        assert!(module.contains("pub const r#type: StaticIRI"));
+        assert!(!module.contains("iddan"));
+        assert!(compiles("nquads", &module));
+    }
+
+    #[test]
+    fn test_generate_collision() {
+        let vocabulary = r#"
+<http://example.com/v#foo-bar> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://www.w3.org/1999/02/22-rdf-syntax-ns#Property> .
+<http://example.com/v#foo_bar> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://www.w3.org/1999/02/22-rdf-syntax-ns#Property> .
+"#;
+        let error = generate_from_str(vocabulary, Format::NQuads, "http://example.com/v#").unwrap_err();
+        assert_eq!(error, "foo-bar and foo_bar both generate the constant foo_bar");
+    }
+
+    #[test]
+    fn test_generate_turtle() {
+        let vocabulary = r#"
+@prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .
+@prefix v: <http://example.com/v#> .
+v:Person a rdfs:Class ; rdfs:comment "A person."@en .
+"#;
+        let module = generate_from_str(vocabulary, Format::Turtle, "http://example.com/v#").unwrap();
+        assert!(module.contains("/// A person.\n#[allow(non_upper_case_globals)]\npub const Person: StaticIRI"));
+        assert!(compiles("turtle", &module));
+    }
+}
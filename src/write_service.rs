@@ -1,19 +1,21 @@
 use std::sync::Mutex;
 use crate::dataset::Dataset;
-use crate::nquads_deserialize;
+use crate::namespace::PrefixMap;
+use crate::quad::Quad;
+use crate::rdf_patch::Patch;
 
-pub fn write(nquads: String, dataset_lock: &Mutex<Dataset>) -> Result<(), String> {
-    let quads = nquads_deserialize::deserialize(&nquads);
+pub fn insert(quad: Quad, dataset_lock: &Mutex<Dataset>) {
+    dataset_lock.lock().unwrap().insert(quad);
+}
+
+// The lock is held while the quads are inserted, so readers see all of them or none of them
+pub fn insert_all<I: IntoIterator<Item = Quad>>(quads: I, dataset_lock: &Mutex<Dataset>) {
+    dataset_lock.lock().unwrap().extend(quads);
+}
+
+// Both locks are held while the patch is applied, so readers see all of it or none of it
+pub fn apply(patch: &Patch, dataset_lock: &Mutex<Dataset>, prefixes_lock: &Mutex<PrefixMap>) {
+    let mut prefixes = prefixes_lock.lock().unwrap();
     let mut dataset = dataset_lock.lock().unwrap();
-    for result in quads {
-        match result {
-            Err(error) => {
-                return Err(error);
-            },
-            _ => {
-                dataset.insert(result.unwrap())
-            }
-        }
-    }
-    Ok(())
-}
\ No newline at end of file
+    patch.apply(&mut dataset, &mut prefixes);
+}